@@ -0,0 +1,412 @@
+//! Opt-in cache of pipeline results.
+//!
+//! When `QSV_CACHE_DIR` is set, the collected result of a pipeline's chainables is stored
+//! there as parquet, keyed by the files it read (path, size and modification time) and the
+//! commands that produced it. Later runs starting with the same commands scan the cached
+//! parquet instead of parsing the input again.
+use crate::controllers::command::{command_line, parse_size, sql_tables, Command};
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use chrono::{DateTime, Local};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory of the result cache; caching is off when it is not set
+pub const CACHE_DIR_ENV: &str = "QSV_CACHE_DIR";
+/// Total size of cached results, e.g. `2GB`, before the least recently used are evicted
+pub const CACHE_MAX_SIZE_ENV: &str = "QSV_CACHE_MAX_SIZE";
+pub const DEFAULT_CACHE_MAX_SIZE: u64 = 4 * 1024 * 1024 * 1024;
+
+/// A file read by a cached pipeline, as it was when the result was stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CachedInput {
+    pub path: String,
+    pub size: u64,
+    /// Modification time in nanoseconds since the Unix epoch
+    pub modified: u64,
+}
+
+/// Metadata stored next to each cached result
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String,
+    /// The commands that produced the result, as a command line
+    pub pipeline: String,
+    pub inputs: Vec<CachedInput>,
+    pub created: String,
+    pub rows: usize,
+    pub columns: usize,
+    // Everything the key is derived from, to tell hash collisions apart
+    signature: String,
+}
+
+/// A cached result with its current state
+#[derive(Debug, Clone)]
+pub struct CacheListing {
+    pub entry: CacheEntry,
+    /// Size of the parquet file in bytes
+    pub size: u64,
+    pub last_used: SystemTime,
+    /// Whether an input file has changed or disappeared since the result was stored
+    pub stale: bool,
+}
+
+/// Directory of cached results with a total size limit
+#[derive(Debug, Clone)]
+pub struct ResultCache {
+    dir: PathBuf,
+    max_size: u64,
+}
+
+impl ResultCache {
+    pub fn new(dir: impl Into<PathBuf>, max_size: u64) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size,
+        }
+    }
+
+    /// The cache configured by `QSV_CACHE_DIR` and `QSV_CACHE_MAX_SIZE`, or `None` when
+    /// caching is off
+    pub fn from_env() -> Result<Option<Self>, QsvError> {
+        let Some(dir) = env::var_os(CACHE_DIR_ENV).filter(|dir| !dir.is_empty()) else {
+            return Ok(None);
+        };
+        let max_size = match env::var(CACHE_MAX_SIZE_ENV) {
+            Ok(size) => parse_size(&size)
+                .map_err(|e| QsvError::InvalidArgument(format!("{CACHE_MAX_SIZE_ENV}: {e}")))?
+                as u64,
+            Err(_) => DEFAULT_CACHE_MAX_SIZE,
+        };
+        Ok(Some(Self::new(dir, max_size)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size
+    }
+
+    /// The longest cached prefix of `commands`: how many commands it covers and its result
+    pub fn lookup(&self, commands: &[Command]) -> Result<Option<(usize, LazyFrame)>, QsvError> {
+        for (len, (signature, _)) in prefix_signatures(commands).iter().enumerate().rev() {
+            let key = cache_key(signature);
+            let Some(entry) = self.read_entry(&key) else {
+                continue;
+            };
+            let path = self.result_path(&key);
+            if entry.signature != *signature || !path.exists() {
+                continue;
+            }
+            // The modification time of the result records its last use, for eviction
+            if let Ok(file) = File::options().write(true).open(&path) {
+                let _ = file.set_modified(SystemTime::now());
+            }
+            LogController::info(&format!(
+                "Using cached result {key} for: {}",
+                entry.pipeline
+            ));
+            let df = LazyFrame::scan_parquet(&path, ScanArgsParquet::default()).map_err(|e| {
+                QsvError::Io(format!("reading cached result {}: {e}", path.display()))
+            })?;
+            return Ok(Some((len + 1, df)));
+        }
+        Ok(None)
+    }
+
+    /// Store the result of `commands`, then evict the least recently used results beyond the
+    /// size limit. Results larger than the limit on their own are not kept.
+    pub fn store(&self, commands: &[Command], df: &mut DataFrame) -> Result<(), QsvError> {
+        let Some((signature, inputs)) = prefix_signatures(commands)
+            .into_iter()
+            .nth(commands.len().saturating_sub(1))
+        else {
+            return Ok(());
+        };
+        fs::create_dir_all(&self.dir).map_err(|e| {
+            QsvError::Io(format!(
+                "creating cache directory {}: {e}",
+                self.dir.display()
+            ))
+        })?;
+        let key = cache_key(&signature);
+        let path = self.result_path(&key);
+
+        // Write under a temporary name so concurrent runs never read a partial file
+        let tmp_path = path.with_extension("parquet.tmp");
+        let file = File::create(&tmp_path)
+            .map_err(|e| QsvError::Io(format!("creating {}: {e}", tmp_path.display())))?;
+        ParquetWriter::new(file)
+            .with_compression(ParquetCompression::Snappy)
+            .finish(df)
+            .map_err(|e| QsvError::Io(format!("writing {}: {e}", tmp_path.display())))?;
+        let size = file_size(&tmp_path);
+        if size > self.max_size {
+            let _ = fs::remove_file(&tmp_path);
+            LogController::warn(&format!(
+                "Result of '{}' ({}) is larger than the cache limit ({}); not cached",
+                pipeline_line(commands),
+                format_size(size),
+                format_size(self.max_size)
+            ));
+            return Ok(());
+        }
+        fs::rename(&tmp_path, &path)
+            .map_err(|e| QsvError::Io(format!("moving {}: {e}", tmp_path.display())))?;
+
+        let entry = CacheEntry {
+            key: key.clone(),
+            pipeline: pipeline_line(commands),
+            inputs,
+            created: Local::now().to_rfc3339(),
+            rows: df.height(),
+            columns: df.width(),
+            signature,
+        };
+        let json = serde_json::to_string_pretty(&entry)
+            .map_err(|e| QsvError::Io(format!("serializing cache entry: {e}")))?;
+        let entry_path = self.entry_path(&key);
+        fs::write(&entry_path, json)
+            .map_err(|e| QsvError::Io(format!("writing {}: {e}", entry_path.display())))?;
+        LogController::info(&format!(
+            "Cached result {key} ({}) for: {}",
+            format_size(size),
+            entry.pipeline
+        ));
+
+        self.evict(Some(&key))?;
+        Ok(())
+    }
+
+    /// Every cached result, most recently used first
+    pub fn list(&self) -> Result<Vec<CacheListing>, QsvError> {
+        let mut listings = Vec::new();
+        for key in self.keys()? {
+            let path = self.result_path(&key);
+            let (Some(entry), Ok(metadata)) = (self.read_entry(&key), fs::metadata(&path)) else {
+                continue;
+            };
+            let stale = entry
+                .inputs
+                .iter()
+                .any(|input| fingerprint(Path::new(&input.path)).as_ref() != Some(input));
+            listings.push(CacheListing {
+                size: metadata.len(),
+                last_used: metadata.modified().unwrap_or(UNIX_EPOCH),
+                stale,
+                entry,
+            });
+        }
+        listings.sort_by_key(|listing| std::cmp::Reverse(listing.last_used));
+        Ok(listings)
+    }
+
+    /// Remove one cached result; returns whether it existed
+    pub fn remove(&self, key: &str) -> Result<bool, QsvError> {
+        let mut removed = false;
+        for path in [self.result_path(key), self.entry_path(key)] {
+            if path.exists() {
+                fs::remove_file(&path)
+                    .map_err(|e| QsvError::Io(format!("removing {}: {e}", path.display())))?;
+                removed = true;
+            }
+        }
+        Ok(removed)
+    }
+
+    /// Remove every cached result; returns how many there were
+    pub fn clear(&self) -> Result<usize, QsvError> {
+        let keys = self.keys()?;
+        for key in &keys {
+            self.remove(key)?;
+        }
+        Ok(keys.len())
+    }
+
+    /// Remove results whose inputs have changed, incomplete entries, and the least recently
+    /// used results beyond the size limit; returns how many were removed
+    pub fn prune(&self) -> Result<usize, QsvError> {
+        let listed: Vec<String> = self
+            .list()?
+            .into_iter()
+            .filter(|listing| !listing.stale)
+            .map(|listing| listing.entry.key)
+            .collect();
+        let mut removed = 0;
+        for key in self.keys()? {
+            if !listed.contains(&key) && self.remove(&key)? {
+                removed += 1;
+            }
+        }
+        Ok(removed + self.evict(None)?)
+    }
+
+    // Drop the least recently used results until the total fits the limit
+    fn evict(&self, keep: Option<&str>) -> Result<usize, QsvError> {
+        let listings = self.list()?;
+        let mut total: u64 = listings.iter().map(|listing| listing.size).sum();
+        let mut removed = 0;
+        for listing in listings.iter().rev() {
+            if total <= self.max_size {
+                break;
+            }
+            if keep == Some(listing.entry.key.as_str()) {
+                continue;
+            }
+            self.remove(&listing.entry.key)?;
+            LogController::info(&format!(
+                "Evicted cached result {} for: {}",
+                listing.entry.key, listing.entry.pipeline
+            ));
+            total -= listing.size;
+            removed += 1;
+        }
+        Ok(removed)
+    }
+
+    // Keys of all files in the cache directory, including incomplete entries
+    fn keys(&self) -> Result<Vec<String>, QsvError> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+        let entries = fs::read_dir(&self.dir).map_err(|e| {
+            QsvError::Io(format!(
+                "reading cache directory {}: {e}",
+                self.dir.display()
+            ))
+        })?;
+        let mut keys: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().into_string().ok()?;
+                let (key, ext) = name.split_once('.')?;
+                (is_cache_key(key) && matches!(ext, "parquet" | "json" | "parquet.tmp"))
+                    .then(|| key.to_string())
+            })
+            .collect();
+        keys.sort();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    fn read_entry(&self, key: &str) -> Option<CacheEntry> {
+        let json = fs::read_to_string(self.entry_path(key)).ok()?;
+        serde_json::from_str(&json).ok()
+    }
+
+    fn result_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.parquet"))
+    }
+
+    fn entry_path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{key}.json"))
+    }
+}
+
+/// A byte count with a binary unit, e.g. `1.5MB`
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes}B")
+    } else {
+        format!("{size:.1}{}", UNITS[unit])
+    }
+}
+
+/// Local time of a file timestamp, for listings
+pub fn format_time(time: SystemTime) -> String {
+    DateTime::<Local>::from(time)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+// Signature and inputs of each prefix of `commands`, stopping at the first command whose
+// input files cannot be read (loading them will report the error)
+fn prefix_signatures(commands: &[Command]) -> Vec<(String, Vec<CachedInput>)> {
+    let mut signatures = Vec::with_capacity(commands.len());
+    let mut signature = String::new();
+    let mut inputs = Vec::new();
+    for cmd in commands {
+        signature.push_str(&command_line(cmd));
+        signature.push('\n');
+        for path in input_paths(cmd) {
+            let Some(input) = fingerprint(Path::new(&path)) else {
+                return signatures;
+            };
+            signature.push_str(&format!(
+                "  {}\t{}\t{}\n",
+                input.path, input.size, input.modified
+            ));
+            inputs.push(input);
+        }
+        signatures.push((signature.clone(), inputs.clone()));
+    }
+    signatures
+}
+
+// Files a command reads besides its input frame
+fn input_paths(cmd: &Command) -> Vec<String> {
+    match cmd.name.as_str() {
+        "load" | "join" => cmd.args.clone(),
+        "sql" => sql_tables(cmd)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(_, path)| path)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn fingerprint(path: &Path) -> Option<CachedInput> {
+    let path = fs::canonicalize(path).ok()?;
+    let metadata = fs::metadata(&path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()?
+        .as_nanos() as u64;
+    Some(CachedInput {
+        path: path.display().to_string(),
+        size: metadata.len(),
+        modified,
+    })
+}
+
+fn pipeline_line(commands: &[Command]) -> String {
+    commands
+        .iter()
+        .map(command_line)
+        .collect::<Vec<_>>()
+        .join(" - ")
+}
+
+// 64-bit FNV-1a, which unlike `DefaultHasher` is stable across Rust releases
+fn cache_key(signature: &str) -> String {
+    let hash = signature
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{hash:016x}")
+}
+
+fn is_cache_key(name: &str) -> bool {
+    name.len() == 16 && name.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
@@ -1,3 +1,6 @@
+use crate::error::QsvError;
+use once_cell::sync::Lazy;
+use regex::Regex;
 use std::collections::HashMap;
 use std::collections::HashSet;
 #[derive(Debug, Clone)]
@@ -5,20 +8,34 @@ pub struct Command {
     pub name: String,
     pub args: Vec<String>,
     pub options: HashMap<String, Option<String>>,
+    /// Every value of options that may be given more than once (see `REPEATABLE_OPTIONS`)
+    pub repeated_options: HashMap<String, Vec<String>>,
 }
+// Options whose values accumulate instead of replacing each other
+const REPEATABLE_OPTIONS: &[&str] = &["param"];
 impl Command {
     pub fn new(name: String) -> Self {
         Command {
             name,
             args: Vec::new(),
             options: HashMap::new(),
+            repeated_options: HashMap::new(),
         }
     }
+    fn insert_option_value(&mut self, key: String, value: String) {
+        if REPEATABLE_OPTIONS.contains(&key.as_str()) {
+            self.repeated_options
+                .entry(key.clone())
+                .or_default()
+                .push(value.clone());
+        }
+        self.options.insert(key, Some(value));
+    }
 }
 // Define valid options for each command
 fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
     match command_name {
-        "load" => {
+        "load" | "repl" => {
             let mut opts = HashSet::new();
             opts.insert("low_memory");
             opts.insert("no_headers");
@@ -34,6 +51,7 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             .iter()
             .cloned()
             .collect(),
+        "filter" => HashSet::new(), // filter has no options
         "sed" => ["ignore_case", "ignore-case", "ignorecase", "column"]
             .iter()
             .cloned()
@@ -42,11 +60,27 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             .iter()
             .cloned()
             .collect(),
+        "join" => [
+            "on",
+            "left_on",
+            "left-on",
+            "right_on",
+            "right-on",
+            "how",
+            "suffix",
+            "separator",
+            "s",
+        ]
+        .iter()
+        .cloned()
+        .collect(),
         "head" => ["number"].iter().cloned().collect(),
         "tail" => ["number"].iter().cloned().collect(),
         "sort" => ["desc"].iter().cloned().collect(),
         "count" => HashSet::new(), // count has no options
-        "uniq" => HashSet::new(),  // uniq has no options
+        "groupby" => ["agg"].iter().cloned().collect(),
+        "sql" => ["table"].iter().cloned().collect(),
+        "uniq" => HashSet::new(), // uniq has no options
         "changetz" => [
             "from_tz",
             "from-tz",
@@ -57,20 +91,80 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             "output_format",
             "output-format",
             "ambiguous",
+            "epoch",
+            "strict",
         ]
         .iter()
         .cloned()
         .collect(),
         "renamecol" => HashSet::new(), // renamecol has no options
         "convert" => ["from", "to"].iter().cloned().collect(),
-        "timeline" => ["interval", "sum", "avg", "min", "max", "std"]
+        "timeline" => [
+            "interval",
+            "sum",
+            "avg",
+            "min",
+            "max",
+            "std",
+            "agg",
+            "by",
+            "fill",
+            "start",
+            "end",
+            "week_start",
+            "week-start",
+            "tz",
+            "epoch",
+            "input_format",
+            "input-format",
+            "strict",
+        ]
+        .iter()
+        .cloned()
+        .collect(),
+        "timeslice" => [
+            "start",
+            "end",
+            "epoch",
+            "input_format",
+            "input-format",
+            "strict",
+        ]
+        .iter()
+        .cloned()
+        .collect(),
+        "pivot" => [
+            "rows",
+            "cols",
+            "values",
+            "agg",
+            "fill",
+            "row_totals",
+            "col_totals",
+        ]
+        .iter()
+        .cloned()
+        .collect(),
+        "melt" => ["id", "value_cols", "variable_name", "value_name"]
             .iter()
             .cloned()
             .collect(),
-        "timeslice" => ["start", "end"].iter().cloned().collect(),
-        "pivot" => ["rows", "cols", "values", "agg"].iter().cloned().collect(),
-        "timeround" => ["unit", "output"].iter().cloned().collect(),
-        "partition" => HashSet::new(), // partition has no options
+        "timeround" => [
+            "unit",
+            "output",
+            "epoch",
+            "input_format",
+            "input-format",
+            "strict",
+        ]
+        .iter()
+        .cloned()
+        .collect(),
+        "todatetime" => ["tz", "epoch", "input_format", "input-format", "strict"]
+            .iter()
+            .cloned()
+            .collect(),
+        "partition" => ["compression"].iter().cloned().collect(),
         "show" => {
             let mut opts = HashSet::new();
             opts.insert("batch_size");
@@ -78,6 +172,10 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             opts
         }
         "showtable" => HashSet::new(), // showtable has no options
+        "chart" => ["type", "x", "by", "width", "height", "bins", "log", "ascii"]
+            .iter()
+            .cloned()
+            .collect(),
         "headers" => {
             let mut opts = HashSet::new();
             opts.insert("plain");
@@ -94,6 +192,7 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             opts.insert("s");
             opts.insert("batch_size");
             opts.insert("batch-size");
+            opts.insert("format");
             opts
         }
         "dumpcache" => {
@@ -102,24 +201,30 @@ fn get_valid_options(command_name: &str) -> HashSet<&'static str> {
             opts.insert("o");
             opts
         }
+        "toquilt" => ["title"].iter().cloned().collect(),
+        "cache" => ["max_size", "max-size"].iter().cloned().collect(),
         "quilt" => {
             let mut opts = HashSet::new();
             opts.insert("output");
             opts.insert("o");
+            opts.insert("param");
+            opts.insert("jobs");
+            opts.insert("validate");
+            opts.insert("schema");
             opts
         }
         _ => HashSet::new(), // unknown command, no validation
     }
 }
 // Validate that all options for a command are valid
-pub fn validate_command_options(cmd: &Command) -> Result<(), String> {
+pub fn validate_command_options(cmd: &Command) -> Result<(), QsvError> {
     let valid_options = get_valid_options(&cmd.name);
     for option_key in cmd.options.keys() {
         if !valid_options.contains(option_key.as_str()) {
-            return Err(format!(
-                "Error: Unknown option '--{}' for command '{}'. Run 'qsv {} --help' for available options.",
+            return Err(QsvError::InvalidArgument(format!(
+                "Unknown option '--{}' for command '{}'. Run 'qsv {} --help' for available options.",
                 option_key, cmd.name, cmd.name
-            ));
+            )));
         }
     }
     Ok(())
@@ -165,6 +270,8 @@ pub fn parse_commands(args: &[String]) -> Vec<Command> {
                         | "output-format"
                         | "output_format"
                         | "ambiguous"
+                        | "epoch"
+                        | "tz"
                         | "output"
                         | "separator"
                         | "s"
@@ -181,6 +288,22 @@ pub fn parse_commands(args: &[String]) -> Vec<Command> {
                         | "cols"
                         | "values"
                         | "agg"
+                        | "by"
+                        | "type"
+                        | "x"
+                        | "width"
+                        | "height"
+                        | "bins"
+                        | "week-start"
+                        | "week_start"
+                        | "fill"
+                        | "id"
+                        | "value-cols"
+                        | "value_cols"
+                        | "variable-name"
+                        | "variable_name"
+                        | "value-name"
+                        | "value_name"
                         | "from"
                         | "to"
                         | "column"
@@ -189,13 +312,26 @@ pub fn parse_commands(args: &[String]) -> Vec<Command> {
                         | "batch_size"
                         | "chunk-size"
                         | "chunk_size"
+                        | "format"
+                        | "on"
+                        | "left-on"
+                        | "left_on"
+                        | "right-on"
+                        | "right_on"
+                        | "how"
+                        | "suffix"
+                        | "table"
+                        | "param"
+                        | "jobs"
+                        | "title"
+                        | "compression"
+                        | "max-size"
+                        | "max_size"
                 );
                 if needs_value && i + 1 < args.len() && !args[i + 1].starts_with('-') {
                     // --option value format
                     let value = args[i + 1].clone();
-                    current_command
-                        .options
-                        .insert(option_str.replace('-', "_"), Some(value));
+                    current_command.insert_option_value(option_str.replace('-', "_"), value);
                     i += 2; // Consumed option and its value
                 } else {
                     // It's a flag option
@@ -280,7 +416,7 @@ fn parse_option(cmd: &mut Command, option_str: &str) {
             "o" => "output".to_string(),
             _ => key.replace('-', "_"),
         };
-        cmd.options.insert(final_key, Some(value.to_string()));
+        cmd.insert_option_value(final_key, value.to_string());
     } else {
         // This is a flag option (e.g., -i, --ignore_case) or a short option passed without '=' that wasn't -s or -n
         // Or it's a key that parse_commands decided should be treated as a flag (e.g. -s at end of args)
@@ -301,23 +437,145 @@ fn parse_option(cmd: &mut Command, option_str: &str) {
         cmd.options.insert(final_key, None);
     }
 }
+/// Commands that end a pipeline; without one, the command line prints a table
+pub const FINALIZER_COMMANDS: &[&str] = &[
+    "show",
+    "showtable",
+    "chart",
+    "headers",
+    "stats",
+    "showquery",
+    "dump",
+    "dumpcache",
+    "partition",
+];
+/// The `name=file` pairs of a `sql --table name=file,...` option
+pub fn sql_tables(cmd: &Command) -> Result<Vec<(String, String)>, QsvError> {
+    let Some(specs) = cmd.options.get("table").and_then(|v| v.as_deref()) else {
+        return Ok(Vec::new());
+    };
+    specs
+        .split(',')
+        .map(str::trim)
+        .filter(|s| !s.is_empty())
+        .map(|spec| match spec.split_once('=') {
+            Some((name, path)) => Ok((name.trim().to_string(), path.trim().to_string())),
+            None => Err(QsvError::InvalidArgument(format!(
+                "Invalid table '{spec}' for 'sql': expected <name>=<file>"
+            ))),
+        })
+        .collect()
+}
+/// Split a typed line into arguments the way a POSIX shell would, so it can be passed to
+/// `parse_commands`: whitespace separates arguments, single quotes are literal, and
+/// backslashes escape the next character outside single quotes.
+pub fn split_command_line(line: &str) -> Result<Vec<String>, QsvError> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_arg = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_arg {
+                    args.push(std::mem::take(&mut current));
+                    in_arg = false;
+                }
+            }
+            '\'' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated_quote(line)),
+                    }
+                }
+            }
+            '"' => {
+                in_arg = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some(c @ ('"' | '\\' | '$' | '`')) => current.push(c),
+                            Some(c) => {
+                                current.push('\\');
+                                current.push(c);
+                            }
+                            None => return Err(unterminated_quote(line)),
+                        },
+                        Some(c) => current.push(c),
+                        None => return Err(unterminated_quote(line)),
+                    }
+                }
+            }
+            '\\' => {
+                in_arg = true;
+                if let Some(c) = chars.next() {
+                    current.push(c);
+                }
+            }
+            c => {
+                in_arg = true;
+                current.push(c);
+            }
+        }
+    }
+    if in_arg {
+        args.push(current);
+    }
+    Ok(args)
+}
+fn unterminated_quote(line: &str) -> QsvError {
+    QsvError::InvalidArgument(format!("Unterminated quote in: {line}"))
+}
+/// The command as it would be typed, quoting arguments where needed
+pub fn command_line(cmd: &Command) -> String {
+    let mut parts = vec![cmd.name.clone()];
+    parts.extend(cmd.args.iter().map(|a| quote(a)));
+    let mut options: Vec<_> = cmd.options.iter().collect();
+    options.sort();
+    for (key, value) in options {
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value {
+            Some(v) => parts.push(format!("{flag} {}", quote(v))),
+            None => parts.push(flag),
+        }
+    }
+    parts.join(" ")
+}
+fn quote(s: &str) -> String {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_alphanumeric() || "-_./:,=@%+".contains(c))
+    {
+        s.to_string()
+    } else {
+        format!("'{}'", s.replace('\'', "'\\''"))
+    }
+}
 // Help functions for CLI
 pub fn print_help() {
     println!("Quilter-CSV: A fast, flexible, and memory-efficient command-line tool written in Rust for processing large CSV files.\n");
     println!("Usage: qsv load <file.csv> - <chainable> <args> - <finalizer> <args>\n");
     println!("Initializers:");
-    println!("  load         Load CSV file(s)");
+    println!("  load         Load CSV, JSON Lines or Parquet file(s)");
     println!();
     println!("Chainables:");
     println!("  select       Select columns");
     println!("  isin         Filter rows by values");
     println!("  contains     Filter rows by pattern");
+    println!("  filter       Filter rows by expression");
     println!("  sed          Replace values by pattern");
     println!("  grep         Filter rows by regex (any column)");
+    println!("  join         Join with another file");
     println!("  head         Show first N rows");
     println!("  tail         Show last N rows");
     println!("  sort         Sort rows");
     println!("  count        Count duplicate rows");
+    println!("  groupby      Group by key columns with named aggregations");
+    println!("  sql          Run a SQL query against the current data");
     println!("  uniq         Remove duplicate rows");
     println!("  changetz     Change timezone");
     println!("  renamecol    Rename column");
@@ -325,21 +583,33 @@ pub fn print_help() {
     println!("  timeline     Aggregate data by time intervals");
     println!("  timeslice    Filter data by time range");
     println!("  pivot        Create pivot tables with cross-tabulation");
+    println!("  melt         Unpivot wide columns into long records");
     println!("  timeround    Round datetime to specified time unit");
+    println!("  todatetime   Turn a column into datetimes with a time zone");
     println!();
     println!("Finalizers:");
     println!("  show         Print as CSV");
     println!("  showtable    Print as table");
+    println!("  chart        Draw a bar chart, histogram or sparkline in the terminal");
     println!("  headers      Show column names");
     println!("  stats        Show statistics");
     println!("  showquery    Show query plan");
-    println!("  dump         Save as CSV");
+    println!("  dump         Save as CSV, TSV, JSON, NDJSON, Parquet, Arrow IPC, Markdown or HTML");
     println!("  dumpcache    Save as parquet cache file");
     println!("  partition    Split data into separate files by column values");
     println!();
     println!();
     println!("Quilters:");
     println!("  quilt        Execute a quilt (data processing pipeline from YAML)");
+    println!(
+        "  toquilt      Save the pipeline before it as a quilt YAML file instead of running it"
+    );
+    println!();
+    println!("Interactive:");
+    println!("  repl         Load file(s) once, then run commands line by line");
+    println!();
+    println!("Cache:");
+    println!("  cache        List, remove, clear or prune cached results (see QSV_CACHE_DIR)");
     println!();
     println!("Examples:");
     println!("  qsv load data.csv - select col1,col2 - head 10 - show");
@@ -350,6 +620,15 @@ pub fn print_help() {
     println!("  qsv load data.csv - changetz datetime --from-tz UTC --to-tz Asia/Tokyo - show");
     println!("  qsv load data.csv - partition category ./partitions/");
     println!();
+    println!("Exit codes:");
+    println!("  0  Success");
+    println!("  1  Invalid command, option or argument");
+    println!("  2  Column not found");
+    println!("  3  Parse error (expression, datetime, file content)");
+    println!("  4  File not found or I/O error");
+    println!("  5  Query execution error");
+    println!("  6  Invalid quilt configuration");
+    println!();
     println!("For more details, see README.md or --help");
 }
 pub fn print_chainable_help(cmd: &str) {
@@ -358,12 +637,16 @@ pub fn print_chainable_help(cmd: &str) {
         "select" => print_select_help(),
         "isin" => print_isin_help(),
         "contains" => print_contains_help(),
+        "filter" => print_filter_help(),
         "sed" => print_sed_help(),
         "grep" => print_grep_help(),
+        "join" => print_join_help(),
         "head" => print_head_help(),
         "tail" => print_tail_help(),
         "sort" => print_sort_help(),
         "count" => print_count_help(),
+        "groupby" => print_groupby_help(),
+        "sql" => print_sql_help(),
         "uniq" => print_uniq_help(),
         "changetz" => print_changetz_help(),
         "renamecol" => print_renamecol_help(),
@@ -372,30 +655,46 @@ pub fn print_chainable_help(cmd: &str) {
         "timeslice" => print_timeslice_help(),
         "partition" => print_partition_help(),
         "pivot" => print_pivot_help(),
+        "melt" => print_melt_help(),
         "timeround" => print_timeround_help(),
+        "todatetime" => print_todatetime_help(),
 
         "show" => print_show_help(),
         "showtable" => print_showtable_help(),
+        "chart" => print_chart_help(),
         "headers" => print_headers_help(),
         "stats" => print_stats_help(),
         "showquery" => print_showquery_help(),
         "dump" => print_dump_help(),
         "dumpcache" => print_dumpcache_help(),
         "quilt" => print_quilt_help(),
+        "toquilt" => print_toquilt_help(),
+        "repl" => print_repl_help(),
+        "cache" => print_cache_help(),
         _ => println!("No detailed help available for this command."),
     }
 }
 fn print_load_help() {
-    println!("load: Load CSV files\n");
+    println!("load: Load CSV, JSON Lines or Parquet files\n");
     println!("Usage: load [files...] [options]\n");
     println!("Options:");
     println!("  -s, --separator <char> Field separator character (default: ',')");
     println!("  --low-memory         Use memory-efficient loading for large files");
     println!("  --no-headers         Treat the first row as data, not headers");
     println!("  --chunk-size <size>  Process files in chunks of this size");
+    println!("\nCompressed input:");
+    println!("  Files ending in .gz, .zst, .bz2 or .xz are decompressed while loading.");
+    println!("  Every CSV/TSV member of a .zip archive is loaded as a separate input file");
+    println!("  and concatenated with the other inputs; other members are skipped.");
+    println!("\nJSON input:");
+    println!(
+        "  .json, .jsonl and .ndjson files (optionally compressed) are read as one record per line"
+    );
+    println!("  (or a single array of records). Nested objects are flattened into dotted column");
+    println!("  names, e.g. {{\"Event\": {{\"ID\": 1}}}} becomes column 'Event.ID'.");
     println!("\nEnvironment Variables:");
     println!("  QSV_CHUNK_SIZE       Default chunk size for CSV processing");
-    println!("  QSV_MEMORY_LIMIT_MB  Memory limit for gzip decompression (512-4096MB)");
+    println!("  QSV_MEMORY_LIMIT_MB  Memory limit for decompressing large files (512-4096MB)");
     println!("\nExamples:");
     println!("  qsv load data.csv - show");
     println!("  qsv load data.csv -s ';' - show");
@@ -404,8 +703,12 @@ fn print_load_help() {
     println!("  qsv load data.csv --no-headers - show");
     println!("  qsv load data.csv --chunk-size 1000 - show");
     println!("  QSV_CHUNK_SIZE=8192 qsv load data.csv - show      # Use environment variable");
-    println!("  QSV_MEMORY_LIMIT_MB=2048 qsv load data.csv.gz - show  # Gzip memory limit");
+    println!(
+        "  QSV_MEMORY_LIMIT_MB=2048 qsv load data.csv.gz - show  # Decompression memory limit"
+    );
     println!("  qsv load file1.csv file2.csv - show");
+    println!("  qsv load evidence.zip logs.csv.zst - show");
+    println!("  qsv load sysmon.jsonl.gz - select Event.System.EventID - show");
 }
 fn print_select_help() {
     println!("select: Select columns from the DataFrame\n");
@@ -437,6 +740,22 @@ fn print_contains_help() {
     println!("  qsv load data.csv - contains col1 foo - show");
     println!("  qsv load data.csv - contains col1 bar -i - show");
 }
+fn print_filter_help() {
+    println!("filter: Filter rows by a boolean expression\n");
+    println!("Usage: filter <expression>\n");
+    println!("Expression syntax:");
+    println!("  Comparison:  ==, =, !=, <>, <, <=, >, >=");
+    println!("  Boolean:     and, or, not (also &&, ||, !) and parentheses");
+    println!("  Null checks: <col> is null, <col> is not null");
+    println!("  Regex:       <col> =~ \"pattern\", <col> !~ \"pattern\"");
+    println!("  Literals:    42, -1.5, \"text\", 'text', true, false, null");
+    println!("  Datetimes:   2023-01-01, 2023-01-01T12:00:00, dt\"2023-01-01 12:00:00\"");
+    println!("  Columns:     bare names (col1, Event.System.EventID) or `quoted name`");
+    println!("\nExamples:");
+    println!("  qsv load data.csv - filter 'col1 > 3' - show");
+    println!("  qsv load access.log.csv - filter 'status >= 400 and (method == \"POST\" or path =~ \"^/admin\")' - show");
+    println!("  qsv load data.csv - filter 'datetime >= dt\"2023-01-01 13:00:00\" and str is not null' - show");
+}
 fn print_sed_help() {
     println!("sed: Replace values in column(s) using a pattern\n");
     println!("Usage:");
@@ -461,6 +780,27 @@ fn print_grep_help() {
     println!("  qsv load data.csv - grep bar -i - show");
     println!("  qsv load data.csv - grep pattern --ignore-case --invert-match - show");
 }
+fn print_join_help() {
+    println!("join: Join the current data with another file\n");
+    println!("Usage: join <file...> (--on <cols> | --left-on <cols> --right-on <cols>) [--how <type>] [--suffix <suffix>] [-s <char>]\n");
+    println!("Options:");
+    println!("  --on <cols>             Key column(s) present on both sides (comma-separated)");
+    println!("  --left-on <cols>        Key column(s) of the current data (comma-separated)");
+    println!("  --right-on <cols>       Key column(s) of the joined file (comma-separated)");
+    println!(
+        "  --how <type>            inner, left, right, full, semi, anti or cross (default: inner)"
+    );
+    println!(
+        "  --suffix <suffix>       Suffix for clashing right-hand column names (default: _right)"
+    );
+    println!("  -s, --separator <char>  Field separator of the joined CSV file(s) (default: ',')");
+    println!("\nExamples:");
+    println!("  qsv load a.csv - join b.csv --on user_id - showtable");
+    println!("  qsv load a.csv - join b.csv --left-on id --right-on user_id --how left - show");
+    println!("  qsv load a.csv - join b.csv --on date,host --how anti - show");
+    println!("  qsv load a.csv - join b.csv --how cross --suffix _b - show");
+    println!("  qsv load a.csv - join hosts.csv --on host --separator ';' - show");
+}
 fn print_head_help() {
     println!("head: Show first N rows\n");
     println!("Usage: head <number>");
@@ -492,6 +832,39 @@ fn print_sort_help() {
     println!("  qsv load data.csv - sort col1,col2 -d - show");
     println!("  qsv load data.csv - sort col1,col2 --desc - show");
 }
+fn print_groupby_help() {
+    println!("groupby: Group by key columns and compute several aggregations at once\n");
+    println!("Usage: groupby <columns> [--agg \"<func>(<column>) [as <name>], ...\"]\n");
+    println!("Options:");
+    println!("  --agg    Comma-separated aggregations (default: count(*) as count)");
+    println!("\nAggregations:");
+    println!("  count(*), count(col)        Number of rows / non-null values");
+    println!("  n_unique(col)               Number of distinct values");
+    println!("  sum, mean, min, max, median, std, var");
+    println!("  first(col), last(col)       First / last value in the group");
+    println!("  mode(col)                   Most frequent value");
+    println!("  list(col)                   All values as [a, b, c]");
+    println!("  concat(col[, 'sep'])        Values joined with sep (default: ,)");
+    println!("  percentile(col, 95), quantile(col, 0.95), p95(col)");
+    println!("\nExamples:");
+    println!("  qsv load logs.csv - groupby host,user --agg \"count(*) as n, n_unique(src_ip), min(ts), max(ts), sum(bytes)\" - show");
+    println!("  qsv load logs.csv - groupby host --agg \"p95(latency), concat(user, ';') as users\" - show");
+    println!("\nNote: Output columns default to <func>_<column>; results are sorted by the group columns.");
+}
+fn print_sql_help() {
+    println!("sql: Run a SQL query against the current data, registered as table 'self'\n");
+    println!("Usage: sql \"<query>\" [--table <name>=<file>[,<name>=<file>...]]\n");
+    println!("Options:");
+    println!("  --table    Register extra files as named tables for joins (comma-separated)");
+    println!(
+        "\nSupports SELECT, WHERE, GROUP BY, HAVING, ORDER BY, JOIN, window functions and CTEs."
+    );
+    println!("\nExamples:");
+    println!("  qsv load data.csv - sql \"SELECT col1, col2 FROM self WHERE col1 > 3\" - show");
+    println!("  qsv load logs.csv - sql \"SELECT host, COUNT(*) AS n FROM self GROUP BY host ORDER BY n DESC\" - show");
+    println!("  qsv load a.csv - sql \"SELECT * FROM self JOIN users USING (user_id)\" --table users=users.csv - show");
+    println!("\nNote: In quilt, earlier stages can be queried by their stage name.");
+}
 fn print_count_help() {
     println!("count: Count duplicate rows, grouping by all columns\n");
     println!("Usage: count\n");
@@ -506,19 +879,39 @@ fn print_uniq_help() {
 }
 fn print_changetz_help() {
     println!("changetz: Change timezone of a datetime column\n");
-    println!("Usage: changetz <colname> --from-tz <from_tz> --to-tz <to_tz> [--input-format <format>] [--output-format <format>] [--ambiguous <strategy>]\n");
+    println!("Usage: changetz <colname> --from-tz <from_tz> --to-tz <to_tz> [--input-format <format>] [--output-format <format>] [--ambiguous <strategy>] [--epoch <epoch>] [--strict]\n");
     println!("Options:");
     println!("  --from-tz       Source timezone (e.g., UTC, America/New_York, local)");
     println!("  --to-tz         Target timezone (e.g., Asia/Tokyo)");
     println!("  --input-format  Input datetime format (default: auto)");
-    println!("  --output-format Output datetime format (default: auto - ISO8601)");
+    println!("  --output-format Output datetime format (default: auto - keep a datetime, shown as ISO8601)");
     println!(
         "  --ambiguous     Strategy for ambiguous times: earliest or latest (default: earliest)"
     );
+    println!("  --epoch         Decode numeric timestamps instead of parsing text (see below)");
+    println!(
+        "  --strict        Fail on values that cannot be parsed instead of leaving them empty"
+    );
+    println!("\nEpochs (values are UTC; use --from-tz UTC):");
+    println!("  filetime        Windows FILETIME, 100ns intervals since 1601-01-01");
+    println!("  filetime_hex    FILETIME as hex: 0x01D91DD892F7E000, 01D91DD8:92F7E000 or");
+    println!("                  little-endian bytes '00 E0 F7 92 D8 1D D9 01'");
+    println!("  webkit          Chrome/WebKit time, microseconds since 1601-01-01");
+    println!("  cocoa           Mac absolute time, seconds since 2001-01-01");
+    println!("  unix_s, unix_ms, unix_us, unix_ns");
+    println!("                  Unix time in seconds, milliseconds, microseconds or nanoseconds");
+    println!("  Decimal values keep their fraction; values that cannot be decoded become empty.");
+    println!("\nUnparsable values:");
+    println!(
+        "  todatetime, changetz, timeround, timeslice, timeline and filter share one datetime"
+    );
+    println!("  parser. Values it cannot parse are left empty and listed on stderr when the");
+    println!("  pipeline ends; --strict makes them an error instead.");
     println!("\nExamples:");
     println!("  qsv load data.csv - changetz datetime --from-tz UTC --to-tz Asia/Tokyo - show");
     println!("  qsv load data.csv - changetz datetime --from-tz UTC --to-tz Asia/Tokyo --input-format '%Y/%m/%d %H:%M' - show");
     println!("  qsv load data.csv - changetz datetime --from-tz America/New_York --to-tz UTC --ambiguous latest - show");
+    println!("  qsv load mft.csv - changetz created --from-tz UTC --to-tz Asia/Tokyo --epoch filetime - show");
 }
 fn print_renamecol_help() {
     println!("renamecol: Rename a column\n");
@@ -553,25 +946,48 @@ fn print_convert_help() {
 }
 fn print_timeline_help() {
     println!("timeline: Aggregate data by time intervals\n");
-    println!("Usage: timeline <time_column> --interval <interval> [--sum|--avg|--min|--max|--std <column>]\n");
+    println!("Usage: timeline <time_column> --interval <interval> [--sum|--avg|--min|--max|--std <columns>] [--agg <aggs>] [--by <column>] [--fill] [--start <time>] [--end <time>] [--week-start <day>] [--tz <zone>] [--epoch <epoch>] [--input-format <fmt>] [--strict]\n");
     println!("Options:");
-    println!("  --interval   Time interval (e.g., 1h, 5m, 30s, 1d)");
-    println!("  --sum        Sum values in specified column");
-    println!("  --avg        Average values in specified column");
-    println!("  --min        Minimum values in specified column");
-    println!("  --max        Maximum values in specified column");
-    println!("  --std        Standard deviation of values in specified column");
+    println!("  --interval   Time interval: 500ms, 30s, 5m, 1h, 1d, 1w, 1mo or 1y");
+    println!("  --sum        Sum values in the specified columns (comma-separated)");
+    println!("  --avg        Average values in the specified columns");
+    println!("  --min        Minimum values in the specified columns");
+    println!("  --max        Maximum values in the specified columns");
+    println!("  --std        Standard deviation of values in the specified columns");
+    println!(
+        "  --agg <aggs> Aggregations in groupby syntax, e.g. 'p95(latency) as p95, n_unique(user)'"
+    );
+    println!("  --by <col>   One series per value of the column (e.g. per host or user)");
+    println!("  --fill       Add empty buckets between the first and last bucket; counts and sums");
+    println!("               of empty buckets are 0, other aggregations are left empty");
+    println!("  --start      First bucket to emit, filling empty ones (implies --fill)");
+    println!("  --end        Last bucket to emit, filling empty ones (implies --fill)");
+    println!("  --week-start <day>  First day of week buckets: mon (default) .. sun");
+    println!("  --tz <zone>  Bucket in the calendar of this zone; naive times are read as UTC");
+    println!("  --epoch      Decode numeric timestamps: filetime, filetime_hex, webkit, cocoa,");
+    println!("               unix_s, unix_ms, unix_us, unix_ns (see 'qsv changetz --help')");
+    println!("  --input-format <fmt>  strftime format of the column (default: detected per value)");
+    println!("  --strict     Fail on values that cannot be parsed instead of leaving them empty");
     println!("\nExamples:");
     println!("  qsv load access.log - timeline timestamp --interval 1h - show");
-    println!("  qsv load metrics.csv - timeline time --interval 5m --avg cpu_usage - show");
-    println!("  qsv load sales.csv - timeline date --interval 1d --sum amount - show");
+    println!("  qsv load metrics.csv - timeline time --interval 5m --avg cpu_usage,mem_usage --max cpu_usage - show");
+    println!("  qsv load sales.csv - timeline date --interval 1w --week-start sun --sum amount --fill - show");
+    println!("  qsv load auth.csv - timeline time --interval 1d --by user --start 2023-01-01 --end 2023-01-31 - show");
+    println!("  qsv load web.csv - timeline time --interval 1mo --agg 'p95(latency) as p95' --tz Europe/Berlin - show");
+    println!(
+        "  qsv load history.csv - timeline last_visit_time --interval 1h --epoch webkit - show"
+    );
 }
 fn print_timeslice_help() {
     println!("timeslice: Filter data by time range\n");
-    println!("Usage: timeslice <time_column> [--start <start_time>] [--end <end_time>]\n");
+    println!("Usage: timeslice <time_column> [--start <start_time>] [--end <end_time>] [--epoch <epoch>] [--input-format <fmt>] [--strict]\n");
     println!("Options:");
     println!("  --start      Start time (inclusive)");
     println!("  --end        End time (inclusive)");
+    println!("  --epoch      Decode numeric timestamps: filetime, filetime_hex, webkit, cocoa,");
+    println!("               unix_s, unix_ms, unix_us, unix_ns (see 'qsv changetz --help')");
+    println!("  --input-format <fmt>  strftime format of the column (default: detected per value)");
+    println!("  --strict     Fail on values that cannot be parsed instead of leaving them empty");
     println!("\nExamples:");
     println!("  qsv load data.csv - timeslice timestamp --start '2023-01-01 00:00:00' - show");
     println!("  qsv load data.csv - timeslice timestamp --end '2023-12-31 23:59:59' - show");
@@ -581,10 +997,14 @@ fn print_timeslice_help() {
 }
 fn print_partition_help() {
     println!("partition: Split data into separate files by column values\n");
-    println!("Usage: partition <colname> [output_directory]\n");
+    println!("Usage: partition <colname> [output_directory] [--compression <codec>]\n");
     println!("Arguments:");
     println!("  <colname>           Column name to partition by");
     println!("  [output_directory]  Directory to save partitioned files (default: ./partitions/)");
+    println!("\nOptions:");
+    println!(
+        "  --compression <codec>  Compress each file: gz, zst, bz2 or xz (e.g. value.csv.zst)"
+    );
     println!("\nExamples:");
     println!(
         "  qsv load data.csv - partition category                    # Uses default ./partitions/"
@@ -592,44 +1012,103 @@ fn print_partition_help() {
     println!("  qsv load data.csv - partition category ./partitions/");
     println!("  qsv load sales.csv - partition region ./by_region/ - show");
     println!("  qsv load logs.csv - partition date ./daily_logs/ - show");
+    println!("  qsv load logs.csv - partition host ./by_host/ --compression zst");
     println!("\nNote: Creates one CSV file per unique value in the specified column.");
 }
 fn print_pivot_help() {
     println!("pivot: Create pivot tables with cross-tabulation\n");
     println!(
-        "Usage: pivot --rows <columns> --cols <columns> --values <column> [--agg <function>]\n"
+        "Usage: pivot --rows <columns> --cols <columns> --values <columns> [--agg <functions>] [--fill <value>] [--row-totals] [--col-totals]\n"
     );
     println!("Options:");
-    println!("  --rows <columns>    Comma-separated list of columns for rows");
-    println!("  --cols <columns>    Comma-separated list of columns for columns");
-    println!("  --values <column>   Column to aggregate");
+    println!("  --rows <columns>     Comma-separated list of columns for rows");
+    println!(
+        "  --cols <columns>     Comma-separated list of columns whose values become output columns"
+    );
+    println!("  --values <columns>   Comma-separated list of columns to aggregate");
     println!(
-        "  --agg <function>    Aggregation function (sum, mean, count, min, max, median, std)"
+        "  --agg <functions>    Comma-separated aggregation functions (sum, mean, count, min, max, median, std; default: sum)"
     );
+    println!("  --fill <value>       Value for cells with no matching rows (default: empty)");
+    println!("  --row-totals         Add a Total column aggregated across all pivot columns");
+    println!("  --col-totals         Add a Total row aggregated across all rows");
     println!("\nExamples:");
     println!("  qsv load sales.csv - pivot --rows region --cols product --values sales_amount --agg sum - show");
-    println!("  qsv load data.csv - pivot --rows category --cols year --values revenue --agg mean - show");
-    println!("  qsv load logs.csv - pivot --rows date --cols error_type --values count --agg count - show");
-    println!("\nNote: Creates a cross-tabulation table with specified rows and columns.");
+    println!("  qsv load data.csv - pivot --rows category --cols year --values revenue --agg mean --fill 0 - show");
+    println!("  qsv load sales.csv - pivot --rows region --cols quarter --values sales --row-totals --col-totals - show");
+    println!("  qsv load sales.csv - pivot --rows region --cols product --values sales,units --agg sum,max - show");
+    println!(
+        "\nNote: Each distinct value of --cols becomes its own column. With several values or"
+    );
+    println!("aggregations, columns are named <value>_<agg>_<col value>. Without --cols, one");
+    println!("<value>_<agg> column is produced per value and aggregation.");
+}
+fn print_melt_help() {
+    println!("melt: Unpivot wide columns into long records (the inverse of pivot)\n");
+    println!(
+        "Usage: melt [--id <columns>] [--value-cols <columns>] [--variable-name <name>] [--value-name <name>]\n"
+    );
+    println!("Options:");
+    println!("  --id <columns>           Comma-separated list of columns kept on every record");
+    println!("  --value-cols <columns>   Columns to melt, supports ranges like col1:col3 or 2:4");
+    println!("                           (default: all columns not listed in --id)");
+    println!("  --variable-name <name>   Name of the column holding melted column names (default: variable)");
+    println!(
+        "  --value-name <name>      Name of the column holding melted values (default: value)"
+    );
+    println!("\nExamples:");
+    println!("  qsv load hosts.csv - melt --id date --value-cols host1:host4 - show");
+    println!(
+        "  qsv load monthly.csv - melt --id region --variable-name month --value-name sales - show"
+    );
+    println!("\nNote: Value columns of different types are melted as strings.");
 }
 fn print_timeround_help() {
     println!("timeround: Round datetime to specified time unit\n");
-    println!("Usage: timeround <colname> --unit <unit> [--output <colname>]\n");
+    println!("Usage: timeround <colname> --unit <unit> [--output <colname>] [--epoch <epoch>] [--input-format <fmt>] [--strict]\n");
     println!("Options:");
     println!("  --unit      Time unit: y/year, M/month, d/day, h/hour, m/minute, s/second");
     println!("  --output    Output column name (default: replaces original column)");
-    println!("\nOutput formats by unit:");
-    println!("  year (y):   2023");
-    println!("  month (M):  2023-01");
-    println!("  day (d):    2023-01-01");
-    println!("  hour (h):   2023-01-01 12");
-    println!("  minute (m): 2023-01-01 12:34");
-    println!("  second (s): 2023-01-01 12:34:56");
+    println!("  --epoch     Decode numeric timestamps: filetime, filetime_hex, webkit, cocoa,");
+    println!("              unix_s, unix_ms, unix_us, unix_ns (see 'qsv changetz --help')");
+    println!("  --input-format <fmt>  strftime format of the column (default: detected per value)");
+    println!("  --strict    Fail on values that cannot be parsed instead of leaving them empty");
+    println!("\nOutput by unit (year, month and day round to dates, the rest to datetimes):");
+    println!("  year (y):   2023-01-01");
+    println!("  month (M):  2023-06-01");
+    println!("  day (d):    2023-06-15");
+    println!("  hour (h):   2023-06-15 12:00:00");
+    println!("  minute (m): 2023-06-15 12:34:00");
+    println!("  second (s): 2023-06-15 12:34:56");
     println!("\nExamples:");
     println!("  qsv load data.csv - timeround timestamp --unit d --output date_only");
     println!("  qsv load data.csv - timeround timestamp --unit h --output hour_rounded");
     println!("  qsv load data.csv - timeround timestamp --unit m");
     println!("  qsv load logs.csv - timeround created_at --unit d --output created_day");
+    println!("  qsv load events.csv - timeround ts --unit m --epoch unix_ms");
+}
+fn print_todatetime_help() {
+    println!("todatetime: Turn a column into datetimes with a time zone\n");
+    println!("Usage: todatetime <colname> [--tz <timezone>] [--epoch <epoch>] [--input-format <fmt>] [--strict]\n");
+    println!("Options:");
+    println!("  --tz            Time zone of the values and of the result (default: UTC)");
+    println!("  --epoch         Decode numeric timestamps: filetime, filetime_hex, webkit, cocoa,");
+    println!("                  unix_s, unix_ms, unix_us, unix_ns (see 'qsv changetz --help')");
+    println!("  --input-format <fmt>  strftime format of the column (default: detected per value)");
+    println!(
+        "  --strict        Fail on values that cannot be parsed instead of leaving them empty"
+    );
+    println!("\nThe column stays a datetime through the rest of the pipeline, so sort, filter,");
+    println!("timeslice, timeround and timeline use it without parsing it again. Text values are");
+    println!(
+        "wall-clock times in --tz; epoch timestamps and datetimes that already have a time zone"
+    );
+    println!("are converted to --tz. show, showtable and dump write datetimes with a time zone as");
+    println!("ISO8601 with their offset (2023-01-01T21:00:00.000000+09:00).");
+    println!("\nExamples:");
+    println!("  qsv load data.csv - todatetime timestamp - sort timestamp - show");
+    println!("  qsv load data.csv - todatetime timestamp --tz Asia/Tokyo - timeline timestamp --interval 1d - show");
+    println!("  qsv load mft.csv - todatetime created --epoch filetime --tz Europe/Berlin - show");
 }
 fn print_show_help() {
     println!("show: Print result as CSV\n");
@@ -648,6 +1127,31 @@ fn print_showtable_help() {
     println!("Examples:");
     println!("  qsv load data.csv - showtable");
 }
+fn print_chart_help() {
+    println!("chart: Draw a numeric column as a bar chart, histogram or sparkline\n");
+    println!("Usage: chart <column> [--type bar|hist|spark] [--x <column>] [--by <column>] [--width <n>] [--height <n>] [--bins <n>] [--log] [--ascii]\n");
+    println!("Options:");
+    println!(
+        "  --type       bar (default): one bar per row; hist: counts of values in equal-width"
+    );
+    println!("               bins; spark: one sparkline per series in row order");
+    println!(
+        "  --x <col>    Column labelling bars and sparkline points (default: first other column)"
+    );
+    println!("  --by <col>   One series per value of the column, e.g. per host");
+    println!("  --width <n>  Width of each line in characters (default: 80)");
+    println!(
+        "  --height <n> Rows of each sparkline (default: 1); most bars to draw (default: all)"
+    );
+    println!("  --bins <n>   Number of histogram bins (default: 10)");
+    println!("  --log        Scale lengths by ln(1 + value) so small values stay visible");
+    println!("  --ascii      Draw with ASCII characters only");
+    println!("\nExamples:");
+    println!("  qsv load access.log - timeline time --interval 1h --fill - chart count");
+    println!("  qsv load access.log - timeline time --interval 1d --by host --fill - chart count --by host --type spark");
+    println!("  qsv load metrics.csv - chart latency --type hist --bins 20 --log");
+    println!("  qsv load sales.csv - groupby region --agg 'sum(amount) as total' - chart total --x region --ascii");
+}
 fn print_headers_help() {
     println!("headers: Show column names\n");
     println!("Usage: headers [-p|--plain]\n");
@@ -670,21 +1174,32 @@ fn print_showquery_help() {
     println!("  qsv load data.csv - showquery");
 }
 fn print_dump_help() {
-    println!("dump: Save DataFrame as CSV\n");
-    println!("Usage: dump [-o|--output <file>] [-s|--separator <char>] [--batch-size <size>]\n");
+    println!("dump: Save DataFrame to a file (CSV, TSV, JSON, NDJSON, Parquet, Arrow IPC, Markdown, HTML)\n");
+    println!("Usage: dump [-o|--output <file>] [-s|--separator <char>] [--format <format>] [--batch-size <size>]\n");
     println!("Options:");
     println!(
         "  -o, --output <file>     Output file path (optional, default: dump_<timestamp>.csv)"
     );
-    println!("  -s, --separator <char>  Field separator character (default: ',')");
+    println!(
+        "  -s, --separator <char>  Field separator character for CSV/TSV (default: ',' / tab)"
+    );
+    println!("  --format <format>       Output format (default: guessed from the file extension, else csv)");
+    println!("                          csv, tsv, ndjson (jsonl), json, parquet, ipc (arrow), markdown (md), html");
     println!("  --batch-size <size>     Memory batch size for streaming (default: 1GB)");
     println!("                          Accepts values like: 512MB, 2GB, 1024MB");
+    println!("\nCompression:");
+    println!("  Text outputs ending in .gz, .zst, .bz2 or .xz are compressed (e.g. out.csv.zst).");
+    println!("  Parquet and Arrow IPC outputs cannot take a compression extension.");
     println!("\nExamples:");
     println!("  qsv load data.csv - dump                        # Auto-named file");
     println!("  qsv load data.csv - dump -o results.csv");
     println!("  qsv load data.csv - dump --output results.csv");
     println!("  qsv load data.csv - dump -o results.csv -s ';'");
+    println!("  qsv load data.csv - dump -o results.ndjson      # Format from extension");
+    println!("  qsv load data.csv - dump -o results.csv.zst     # zstd compressed CSV");
+    println!("  qsv load data.csv - dump -o results.txt --format markdown");
     println!("  qsv load huge.csv - dump -o output.csv --batch-size 2GB");
+    println!("  qsv load huge.csv - dump -o output.parquet --batch-size 512MB");
 }
 fn print_dumpcache_help() {
     println!("dumpcache: Save DataFrame as parquet cache file\n");
@@ -705,27 +1220,175 @@ fn print_dumpcache_help() {
 
 fn print_quilt_help() {
     println!("quilt: Execute a quilt (data processing pipeline from YAML)\n");
-    println!("Usage: quilt <config_path> [csv_file_paths...] [-o <output_file>]\n");
+    println!("Usage: quilt <config_path> [csv_file_paths...] [-o <output_file>] [--param <name>=<value>...] [--jobs <n>] [--validate]");
+    println!("       quilt --schema\n");
     println!("Arguments:");
     println!("  <config_path>    Path to the Quilt YAML configuration file. (Required)");
     println!("  [csv_file_paths...] Optional paths to CSV files to be processed if not specified in YAML's load steps.");
     println!("Options:");
     println!("  -o, --output <output_file>  Optional path to save the result as CSV.");
     println!("                              If not provided, output is printed to console.");
+    println!("  --param <name>=<value>      Set a ${{name}} variable, overriding the YAML 'params:' default.");
+    println!("                              May be repeated. ${{env:NAME}} reads an environment variable.");
+    println!(
+        "  --jobs <n>                  Maximum number of independent stages run at the same time."
+    );
+    println!("                              Overrides 'max_parallel' in the YAML (default: number of CPUs).");
+    println!("  --validate                  Check the quilt without reading any data and report every problem.");
+    println!(
+        "  --schema                    Print a JSON Schema for quilt files, for editor completion."
+    );
     println!("Examples:");
     println!("  qsv quilt my_pipeline.yaml");
     println!("  qsv quilt my_pipeline.yaml -o result.csv");
+    println!("  qsv quilt my_pipeline.yaml --param start=2024-01-01 --param host=dc01");
+    println!("  qsv quilt my_pipeline.yaml --jobs 1");
+    println!("  qsv quilt my_pipeline.yaml --validate");
+}
+fn print_toquilt_help() {
+    println!("toquilt: Save the pipeline before it as a quilt YAML file instead of running it\n");
+    println!("Usage: <pipeline> - toquilt <output.yaml> [--title <title>]\n");
+    println!("Arguments:");
+    println!("  <output.yaml>     Path of the quilt file to write.");
+    println!("Options:");
+    println!("  --title <title>   Title of the quilt (default: 'Exported qsv pipeline').");
+    println!("\nThe pipeline becomes one process stage named 'pipeline'. Relative input paths");
+    println!("are made absolute unless the quilt is saved in the current directory.");
+    println!("Examples:");
+    println!(
+        "  qsv load logs.csv - grep error - timeline time --interval 1h - toquilt errors.yaml"
+    );
+    println!("  qsv quilt errors.yaml");
+}
+fn print_cache_help() {
+    println!("cache: Manage the result cache\n");
+    println!("Usage: cache <list|remove <key>...|clear|prune> [--max-size <size>]\n");
+    println!("When QSV_CACHE_DIR is set, the result of the chainables before the first finalizer");
+    println!("is stored there as parquet, keyed by the input files (path, size and modification");
+    println!("time) and the commands. The parsed input is stored as well. Later runs starting");
+    println!("with the same commands read the cached result instead of the input files.\n");
+    println!("Actions:");
+    println!("  list              Show cached results, most recently used first");
+    println!("  remove <key>...   Remove the given results");
+    println!("  clear             Remove all results");
+    println!("  prune             Remove results whose inputs changed and the least recently");
+    println!("                    used results beyond the size limit");
+    println!("Options:");
+    println!("  --max-size <size> Size limit for prune (default: QSV_CACHE_MAX_SIZE or 4GB)");
+    println!("\nEnvironment Variables:");
+    println!("  QSV_CACHE_DIR       Cache directory; caching is off when unset");
+    println!("  QSV_CACHE_MAX_SIZE  Total size of cached results, e.g. 2GB (default: 4GB)");
+    println!("\nExamples:");
+    println!("  QSV_CACHE_DIR=~/.cache/qsv qsv load huge.csv - grep error - head 10");
+    println!("  QSV_CACHE_DIR=~/.cache/qsv qsv cache list");
+    println!("  QSV_CACHE_DIR=~/.cache/qsv qsv cache prune --max-size 1GB");
+}
+fn print_repl_help() {
+    println!("repl: Load file(s) once, then run commands line by line\n");
+    println!("Usage: repl [files...] [load options]\n");
+    println!("Each line is a pipeline fragment in the usual syntax, e.g. 'grep error - head 5'.");
+    println!("Chainables extend the current chain; a line without a finalizer prints a table.");
+    println!("The input is read once and each result is kept in memory, so further commands");
+    println!("start from the last result instead of re-reading the files.\n");
+    println!("REPL commands:");
+    println!("  undo              Remove the last chainable from the chain");
+    println!("  history           Print the current chain as a qsv command line");
+    println!("  help              Show this help");
+    println!("  exit, quit        Leave the REPL (or press Ctrl-D)");
+    println!("\nOptions: the same as 'load'.");
+    println!("Examples:");
+    println!("  qsv repl logs.csv");
+    println!("  qsv> grep error");
+    println!("  qsv> select time,message - head 5");
+    println!("  qsv> undo");
+    println!("  qsv> toquilt errors.yaml");
 }
 
-/// Parse batch size string like "512MB", "2GB" into bytes
-pub fn parse_batch_size(size_str: &str) -> Result<usize, String> {
-    let size_str = size_str.trim().to_uppercase();
+// Define static Regex patterns for column range parsing (both colon and hyphen notation)
+static RE_COL_RANGE_COLON: Lazy<Regex> = Lazy::new(|| {
+    // This regex captures colon notation: col1:col3 or col1:3
+    // p1: The prefix of the start of the range (e.g., "col")
+    // n1: The number of the start of the range (e.g., "1")
+    // p2: (Optional) The prefix of the end of the range if specified (e.g., "col" in "col1:col3")
+    // n2: (Conditional) The number of the end of the range if p2 is specified (e.g., "3" in "col1:col3")
+    // n3: (Conditional) The number of the end of the range if p2 is NOT specified (e.g., "3" in "col1:3")
+    Regex::new(r"^(?P<p1>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n1>\d+):(?:(?P<p2>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n2>\d+)|(?P<n3>\d+))$").unwrap()
+});
 
-    // Default 1GB if empty
-    if size_str.is_empty() {
-        return Ok(1_073_741_824); // 1GB
+static RE_COL_RANGE_HYPHEN: Lazy<Regex> = Lazy::new(|| {
+    // This regex captures hyphen notation: col1-col3 or col1-3
+    // p1: The prefix of the start of the range (e.g., "col")
+    // n1: The number of the start of the range (e.g., "1")
+    // p2: (Optional) The prefix of the end of the range if specified (e.g., "col" in "col1-col3")
+    // n2: (Conditional) The number of the end of the range if p2 is specified (e.g., "3" in "col1-col3")
+    // n3: (Conditional) The number of the end of the range if p2 is NOT specified (e.g., "3" in "col1-3")
+    Regex::new(r"^(?P<p1>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n1>\d+)-(?:(?P<p2>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n2>\d+)|(?P<n3>\d+))$").unwrap()
+});
+
+/// Split a comma-separated column list, expanding ranges such as `col1-col3` or `col1:3`
+pub fn parse_column_names(input: &str) -> Result<Vec<String>, QsvError> {
+    let mut result = Vec::new();
+
+    for part in input.split(',') {
+        let part = part.trim();
+
+        // Handle quoted colon notation: "col1":"col3"
+        if part.starts_with('"') && part.contains(":") && part.ends_with('"') {
+            // Pass quoted colon range as-is to select.rs for proper processing
+            result.push(part.to_string());
+            continue;
+        }
+
+        // Try colon notation first (col1:col3), then hyphen notation (col1-col3)
+        let captures_opt = RE_COL_RANGE_COLON
+            .captures(part)
+            .or_else(|| RE_COL_RANGE_HYPHEN.captures(part));
+
+        if let Some(captures) = captures_opt {
+            let prefix1 = captures.name("p1").unwrap().as_str();
+            let num1: usize = captures.name("n1").unwrap().as_str().parse().unwrap();
+
+            let (prefix2, num2) = if let Some(p2) = captures.name("p2") {
+                // Format: col1:col3 or col1-col3
+                let prefix2 = p2.as_str();
+                let num2: usize = captures.name("n2").unwrap().as_str().parse().unwrap();
+                (prefix2, num2)
+            } else {
+                // Format: col1:3 or col1-3
+                let num2: usize = captures.name("n3").unwrap().as_str().parse().unwrap();
+                (prefix1, num2)
+            };
+
+            // Ensure both prefixes are the same
+            if prefix1 != prefix2 {
+                return Err(QsvError::InvalidArgument(format!(
+                    "Mismatched prefixes in range '{part}'. Both sides must have the same prefix."
+                )));
+            }
+
+            // Generate the range
+            if num1 <= num2 {
+                for i in num1..=num2 {
+                    result.push(format!("{prefix1}{i}"));
+                }
+            } else {
+                return Err(QsvError::InvalidArgument(format!(
+                    "Invalid range '{part}'. Start number must be <= end number."
+                )));
+            }
+        } else {
+            // Not a range, add as-is
+            result.push(part.to_string());
+        }
     }
 
+    Ok(result)
+}
+
+/// Parse a size string like "512KB", "2GB" or "1024" (bytes) into bytes
+pub fn parse_size(size_str: &str) -> Result<usize, String> {
+    let size_str = size_str.trim().to_uppercase();
+
     // Extract number and unit
     let (number_str, unit) = if size_str.ends_with("GB") {
         (&size_str[..size_str.len() - 2], "GB")
@@ -743,20 +1406,30 @@ pub fn parse_batch_size(size_str: &str) -> Result<usize, String> {
     // Parse the number
     let number: f64 = number_str
         .parse()
-        .map_err(|_| format!("Invalid number in batch size: {size_str}"))?;
+        .map_err(|_| format!("Invalid number in size: {size_str}"))?;
 
     if number < 0.0 {
-        return Err("Batch size cannot be negative".to_string());
+        return Err("Size cannot be negative".to_string());
     }
 
     // Convert to bytes
-    let bytes = match unit {
-        "B" => number as usize,
-        "KB" => (number * 1_024.0) as usize,
-        "MB" => (number * 1_048_576.0) as usize,
-        "GB" => (number * 1_073_741_824.0) as usize,
-        _ => return Err(format!("Unknown unit: {unit}")),
-    };
+    match unit {
+        "B" => Ok(number as usize),
+        "KB" => Ok((number * 1_024.0) as usize),
+        "MB" => Ok((number * 1_048_576.0) as usize),
+        "GB" => Ok((number * 1_073_741_824.0) as usize),
+        _ => Err(format!("Unknown unit: {unit}")),
+    }
+}
+
+/// Parse batch size string like "512MB", "2GB" into bytes
+pub fn parse_batch_size(size_str: &str) -> Result<usize, String> {
+    // Default 1GB if empty
+    if size_str.trim().is_empty() {
+        return Ok(1_073_741_824); // 1GB
+    }
+    let bytes = parse_size(size_str)?;
+    let size_str = size_str.trim().to_uppercase();
 
     // Validate reasonable bounds
     const MIN_BATCH_SIZE: usize = 1_048_576; // 1MB minimum
@@ -0,0 +1,330 @@
+//! Compressed input and output: gzip, zstd, bzip2 and xz streams chosen by file extension,
+//! and zip archives whose CSV members are read as separate input files.
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+const DECOMPRESS_BUFFER_SIZE: usize = 16 * 1024 * 1024; // 16MB read buffer for large streams
+const IN_MEMORY_THRESHOLD: u64 = 512 * 1024 * 1024; // Compressed size decompressed without a cap
+
+/// Compression codec of a single-stream file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+    Xz,
+}
+
+impl Compression {
+    /// Codec of a file extension such as `gz` or `zst`
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_lowercase().as_str() {
+            "gz" | "gzip" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "bz2" | "bzip2" => Some(Self::Bzip2),
+            "xz" => Some(Self::Xz),
+            _ => None,
+        }
+    }
+
+    /// Codec of a path from its last extension, e.g. `logs.csv.zst`
+    pub fn from_path(path: &Path) -> Option<Self> {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Self::from_extension)
+    }
+
+    /// Parse a `--compression` value
+    pub fn from_name(name: &str) -> Result<Self, QsvError> {
+        Self::from_extension(name).ok_or_else(|| {
+            QsvError::InvalidArgument(format!(
+                "Unsupported compression '{name}'. Supported codecs: gz, zst, bz2, xz"
+            ))
+        })
+    }
+
+    /// File extension appended to compressed output
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gz",
+            Self::Zstd => "zst",
+            Self::Bzip2 => "bz2",
+            Self::Xz => "xz",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+            Self::Bzip2 => "bzip2",
+            Self::Xz => "xz",
+        }
+    }
+
+    /// Wrap a reader so it yields decompressed bytes
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            // Multi-member decoders read concatenated streams as produced by e.g. `pigz`
+            Self::Gzip => Box::new(flate2::read::MultiGzDecoder::new(reader)),
+            Self::Zstd => Box::new(zstd::Decoder::new(reader)?),
+            Self::Bzip2 => Box::new(bzip2::read::MultiBzDecoder::new(reader)),
+            Self::Xz => Box::new(xz2::read::XzDecoder::new_multi_decoder(reader)),
+        })
+    }
+
+    /// Wrap a writer so the bytes written to it are compressed
+    pub fn encoder<W: Write>(&self, writer: W) -> io::Result<CompressedWriter<W>> {
+        Ok(match self {
+            Self::Gzip => CompressedWriter::Gzip(flate2::write::GzEncoder::new(
+                writer,
+                flate2::Compression::default(),
+            )),
+            Self::Zstd => CompressedWriter::Zstd(zstd::Encoder::new(writer, 0)?),
+            Self::Bzip2 => CompressedWriter::Bzip2(bzip2::write::BzEncoder::new(
+                writer,
+                bzip2::Compression::default(),
+            )),
+            Self::Xz => CompressedWriter::Xz(xz2::write::XzEncoder::new(writer, 6)),
+        })
+    }
+}
+
+/// Writer that compresses with the codec chosen for the output; `finish` must be called to
+/// write the end of the compressed stream
+pub enum CompressedWriter<W: Write> {
+    Plain(W),
+    Gzip(flate2::write::GzEncoder<W>),
+    Zstd(zstd::Encoder<'static, W>),
+    Bzip2(bzip2::write::BzEncoder<W>),
+    Xz(xz2::write::XzEncoder<W>),
+}
+
+impl<W: Write> CompressedWriter<W> {
+    /// Complete the compressed stream, flush, and return the underlying writer
+    pub fn finish(self) -> io::Result<W> {
+        let mut writer = match self {
+            Self::Plain(writer) => writer,
+            Self::Gzip(encoder) => encoder.finish()?,
+            Self::Zstd(encoder) => encoder.finish()?,
+            Self::Bzip2(encoder) => encoder.finish()?,
+            Self::Xz(encoder) => encoder.finish()?,
+        };
+        writer.flush()?;
+        Ok(writer)
+    }
+}
+
+impl<W: Write> Write for CompressedWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Self::Plain(writer) => writer.write(buf),
+            Self::Gzip(encoder) => encoder.write(buf),
+            Self::Zstd(encoder) => encoder.write(buf),
+            Self::Bzip2(encoder) => encoder.write(buf),
+            Self::Xz(encoder) => encoder.write(buf),
+        }
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Self::Plain(writer) => writer.flush(),
+            Self::Gzip(encoder) => encoder.flush(),
+            Self::Zstd(encoder) => encoder.flush(),
+            Self::Bzip2(encoder) => encoder.flush(),
+            Self::Xz(encoder) => encoder.flush(),
+        }
+    }
+}
+
+/// Create an output file, compressed when its extension names a codec (`out.csv.zst`)
+pub fn create_output(path: &Path) -> Result<CompressedWriter<BufWriter<File>>, QsvError> {
+    let file = File::create(path)
+        .map_err(|e| QsvError::Io(format!("creating file '{}': {e}", path.display())))?;
+    let writer = BufWriter::new(file);
+    match Compression::from_path(path) {
+        Some(codec) => codec.encoder(writer).map_err(|e| {
+            QsvError::Io(format!(
+                "starting {} compression for '{}': {e}",
+                codec.name(),
+                path.display()
+            ))
+        }),
+        None => Ok(CompressedWriter::Plain(writer)),
+    }
+}
+
+/// The path without a compression extension, e.g. `logs.csv` for `logs.csv.zst`
+pub fn strip_compression(path: &Path) -> PathBuf {
+    match Compression::from_path(path) {
+        Some(_) => path.with_extension(""),
+        None => path.to_path_buf(),
+    }
+}
+
+/// Lowercase extension of the data inside a possibly compressed file (`csv` for `logs.csv.gz`)
+pub fn data_extension(path: &Path) -> Option<String> {
+    strip_compression(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.to_lowercase())
+}
+
+pub fn is_zip_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Open a file for reading, decompressing it when its extension names a codec
+pub fn open_decompressed(path: &Path) -> Result<Box<dyn Read>, QsvError> {
+    let file =
+        File::open(path).map_err(|e| QsvError::Io(format!("opening {}: {e}", path.display())))?;
+    match Compression::from_path(path) {
+        Some(codec) => codec.decoder(BufReader::new(file)).map_err(|e| {
+            QsvError::Io(format!(
+                "decompressing {} file {}: {e}",
+                codec.name(),
+                path.display()
+            ))
+        }),
+        None => Ok(Box::new(file)),
+    }
+}
+
+/// Memory cap for decompressing large inputs, from `QSV_MEMORY_LIMIT_MB` (512-4096MB)
+pub fn get_env_memory_limit_mb() -> usize {
+    std::env::var("QSV_MEMORY_LIMIT_MB")
+        .unwrap_or_else(|_| "1024".to_string()) // Default 1GB
+        .parse::<usize>()
+        .unwrap_or(1024)
+        .clamp(512, 4096) // Limit between 512MB-4GB
+}
+
+/// Decompressed contents of a compressed file, with whether it was large enough to be read
+/// in capped chunks.
+///
+/// Inputs under 512MB compressed are decompressed in one go. Larger ones are read in 16MB
+/// chunks up to the `QSV_MEMORY_LIMIT_MB` cap, and fail if they decompress to more.
+pub fn decompress_file(path: &Path, codec: Compression) -> Result<(Vec<u8>, bool), QsvError> {
+    let file = File::open(path).map_err(|e| {
+        QsvError::Io(format!(
+            "opening {} file {}: {e}",
+            codec.name(),
+            path.display()
+        ))
+    })?;
+    let file_size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    let reader = codec.decoder(BufReader::new(file)).map_err(|e| {
+        QsvError::Io(format!(
+            "decompressing {} file {}: {e}",
+            codec.name(),
+            path.display()
+        ))
+    })?;
+    let source = format!("{} file {}", codec.name(), path.display());
+    let large = file_size == 0 || file_size >= IN_MEMORY_THRESHOLD;
+    Ok((read_to_memory(reader, file_size, large, &source)?, large))
+}
+
+fn read_to_memory(
+    mut reader: impl Read,
+    compressed_size: u64,
+    large: bool,
+    source: &str,
+) -> Result<Vec<u8>, QsvError> {
+    if !large {
+        // For smaller files, decompress to memory (faster)
+        LogController::debug(&format!(
+            "Small {source} ({}MB), using memory decompression",
+            compressed_size / 1024 / 1024
+        ));
+        let mut decompressed = Vec::with_capacity((compressed_size / 2) as usize); // Estimate 50% compression ratio
+        reader
+            .read_to_end(&mut decompressed)
+            .map_err(|e| QsvError::Io(format!("decompressing {source}: {e}")))?;
+        return Ok(decompressed);
+    }
+
+    LogController::debug(&format!(
+        "Large {source} ({}MB), using streaming decompression",
+        compressed_size / 1024 / 1024
+    ));
+    // Use a larger memory buffer for streaming (configurable)
+    let max_memory_usage = get_env_memory_limit_mb() * 1024 * 1024;
+    let mut decompressed = Vec::with_capacity(max_memory_usage);
+    let mut chunk = vec![0u8; DECOMPRESS_BUFFER_SIZE];
+    loop {
+        match reader.read(&mut chunk) {
+            Ok(0) => break, // EOF
+            Ok(n) => {
+                // Stop at the memory limit; results from part of a file would look complete
+                if decompressed.len() + n > max_memory_usage {
+                    return Err(QsvError::Io(format!(
+                        "decompressing {source}: the data exceeds the {}MB memory limit. Raise QSV_MEMORY_LIMIT_MB (up to 4096) or decompress the file first",
+                        max_memory_usage / (1024 * 1024)
+                    )));
+                }
+                decompressed.extend_from_slice(&chunk[..n]);
+            }
+            Err(e) => return Err(QsvError::Io(format!("reading {source}: {e}"))),
+        }
+    }
+    LogController::debug(&format!(
+        "Decompressed {}MB into memory",
+        decompressed.len() / (1024 * 1024)
+    ));
+    Ok(decompressed)
+}
+
+/// A member file of a zip archive, decompressed
+pub struct ZipMember {
+    pub name: String,
+    pub data: Vec<u8>,
+    /// Whether the member was large enough to be read in capped chunks
+    pub large: bool,
+}
+
+/// The members of a zip archive accepted by `accept` (given the member name without any
+/// compression extension), in archive order. Members that are themselves compressed, such
+/// as `logs.csv.gz`, are decompressed as well.
+pub fn read_zip_members(
+    path: &Path,
+    accept: impl Fn(&Path) -> bool,
+) -> Result<Vec<ZipMember>, QsvError> {
+    let file = File::open(path)
+        .map_err(|e| QsvError::Io(format!("opening zip archive {}: {e}", path.display())))?;
+    let mut archive = zip::ZipArchive::new(BufReader::new(file))
+        .map_err(|e| QsvError::Parse(format!("zip archive {}: {e}", path.display())))?;
+    let mut members = Vec::new();
+    for index in 0..archive.len() {
+        let member = archive.by_index(index).map_err(|e| {
+            QsvError::Parse(format!(
+                "zip archive {} member {index}: {e}",
+                path.display()
+            ))
+        })?;
+        let name = member.name().to_string();
+        let member_path = Path::new(&name);
+        if member.is_dir() || !accept(&strip_compression(member_path)) {
+            LogController::debug(&format!("Skipping zip member {name}"));
+            continue;
+        }
+        let source = format!("{}:{name}", path.display());
+        let size = member.compressed_size();
+        let large = size >= IN_MEMORY_THRESHOLD;
+        let data = match Compression::from_path(member_path) {
+            Some(codec) => {
+                let decoder = codec
+                    .decoder(member)
+                    .map_err(|e| QsvError::Io(format!("decompressing {source}: {e}")))?;
+                read_to_memory(decoder, size, large, &source)?
+            }
+            None => read_to_memory(member, size, large, &source)?,
+        };
+        members.push(ZipMember { name, data, large });
+    }
+    Ok(members)
+}
@@ -1,16 +1,26 @@
+use crate::controllers::compression::Compression;
+use crate::controllers::datetime::DatetimeParser;
+use crate::error::QsvError;
+use crate::operations::chainables::changetz::ChangetzOptions;
+use crate::operations::chainables::pivot::PivotOptions;
+use crate::operations::chainables::timeline::TimelineOptions;
 use crate::operations::chainables::{
-    changetz, contains, convert, count, grep, head, isin, pivot, renamecol, sed, select, sort,
-    tail, timeline, timeround, timeslice, uniq,
+    changetz, contains, convert, count, filter, grep, groupby, head, isin, join, melt, pivot,
+    renamecol, sed, select, sort, sql, tail, timeline, timeround, timeslice, todatetime, uniq,
 };
+use crate::operations::finalizers::chart::ChartOptions;
+use crate::operations::finalizers::dump::DumpFormat;
 use crate::operations::finalizers::{
-    dump, dumpcache, headers, partition, show, showquery, showtable, stats,
+    chart, dump, dumpcache, headers, partition, show, showquery, showtable, stats,
 };
 use crate::operations::initializers::load;
-use chrono::Local;
 use polars::prelude::*;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
-#[derive(Clone)]
+/// Fluent wrapper around a lazy query; each method applies one qsv operation, and fails
+/// with `QsvError::InvalidArgument` until data has been loaded
+#[derive(Clone, Default)]
 pub struct DataFrameController {
     df: Option<LazyFrame>,
 }
@@ -18,12 +28,40 @@ impl DataFrameController {
     pub fn new() -> Self {
         Self { df: None }
     }
+    /// Start from an existing frame instead of loading files
+    pub fn from_lazy_frame(df: LazyFrame) -> Self {
+        Self { df: Some(df) }
+    }
     pub fn set_df(&mut self, df: LazyFrame) {
         self.df = Some(df);
     }
     pub fn is_empty(&self) -> bool {
         self.df.is_none()
     }
+    /// The current (uncollected) query, if data has been loaded
+    pub fn lazy_frame(&self) -> Option<&LazyFrame> {
+        self.df.as_ref()
+    }
+    pub fn into_lazy_frame(self) -> Option<LazyFrame> {
+        self.df
+    }
+    /// Execute the current query and return the resulting `DataFrame`
+    pub fn collect(&self) -> Result<DataFrame, QsvError> {
+        match &self.df {
+            Some(df) => Ok(df.clone().collect()?),
+            None => Err(QsvError::InvalidArgument(
+                "No data loaded. Please load data first before collecting.".to_string(),
+            )),
+        }
+    }
+    // The loaded query, or an error naming the operation that needed it
+    fn loaded(&self, operation: &str) -> Result<&LazyFrame, QsvError> {
+        self.df.as_ref().ok_or_else(|| {
+            QsvError::InvalidArgument(format!(
+                "No data loaded. Please load data first before using '{operation}'."
+            ))
+        })
+    }
     // -- initializers --
     pub fn load(
         &mut self,
@@ -32,30 +70,58 @@ impl DataFrameController {
         low_memory: bool,
         no_headers: bool,
         chunk_size: Option<usize>,
-    ) -> &mut Self {
+    ) -> Result<&mut Self, QsvError> {
         self.df = Some(load::load(
             paths, separator, low_memory, no_headers, chunk_size,
-        ));
-        self
+        )?);
+        Ok(self)
     }
     // -- chainables --
-    pub fn select(&mut self, colnames: &[String]) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(select::select(df, colnames));
-        }
-        self
+    pub fn select(&mut self, colnames: &[String]) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("select")?;
+        self.df = Some(select::select(df, colnames)?);
+        Ok(self)
     }
-    pub fn isin(&mut self, colname: &str, values: &[String]) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(isin::isin(df, colname, values));
-        }
-        self
+    pub fn isin(&mut self, colname: &str, values: &[String]) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("isin")?;
+        self.df = Some(isin::isin(df, colname, values)?);
+        Ok(self)
     }
-    pub fn contains(&mut self, colname: &str, pattern: &str, ignorecase: bool) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(contains::contains(df, colname, pattern, ignorecase));
-        }
-        self
+    pub fn contains(
+        &mut self,
+        colname: &str,
+        pattern: &str,
+        ignorecase: bool,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("contains")?;
+        self.df = Some(contains::contains(df, colname, pattern, ignorecase)?);
+        Ok(self)
+    }
+    pub fn filter(&mut self, expression: &str) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("filter")?;
+        self.df = Some(filter::filter(df, expression)?);
+        Ok(self)
+    }
+    pub fn join(
+        &mut self,
+        right_paths: &[PathBuf],
+        left_on: &[String],
+        right_on: &[String],
+        how: &str,
+        suffix: &str,
+        separator: &str,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("join")?;
+        self.df = Some(join::join(
+            df,
+            right_paths,
+            left_on,
+            right_on,
+            how,
+            suffix,
+            separator,
+        )?);
+        Ok(self)
     }
     pub fn sed(
         &mut self,
@@ -63,195 +129,256 @@ impl DataFrameController {
         pattern: &str,
         replacement: &str,
         ignorecase: bool,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(sed::sed(df, colname, pattern, replacement, ignorecase));
-        }
-        self
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("sed")?;
+        self.df = Some(sed::sed(df, colname, pattern, replacement, ignorecase)?);
+        Ok(self)
     }
-    pub fn grep(&mut self, pattern: &str, ignorecase: bool, is_inverted: bool) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(grep::grep(df, pattern, ignorecase, is_inverted));
-        }
-        self
+    pub fn grep(
+        &mut self,
+        pattern: &str,
+        ignorecase: bool,
+        is_inverted: bool,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("grep")?;
+        self.df = Some(grep::grep(df, pattern, ignorecase, is_inverted)?);
+        Ok(self)
     }
-    pub fn head(&mut self, number: usize) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(head::head(df, number));
-        }
-        self
+    pub fn head(&mut self, number: usize) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("head")?;
+        self.df = Some(head::head(df, number)?);
+        Ok(self)
     }
-    pub fn tail(&mut self, number: usize) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(tail::tail(df, number));
-        }
-        self
+    pub fn tail(&mut self, number: usize) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("tail")?;
+        self.df = Some(tail::tail(df, number)?);
+        Ok(self)
     }
-    pub fn sort(&mut self, colnames: &[String], desc: bool) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(sort::sort(df, colnames, desc));
-        }
-        self
+    pub fn sort(&mut self, colnames: &[String], desc: bool) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("sort")?;
+        self.df = Some(sort::sort(df, colnames, desc)?);
+        Ok(self)
     }
-    pub fn count(&mut self) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(count::count(df));
-        }
-        self
+    pub fn count(&mut self) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("count")?;
+        self.df = Some(count::count(df)?);
+        Ok(self)
     }
-    pub fn uniq(&mut self) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(uniq::uniq(df));
-        }
-        self
+    pub fn groupby(
+        &mut self,
+        keys: &[String],
+        aggregations: &[String],
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("groupby")?;
+        self.df = Some(groupby::groupby(df, keys, aggregations)?);
+        Ok(self)
+    }
+    pub fn sql(
+        &mut self,
+        query: &str,
+        tables: &HashMap<String, LazyFrame>,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("sql")?;
+        self.df = Some(sql::sql(df, query, tables)?);
+        Ok(self)
+    }
+    pub fn uniq(&mut self) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("uniq")?;
+        self.df = Some(uniq::uniq(df)?);
+        Ok(self)
     }
     pub fn changetz(
         &mut self,
         colname: &str,
         tz_from: &str,
         tz_to: &str,
-        input_format: Option<&str>,
-        output_format: Option<&str>,
-        ambiguous_time: Option<&str>,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            let input_format_str = input_format.unwrap_or("auto");
-            let output_format_str = output_format.unwrap_or("auto");
-            let ambiguous_str = ambiguous_time.unwrap_or("earliest");
-            self.df = Some(changetz::changetz(
-                df,
-                colname,
-                tz_from,
-                tz_to,
-                input_format_str,
-                output_format_str,
-                ambiguous_str,
-            ));
-        }
-        self
+        options: &ChangetzOptions,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("changetz")?;
+        self.df = Some(changetz::changetz(df, colname, tz_from, tz_to, options)?);
+        Ok(self)
     }
-    pub fn renamecol(&mut self, old_name: &str, new_name: &str) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(renamecol::renamecol(df, old_name, new_name));
-        }
-        self
+    pub fn renamecol(&mut self, old_name: &str, new_name: &str) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("renamecol")?;
+        self.df = Some(renamecol::renamecol(df, old_name, new_name)?);
+        Ok(self)
     }
-    pub fn convert(&mut self, colname: &str, from_format: &str, to_format: &str) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(convert::convert(df, colname, from_format, to_format));
-        }
-        self
+    pub fn convert(
+        &mut self,
+        colname: &str,
+        from_format: &str,
+        to_format: &str,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("convert")?;
+        self.df = Some(convert::convert(df, colname, from_format, to_format)?);
+        Ok(self)
     }
     pub fn timeline(
         &mut self,
         time_column: &str,
         interval: &str,
-        agg_type: &str,
-        agg_column: Option<&str>,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(timeline::timeline(
-                df,
-                time_column,
-                interval,
-                agg_type,
-                agg_column,
-            ));
-        }
-        self
+        options: &TimelineOptions,
+        parser: &DatetimeParser,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("timeline")?;
+        self.df = Some(timeline::timeline(
+            df,
+            time_column,
+            interval,
+            options,
+            parser,
+        )?);
+        Ok(self)
     }
     pub fn timeslice(
         &mut self,
         time_column: &str,
         start_time: Option<&str>,
         end_time: Option<&str>,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(timeslice::timeslice(df, time_column, start_time, end_time));
-        }
-        self
+        parser: &DatetimeParser,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("timeslice")?;
+        self.df = Some(timeslice::timeslice(
+            df,
+            time_column,
+            start_time,
+            end_time,
+            parser,
+        )?);
+        Ok(self)
     }
     pub fn pivot(
         &mut self,
         rows: &[String],
         columns: &[String],
-        values: &str,
-        agg_func: &str,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(pivot::pivot(df, rows, columns, values, agg_func));
-        }
-        self
+        values: &[String],
+        agg_funcs: &[String],
+        options: &PivotOptions,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("pivot")?;
+        self.df = Some(pivot::pivot(df, rows, columns, values, agg_funcs, options)?);
+        Ok(self)
+    }
+    pub fn melt(
+        &mut self,
+        id_cols: &[String],
+        value_cols: &[String],
+        variable_name: &str,
+        value_name: &str,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("melt")?;
+        self.df = Some(melt::melt(
+            df,
+            id_cols,
+            value_cols,
+            variable_name,
+            value_name,
+        )?);
+        Ok(self)
     }
     pub fn timeround(
         &mut self,
         colname: &str,
         unit: &str,
         output_colname: Option<&str>,
-    ) -> &mut Self {
-        if let Some(df) = &self.df {
-            self.df = Some(timeround::timeround(df, colname, unit, output_colname));
-        }
-        self
+        parser: &DatetimeParser,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("timeround")?;
+        self.df = Some(timeround::timeround(
+            df,
+            colname,
+            unit,
+            output_colname,
+            parser,
+        )?);
+        Ok(self)
+    }
+    pub fn todatetime(
+        &mut self,
+        colname: &str,
+        tz: Option<&str>,
+        parser: &DatetimeParser,
+    ) -> Result<&mut Self, QsvError> {
+        let df = self.loaded("todatetime")?;
+        self.df = Some(todatetime::todatetime(df, colname, tz, parser)?);
+        Ok(self)
     }
     // -- finalizers --
-    pub fn headers(&self, plain: bool) {
-        if let Some(df) = &self.df {
-            headers::headers(df, plain);
-        }
+    pub fn headers(&self, plain: bool) -> Result<(), QsvError> {
+        let df = self.loaded("headers")?;
+        headers::headers(df, plain)?;
+        Ok(())
     }
-    pub fn stats(&self) {
-        if let Some(df) = &self.df {
-            stats::stats(df);
-        }
+    pub fn stats(&self) -> Result<(), QsvError> {
+        let df = self.loaded("stats")?;
+        stats::stats(df)?;
+        Ok(())
     }
-    pub fn showquery(&self) {
-        if let Some(df) = &self.df {
-            showquery::showquery(df);
-        }
+    pub fn showquery(&self) -> Result<(), QsvError> {
+        let df = self.loaded("showquery")?;
+        showquery::showquery(df)?;
+        Ok(())
     }
-    pub fn show(&self) {
-        if let Some(df) = &self.df {
-            show::show(df);
-        }
+    pub fn show(&self) -> Result<(), QsvError> {
+        let df = self.loaded("show")?;
+        show::show(df)?;
+        Ok(())
     }
-    pub fn show_with_batch_size(&self, batch_size: usize) {
-        if let Some(df) = &self.df {
-            show::show_with_batch_size(df, batch_size);
-        }
+    pub fn show_with_batch_size(&self, batch_size: usize) -> Result<(), QsvError> {
+        let df = self.loaded("show")?;
+        show::show_with_batch_size(df, batch_size)?;
+        Ok(())
     }
-    pub fn showtable(&self) {
-        if let Some(df) = &self.df {
-            showtable::showtable(df);
-        }
+    pub fn showtable(&self) -> Result<(), QsvError> {
+        let df = self.loaded("showtable")?;
+        showtable::showtable(df)?;
+        Ok(())
     }
-    pub fn partition(&self, colname: &str, output_dir: &str) {
-        if let Some(df) = &self.df {
-            partition::partition(df, colname, output_dir);
-        }
+    pub fn chart(&self, column: &str, options: &ChartOptions) -> Result<(), QsvError> {
+        let df = self.loaded("chart")?;
+        chart::chart(df, column, options)?;
+        Ok(())
     }
-    pub fn dump(&self, path: Option<&str>, separator: Option<char>) {
-        if let Some(df) = &self.df {
-            let output_path_str = path.map(|p| p.to_string()).unwrap_or_else(|| {
-                let now = Local::now();
-                format!("dump_{}.csv", now.format("%Y%m%d_%H%M%S"))
-            });
-            let sep_char = separator.unwrap_or(',');
-            dump::dump(df, Some(&output_path_str), sep_char);
-        }
+    pub fn partition(
+        &self,
+        colname: &str,
+        output_dir: &str,
+        compression: Option<Compression>,
+    ) -> Result<(), QsvError> {
+        let df = self.loaded("partition")?;
+        partition::partition(df, colname, output_dir, compression)?;
+        Ok(())
     }
-    pub fn dump_with_batch_size(&self, path: Option<&str>, separator: char, batch_size: usize) {
-        if let Some(df) = &self.df {
-            let output_path_str = path.map(|p| p.to_string()).unwrap_or_else(|| {
-                let now = Local::now();
-                format!("dump_{}.csv", now.format("%Y%m%d_%H%M%S"))
-            });
-            dump::dump_with_batch_size(df, Some(&output_path_str), separator, batch_size);
-        }
+    pub fn dump(
+        &self,
+        path: Option<&str>,
+        separator: Option<char>,
+        format: Option<DumpFormat>,
+    ) -> Result<(), QsvError> {
+        let df = self.loaded("dump")?;
+        let output_path_str = path
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| dump::default_output_path(format));
+        dump::dump(df, Some(&output_path_str), separator, format)?;
+        Ok(())
     }
-    pub fn dumpcache(&self, output_path: Option<&str>) {
-        if let Some(df) = &self.df {
-            dumpcache::dumpcache(df, output_path);
-        }
+    pub fn dump_with_batch_size(
+        &self,
+        path: Option<&str>,
+        separator: Option<char>,
+        format: Option<DumpFormat>,
+        batch_size: usize,
+    ) -> Result<(), QsvError> {
+        let df = self.loaded("dump")?;
+        let output_path_str = path
+            .map(|p| p.to_string())
+            .unwrap_or_else(|| dump::default_output_path(format));
+        dump::dump_with_batch_size(df, Some(&output_path_str), separator, format, batch_size)?;
+        Ok(())
+    }
+    pub fn dumpcache(&self, output_path: Option<&str>) -> Result<(), QsvError> {
+        let df = self.loaded("dumpcache")?;
+        dumpcache::dumpcache(df, output_path)?;
+        Ok(())
     }
 }
@@ -0,0 +1,409 @@
+//! Datetime parsing shared by every time-aware operation (`todatetime`, `changetz`,
+//! `timeround`, `timeslice`, `timeline` and datetime comparisons in `filter`).
+//!
+//! Values are parsed with an explicit strftime format, decoded from an epoch, or detected
+//! automatically. Values that cannot be parsed are either left empty and listed by
+//! [`report_unparsed`] (the default) or rejected with `--strict`.
+//!
+//! Parsed columns stay polars `Datetime` columns through the pipeline, with or without a time
+//! zone; [`format_datetimes`] turns them into text only where finalizers write them out.
+use crate::controllers::epoch::{parse_epoch_option, Epoch};
+use crate::error::QsvError;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone as _};
+use chrono_tz::Tz;
+use dtparse::parse as dtparse_parse;
+use once_cell::sync::Lazy;
+use polars::prelude::*;
+use regex::Regex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Mutex;
+
+// Machine-written layouts tried before dtparse, which is far slower
+const COMMON_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S%.fZ",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S",
+    "%d/%b/%Y:%H:%M:%S", // Apache log format
+];
+
+// Layouts dtparse gets wrong or rejects
+const FALLBACK_FORMATS: &[&str] = &[
+    // US formats
+    "%m/%d/%Y %H:%M:%S%.f",
+    "%m/%d/%Y %H:%M:%S",
+    // EU formats
+    "%d/%m/%Y %H:%M:%S%.f",
+    "%d/%m/%Y %H:%M:%S",
+    // Month name formats (common in logs)
+    "%d %b %Y %H:%M:%S", // 15 Jan 2023 14:30:25
+    "%b %d %Y %H:%M:%S", // Jan 15 2023 14:30:25
+    "%d %B %Y %H:%M:%S", // 15 January 2023 14:30:25
+    "%B %d %Y %H:%M:%S", // January 15 2023 14:30:25
+    "%d-%b-%Y %H:%M:%S", // 15-Jan-2023 14:30:25
+    // Log formats
+    "%a %b %d %H:%M:%S %Y",  // Mon Jan 15 14:30:25 2023
+    "%a, %d %b %Y %H:%M:%S", // Mon, 15 Jan 2023 14:30:25
+    // Windows Event Log formats
+    "%m/%d/%Y %I:%M:%S %p", // 1/15/2023 2:30:25 PM
+    "%Y-%m-%d %I:%M:%S %p", // 2023-01-15 2:30:25 PM
+];
+
+const DATE_FORMATS: &[&str] = &[
+    "%Y-%m-%d", "%Y/%m/%d", "%m/%d/%Y", "%d/%m/%Y", "%d %b %Y", "%b %d %Y",
+];
+
+static FUZZY_DATETIME_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
+    let patterns = [
+        // Month name with day and year: "January 15th, 2023 at 2:30 PM"
+        r"(?i)(?:on\s+)?(?:january|february|march|april|may|june|july|august|september|october|november|december)\s+\d{1,2}(?:st|nd|rd|th)?,?\s+\d{4}(?:\s+at\s+)?\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
+        // Short month: "Jan 15, 2023 2:30 PM"
+        r"(?i)(?:on\s+)?(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)\s+\d{1,2},?\s+\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
+        // ISO-like in text: "2023-01-15 14:30:00"
+        r"\d{4}-\d{1,2}-\d{1,2}\s+\d{1,2}:\d{2}(?::\d{2})?",
+        // US date format: "1/15/2023 2:30 PM"
+        r"\d{1,2}/\d{1,2}/\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
+        // Day month year: "Friday Jan 13 2023 9:00 AM"
+        r"(?i)(?:monday|tuesday|wednesday|thursday|friday|saturday|sunday)\s+(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)\s+\d{1,2}\s+\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
+    ];
+    patterns.iter().map(|p| Regex::new(p).unwrap()).collect()
+});
+
+// Distinct unparsable values per operation and column since the last report. Values are
+// counted distinct because a query runs in batches and may be collected more than once
+// (a sample, then the full result).
+type UnparsedValues = BTreeMap<(String, String), BTreeSet<String>>;
+static UNPARSED: Lazy<Mutex<UnparsedValues>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+const MAX_UNPARSED: usize = 10_000;
+const REPORT_SAMPLES: usize = 5;
+
+/// How finalizers write datetimes without a time zone; the fraction is omitted when zero
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f";
+/// How finalizers write datetimes with a time zone: ISO8601 in that zone, with its offset
+pub const ZONED_DATETIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.6f%:z";
+
+/// Parse a datetime in any supported layout: common ISO/log formats, Unix seconds (9 or more
+/// digits), anything dtparse understands, datetimes embedded in text and a set of fallbacks.
+pub fn parse_datetime_auto(s: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if let Some(dt) = parse_formats(s, COMMON_FORMATS) {
+        return Some(dt);
+    }
+    if let Some(dt) = parse_unix_seconds(s) {
+        return Some(dt);
+    }
+    // dtparse gives maximum flexibility (similar to Python dateutil.parser)
+    if let Ok((dt, _)) = dtparse_parse(s) {
+        return Some(dt);
+    }
+    if let Some(dt) = extract_datetime_fuzzy(s).and_then(|text| parse_extracted_datetime(&text)) {
+        return Some(dt);
+    }
+    parse_formats(s, FALLBACK_FORMATS).or_else(|| parse_dates(s, DATE_FORMATS))
+}
+
+/// Parse a datetime with an explicit strftime format; date-only formats mean midnight
+pub fn parse_datetime_with_format(s: &str, format: &str) -> Option<NaiveDateTime> {
+    let s = s.trim();
+    NaiveDateTime::parse_from_str(s, format)
+        .ok()
+        .or_else(|| parse_dates(s, &[format]))
+}
+
+fn parse_formats(s: &str, formats: &[&str]) -> Option<NaiveDateTime> {
+    formats
+        .iter()
+        .find_map(|fmt| NaiveDateTime::parse_from_str(s, fmt).ok())
+}
+
+fn parse_dates(s: &str, formats: &[&str]) -> Option<NaiveDateTime> {
+    formats
+        .iter()
+        .find_map(|fmt| NaiveDate::parse_from_str(s, fmt).ok())
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+}
+
+// Long digit strings are Unix timestamps; shorter ones (`20230115`) are left to dtparse
+fn parse_unix_seconds(s: &str) -> Option<NaiveDateTime> {
+    let whole = s.split_once('.').map_or(s, |(whole, _)| whole);
+    let digits = whole.strip_prefix('-').unwrap_or(whole);
+    if digits.len() < 9 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    Epoch::UnixS.decode(s)
+}
+
+/// Extract datetime patterns from fuzzy text using regex
+fn extract_datetime_fuzzy(text: &str) -> Option<String> {
+    FUZZY_DATETIME_PATTERNS
+        .iter()
+        .find_map(|re| re.find(text))
+        .map(|m| m.as_str().to_string())
+}
+
+/// Parse extracted datetime string using multiple formats
+fn parse_extracted_datetime(extracted: &str) -> Option<NaiveDateTime> {
+    // Clean up the extracted string
+    let cleaned = extracted
+        .replace(" at ", " ")
+        .replace("st,", ",")
+        .replace("nd,", ",")
+        .replace("rd,", ",")
+        .replace("th,", ",")
+        .replace("st ", " ")
+        .replace("nd ", " ")
+        .replace("rd ", " ")
+        .replace("th ", " ");
+    // Try dtparse again on the cleaned extracted text
+    if let Ok((dt, _)) = dtparse_parse(&cleaned) {
+        return Some(dt);
+    }
+    // Formats specifically for extracted patterns
+    let formats = [
+        "%B %d, %Y %I:%M:%S %p",   // January 15, 2023 2:30:00 PM
+        "%B %d, %Y %I:%M %p",      // January 15, 2023 2:30 PM
+        "%b %d, %Y %I:%M:%S %p",   // Jan 15, 2023 2:30:00 PM
+        "%b %d, %Y %I:%M %p",      // Jan 15, 2023 2:30 PM
+        "%Y-%m-%d %H:%M:%S",       // 2023-01-15 14:30:00
+        "%Y-%m-%d %H:%M",          // 2023-01-15 14:30
+        "%m/%d/%Y %I:%M:%S %p",    // 1/15/2023 2:30:00 PM
+        "%m/%d/%Y %I:%M %p",       // 1/15/2023 2:30 PM
+        "%A %b %d %Y %I:%M:%S %p", // Friday Jan 13 2023 9:00:00 AM
+        "%A %b %d %Y %I:%M %p",    // Friday Jan 13 2023 9:00 AM
+    ];
+    parse_formats(&cleaned, &formats)
+}
+
+/// How a time-aware operation reads the values of its datetime column
+#[derive(Debug, Clone, Default)]
+pub struct DatetimeParser {
+    /// strftime format of the values; detected per value when unset
+    pub format: Option<String>,
+    /// Decode numeric timestamps counted from this epoch instead of parsing text
+    pub epoch: Option<Epoch>,
+    /// Fail on values that cannot be parsed instead of leaving them empty
+    pub strict: bool,
+}
+
+impl DatetimeParser {
+    /// Parser for `--input-format`, `--epoch` and `--strict`; a format of `auto` means
+    /// detection
+    pub fn new(format: Option<&str>, epoch: Option<&str>, strict: bool) -> Result<Self, QsvError> {
+        let format = format.filter(|format| *format != "auto");
+        let epoch = parse_epoch_option(epoch)?;
+        if format.is_some() && epoch.is_some() {
+            return Err(QsvError::InvalidArgument(
+                "Use either --epoch or --input-format, not both".to_string(),
+            ));
+        }
+        Ok(Self {
+            format: format.map(String::from),
+            epoch,
+            strict,
+        })
+    }
+
+    pub fn parse(&self, value: &str) -> Option<NaiveDateTime> {
+        match (self.epoch, &self.format) {
+            (Some(epoch), _) => epoch.decode(value),
+            (None, Some(format)) => parse_datetime_with_format(value, format),
+            (None, None) => parse_datetime_auto(value),
+        }
+    }
+
+    /// Parse the values of a column; blank values and nulls become `None`, other values that
+    /// cannot be parsed are reported, or fail the query in strict mode
+    pub fn parse_column(
+        &self,
+        column: &Column,
+        operation: &str,
+    ) -> PolarsResult<Vec<Option<NaiveDateTime>>> {
+        if matches!(column.dtype(), DataType::Datetime(_, _) | DataType::Date) {
+            let micros = column.cast(&DataType::Datetime(TimeUnit::Microseconds, None))?;
+            return Ok(micros
+                .datetime()?
+                .into_iter()
+                .map(|us| DateTime::from_timestamp_micros(us?).map(|dt| dt.naive_utc()))
+                .collect());
+        }
+        let strings = column.cast(&DataType::String)?;
+        let mut failed = Vec::new();
+        let parsed = strings
+            .str()?
+            .into_iter()
+            .map(|value| {
+                let value = value?;
+                let dt = self.parse(value);
+                if dt.is_none() && !value.trim().is_empty() {
+                    failed.push(value);
+                }
+                dt
+            })
+            .collect();
+        if !failed.is_empty() {
+            let column = column.name().as_str();
+            if self.strict {
+                polars_bail!(ComputeError:
+                    "{operation} could not parse {} value(s) of column '{column}' as datetimes: {}. Set --input-format or --epoch, or drop --strict to leave them empty",
+                    failed.len(),
+                    samples(failed.iter().copied(), failed.len())
+                );
+            }
+            record_unparsed(operation, column, &failed);
+        }
+        Ok(parsed)
+    }
+
+    /// Expression parsing a column into a `Datetime` column; columns that already hold
+    /// dates or datetimes are only cast
+    pub fn to_datetime_expr(&self, expr: Expr, unit: TimeUnit, operation: &'static str) -> Expr {
+        let parser = self.clone();
+        expr.map(
+            move |c: Column| {
+                match c.dtype() {
+                    DataType::Datetime(_, tz) => {
+                        return Ok(Some(c.cast(&DataType::Datetime(unit, tz.clone()))?))
+                    }
+                    DataType::Date => return Ok(Some(c.cast(&DataType::Datetime(unit, None))?)),
+                    _ => {}
+                }
+                let timestamps: Int64Chunked = parser
+                    .parse_column(&c, operation)?
+                    .into_iter()
+                    .map(|dt| {
+                        let dt = dt?.and_utc();
+                        match unit {
+                            TimeUnit::Milliseconds => Some(dt.timestamp_millis()),
+                            TimeUnit::Microseconds => Some(dt.timestamp_micros()),
+                            TimeUnit::Nanoseconds => dt.timestamp_nanos_opt(),
+                        }
+                    })
+                    .collect();
+                Ok(Some(
+                    timestamps
+                        .with_name(c.name().clone())
+                        .into_datetime(unit, None)
+                        .into_series()
+                        .into(),
+                ))
+            },
+            GetOutput::map_dtype(move |dtype| {
+                Ok(match dtype {
+                    DataType::Datetime(_, tz) => DataType::Datetime(unit, tz.clone()),
+                    _ => DataType::Datetime(unit, None),
+                })
+            }),
+        )
+    }
+}
+
+/// Parse an IANA time zone name such as `Asia/Tokyo` or `UTC`
+pub fn parse_timezone(name: &str) -> Result<Tz, QsvError> {
+    name.parse()
+        .map_err(|_| QsvError::InvalidArgument(format!("Invalid timezone '{name}'")))
+}
+
+/// The polars time zone of a `Datetime` column holding instants in `tz`
+pub fn polars_timezone(tz: Tz) -> Result<TimeZone, QsvError> {
+    Ok(TimeZone::opt_try_new(Some(tz.name()))?.unwrap_or(TimeZone::UTC))
+}
+
+/// The UTC instant of a wall-clock time in `tz`. Times repeated by a DST transition resolve
+/// to their `latest` or earliest occurrence; times skipped by one have no instant.
+pub fn localize(dt: NaiveDateTime, tz: Tz, latest: bool) -> Option<NaiveDateTime> {
+    let local = tz.from_local_datetime(&dt);
+    let resolved = if latest {
+        local.latest()
+    } else {
+        local.earliest()
+    };
+    resolved.map(|dt| dt.naive_utc())
+}
+
+/// A comparison bound for a column of type `dtype`, as a naive UTC datetime. Bounds are
+/// wall-clock times in the zone of a zoned `Datetime` column and taken as they are otherwise.
+pub fn bound_for(dt: NaiveDateTime, dtype: Option<&DataType>) -> Option<NaiveDateTime> {
+    match dtype {
+        Some(DataType::Datetime(_, Some(tz))) => match tz.parse::<Tz>() {
+            Ok(tz) => localize(dt, tz, false),
+            Err(_) => Some(dt),
+        },
+        _ => Some(dt),
+    }
+}
+
+/// Format the `Datetime` columns of a frame as text, as [`DATETIME_FORMAT`] or, with a time
+/// zone, [`ZONED_DATETIME_FORMAT`]; other columns, dates included, are left as they are
+pub fn format_datetimes(df: &LazyFrame) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
+    let formatted: Vec<Expr> = schema
+        .iter()
+        .filter_map(|(name, dtype)| match dtype {
+            DataType::Datetime(_, tz) => {
+                let format = if tz.is_some() {
+                    ZONED_DATETIME_FORMAT
+                } else {
+                    DATETIME_FORMAT
+                };
+                Some(col(name.clone()).dt().to_string(format))
+            }
+            _ => None,
+        })
+        .collect();
+    if formatted.is_empty() {
+        return Ok(df.clone());
+    }
+    Ok(df.clone().with_columns(formatted))
+}
+
+fn samples<'a>(values: impl Iterator<Item = &'a str>, total: usize) -> String {
+    let shown: Vec<String> = values
+        .take(REPORT_SAMPLES)
+        .map(|value| format!("'{value}'"))
+        .collect();
+    let more = total.saturating_sub(shown.len());
+    if more > 0 {
+        format!("{} and {more} more", shown.join(", "))
+    } else {
+        shown.join(", ")
+    }
+}
+
+fn record_unparsed(operation: &str, column: &str, failed: &[&str]) {
+    let mut unparsed = UNPARSED.lock().unwrap_or_else(|e| e.into_inner());
+    let values = unparsed
+        .entry((operation.to_string(), column.to_string()))
+        .or_default();
+    for value in failed {
+        if values.len() >= MAX_UNPARSED {
+            break;
+        }
+        values.insert(value.to_string());
+    }
+}
+
+/// Print the values lenient parsing left empty since the last report, one line per operation
+/// and column.
+///
+/// Written to stderr whatever the log level, since values silently turning empty is what this
+/// report is for.
+pub fn report_unparsed() {
+    let unparsed = std::mem::take(&mut *UNPARSED.lock().unwrap_or_else(|e| e.into_inner()));
+    for ((operation, column), values) in unparsed {
+        let count = if values.len() >= MAX_UNPARSED {
+            format!("{MAX_UNPARSED}+")
+        } else {
+            values.len().to_string()
+        };
+        eprintln!(
+            "Warning: {operation} could not parse {count} distinct value(s) of column '{column}' as datetimes; they were left empty: {}",
+            samples(values.iter().map(String::as_str), values.len())
+        );
+    }
+}
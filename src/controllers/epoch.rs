@@ -0,0 +1,147 @@
+//! Numeric timestamps counted from a fixed epoch, as found in forensic artifacts: Windows
+//! FILETIME, Chrome/WebKit time, Mac absolute (Cocoa) time and Unix time in any unit.
+use crate::error::QsvError;
+use chrono::{NaiveDate, NaiveDateTime, TimeDelta};
+
+const NANOS_PER_SECOND: i128 = 1_000_000_000;
+
+/// How the values of an `--epoch` column are counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Epoch {
+    /// Windows FILETIME: 100ns intervals since 1601-01-01
+    Filetime,
+    /// FILETIME written as hex, e.g. `0x01D9A0F1C2B3A400`, `01D9A0F1:C2B3A400` or
+    /// little-endian bytes `00 A4 B3 C2 F1 A0 D9 01`
+    FiletimeHex,
+    /// Chrome/WebKit time: microseconds since 1601-01-01
+    Webkit,
+    /// Mac absolute time: seconds since 2001-01-01
+    Cocoa,
+    UnixS,
+    UnixMs,
+    UnixUs,
+    UnixNs,
+}
+
+impl Epoch {
+    pub const NAMES: &'static str =
+        "filetime, filetime_hex, webkit, cocoa, unix_s, unix_ms, unix_us, unix_ns";
+
+    /// Parse an `--epoch` value; `-` and `_` are interchangeable (`unix-ms`)
+    pub fn from_name(name: &str) -> Result<Self, QsvError> {
+        match name.to_lowercase().replace('-', "_").as_str() {
+            "filetime" => Ok(Self::Filetime),
+            "filetime_hex" | "hex_filetime" | "hexfiletime" => Ok(Self::FiletimeHex),
+            "webkit" | "chrome" => Ok(Self::Webkit),
+            "cocoa" | "mac" => Ok(Self::Cocoa),
+            "unix_s" | "unix" => Ok(Self::UnixS),
+            "unix_ms" => Ok(Self::UnixMs),
+            "unix_us" => Ok(Self::UnixUs),
+            "unix_ns" => Ok(Self::UnixNs),
+            _ => Err(QsvError::InvalidArgument(format!(
+                "Unsupported epoch '{name}'. Use: {}",
+                Self::NAMES
+            ))),
+        }
+    }
+
+    /// Nanoseconds per counted tick
+    fn tick_nanos(&self) -> i128 {
+        match self {
+            Self::Filetime | Self::FiletimeHex => 100,
+            Self::Webkit | Self::UnixUs => 1_000,
+            Self::Cocoa | Self::UnixS => NANOS_PER_SECOND,
+            Self::UnixMs => 1_000_000,
+            Self::UnixNs => 1,
+        }
+    }
+
+    fn origin(&self) -> NaiveDateTime {
+        let (year, month, day) = match self {
+            Self::Filetime | Self::FiletimeHex | Self::Webkit => (1601, 1, 1),
+            Self::Cocoa => (2001, 1, 1),
+            _ => (1970, 1, 1),
+        };
+        NaiveDate::from_ymd_opt(year, month, day)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .expect("valid epoch origin")
+    }
+
+    /// Decode one value to a UTC datetime; `None` when the value is not a timestamp of this
+    /// epoch or is out of range
+    pub fn decode(&self, value: &str) -> Option<NaiveDateTime> {
+        let value = value.trim();
+        if value.is_empty() {
+            return None;
+        }
+        let nanos = match self {
+            Self::FiletimeHex => parse_hex_filetime(value)? as i128 * self.tick_nanos(),
+            _ => parse_ticks(value, self.tick_nanos())?,
+        };
+        let seconds = i64::try_from(nanos.div_euclid(NANOS_PER_SECOND)).ok()?;
+        let subsec = nanos.rem_euclid(NANOS_PER_SECOND) as i64;
+        self.origin()
+            .checked_add_signed(TimeDelta::try_seconds(seconds)?)?
+            .checked_add_signed(TimeDelta::nanoseconds(subsec))
+    }
+}
+
+/// Parse an `--epoch` option value if one was given
+pub fn parse_epoch_option(name: Option<&str>) -> Result<Option<Epoch>, QsvError> {
+    name.map(Epoch::from_name).transpose()
+}
+
+// A decimal tick count in nanoseconds, keeping the fraction exact (`696969696.123` Cocoa
+// seconds) and falling back to floats for exponent notation
+fn parse_ticks(value: &str, tick_nanos: i128) -> Option<i128> {
+    let (negative, digits) = match value.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_decimal = |s: &str| s.bytes().all(|b| b.is_ascii_digit());
+    let nanos = if !whole.is_empty() && is_decimal(whole) && is_decimal(fraction) {
+        let whole: i128 = whole.parse().ok()?;
+        // Digits beyond nanosecond resolution of the smallest tick carry no information
+        let fraction = &fraction[..fraction.len().min(18)];
+        let fraction_nanos = if fraction.is_empty() {
+            0
+        } else {
+            fraction.parse::<i128>().ok()? * tick_nanos / 10i128.pow(fraction.len() as u32)
+        };
+        whole.checked_mul(tick_nanos)?.checked_add(fraction_nanos)?
+    } else {
+        let float: f64 = digits.parse().ok()?;
+        if !float.is_finite() {
+            return None;
+        }
+        (float * tick_nanos as f64) as i128
+    };
+    Some(if negative { -nanos } else { nanos })
+}
+
+fn parse_hex_filetime(value: &str) -> Option<u64> {
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    if tokens.len() == 8 {
+        // Raw little-endian bytes as shown by hex viewers and registry exports
+        let mut bytes = [0u8; 8];
+        for (byte, token) in bytes.iter_mut().zip(&tokens) {
+            *byte = u8::from_str_radix(token, 16).ok()?;
+        }
+        return Some(u64::from_le_bytes(bytes));
+    }
+    let hex = value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    // `HIGH:LOW` dwords as printed by many Windows tools
+    let hex = match hex.split_once(':') {
+        Some((high, low)) if low.len() <= 8 => format!("{high}{low:0>8}"),
+        Some(_) => return None,
+        None => hex.to_string(),
+    };
+    if hex.is_empty() || hex.len() > 16 {
+        return None;
+    }
+    u64::from_str_radix(&hex, 16).ok()
+}
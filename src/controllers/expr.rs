@@ -0,0 +1,11 @@
+//! Expression helpers shared by the chainables that build polars expressions from user input
+//! (`filter`, `groupby` and `pivot`).
+use polars::prelude::*;
+
+/// Literal with a concrete type.
+///
+/// `lit()` builds dynamically typed integer literals, which polars materializes as Int128
+/// (unsupported in this build) when coercing; use concretely typed scalars instead.
+pub fn typed_literal<T: Into<Scalar>>(value: T) -> Expr {
+    Expr::Literal(value.into().into())
+}
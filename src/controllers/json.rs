@@ -0,0 +1,259 @@
+use crate::controllers::compression::{data_extension, open_decompressed};
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use polars::prelude::*;
+use serde::de::{Deserialize, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+// Separator used when flattening nested objects into column names (e.g. "Event.System.EventID")
+const FLATTEN_SEPARATOR: &str = ".";
+
+// Check if a path points to a JSON / JSON Lines file (optionally compressed)
+pub fn is_json_path(path: &Path) -> bool {
+    matches!(
+        data_extension(path).as_deref(),
+        Some("json") | Some("jsonl") | Some("ndjson")
+    )
+}
+
+// JSON value that keeps object keys in document order (serde_json::Map sorts them),
+// so flattened columns appear in the same order as in the source records
+enum OrderedValue {
+    Leaf(JsonValue),
+    Object(Vec<(String, OrderedValue)>),
+}
+impl<'de> Deserialize<'de> for OrderedValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct OrderedValueVisitor;
+        impl<'de> Visitor<'de> for OrderedValueVisitor {
+            type Value = OrderedValue;
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a JSON value")
+            }
+            fn visit_bool<E>(self, v: bool) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::Bool(v)))
+            }
+            fn visit_i64<E>(self, v: i64) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::from(v)))
+            }
+            fn visit_u64<E>(self, v: u64) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::from(v)))
+            }
+            fn visit_f64<E>(self, v: f64) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::from(v)))
+            }
+            fn visit_str<E>(self, v: &str) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::String(v.to_string())))
+            }
+            fn visit_string<E>(self, v: String) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::String(v)))
+            }
+            fn visit_unit<E>(self) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::Null))
+            }
+            fn visit_none<E>(self) -> Result<OrderedValue, E> {
+                Ok(OrderedValue::Leaf(JsonValue::Null))
+            }
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<OrderedValue, A::Error> {
+                // Arrays are kept as leaves and rendered as JSON text
+                let mut items = Vec::new();
+                while let Some(item) = seq.next_element::<JsonValue>()? {
+                    items.push(item);
+                }
+                Ok(OrderedValue::Leaf(JsonValue::Array(items)))
+            }
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<OrderedValue, A::Error> {
+                let mut entries = Vec::new();
+                while let Some((key, value)) = map.next_entry::<String, OrderedValue>()? {
+                    entries.push((key, value));
+                }
+                Ok(OrderedValue::Object(entries))
+            }
+        }
+        deserializer.deserialize_any(OrderedValueVisitor)
+    }
+}
+// Column values collected while reading records, in first-seen column order
+#[derive(Default)]
+struct FlattenedColumns {
+    names: Vec<String>,
+    positions: HashMap<String, usize>,
+    values: Vec<Vec<Option<JsonValue>>>,
+    rows: usize,
+}
+impl FlattenedColumns {
+    fn push_record(&mut self, record: Vec<(String, OrderedValue)>) {
+        let mut flat = Vec::with_capacity(record.len());
+        flatten_object(None, record, &mut flat);
+        for (name, value) in flat {
+            let idx = match self.positions.get(&name) {
+                Some(idx) => *idx,
+                None => {
+                    // New column: back-fill nulls for all previous rows
+                    self.names.push(name.clone());
+                    self.positions.insert(name, self.values.len());
+                    self.values.push(vec![None; self.rows]);
+                    self.values.len() - 1
+                }
+            };
+            if self.values[idx].len() > self.rows {
+                // Same flattened name seen twice in one record (e.g. "a.b" and {"a": {"b"}})
+                self.values[idx][self.rows] = Some(value);
+            } else {
+                self.values[idx].push(Some(value));
+            }
+        }
+        self.rows += 1;
+        // Pad columns that were absent from this record
+        for column in self.values.iter_mut() {
+            if column.len() < self.rows {
+                column.push(None);
+            }
+        }
+    }
+    fn into_dataframe(self) -> PolarsResult<DataFrame> {
+        let columns = self
+            .names
+            .into_iter()
+            .zip(self.values)
+            .map(|(name, values)| build_column(&name, values))
+            .collect::<Vec<Column>>();
+        DataFrame::new(columns)
+    }
+}
+// Flatten nested objects into dotted keys; arrays and scalars are kept as leaf values
+fn flatten_object(
+    prefix: Option<&str>,
+    object: Vec<(String, OrderedValue)>,
+    out: &mut Vec<(String, JsonValue)>,
+) {
+    for (key, value) in object {
+        let name = match prefix {
+            Some(p) => format!("{p}{FLATTEN_SEPARATOR}{key}"),
+            None => key,
+        };
+        match value {
+            OrderedValue::Object(inner) if !inner.is_empty() => {
+                flatten_object(Some(&name), inner, out)
+            }
+            OrderedValue::Object(_) => out.push((name, JsonValue::String("{}".to_string()))),
+            OrderedValue::Leaf(JsonValue::Null) => {}
+            OrderedValue::Leaf(other) => out.push((name, other)),
+        }
+    }
+}
+// Build a typed column: Int64, Float64 or Boolean when every value agrees, otherwise String
+fn build_column(name: &str, values: Vec<Option<JsonValue>>) -> Column {
+    let present = || values.iter().flatten();
+    let name = PlSmallStr::from(name);
+    if present().all(|v| v.is_i64()) {
+        let data: Vec<Option<i64>> = values
+            .iter()
+            .map(|v| v.as_ref().and_then(|v| v.as_i64()))
+            .collect();
+        Column::new(name, data)
+    } else if present().all(|v| v.is_number()) {
+        let data: Vec<Option<f64>> = values
+            .iter()
+            .map(|v| v.as_ref().and_then(|v| v.as_f64()))
+            .collect();
+        Column::new(name, data)
+    } else if present().all(|v| v.is_boolean()) {
+        let data: Vec<Option<bool>> = values
+            .iter()
+            .map(|v| v.as_ref().and_then(|v| v.as_bool()))
+            .collect();
+        Column::new(name, data)
+    } else {
+        let data: Vec<Option<String>> = values
+            .into_iter()
+            .map(|v| {
+                v.map(|v| match v {
+                    JsonValue::String(s) => s,
+                    other => other.to_string(),
+                })
+            })
+            .collect();
+        Column::new(name, data)
+    }
+}
+pub struct JsonController {
+    paths: Vec<PathBuf>,
+}
+impl JsonController {
+    pub fn new(paths: &[PathBuf]) -> Self {
+        Self {
+            paths: paths.to_vec(),
+        }
+    }
+    pub fn get_dataframe(&self) -> Result<LazyFrame, QsvError> {
+        // Records from all files are collected into one frame so that files with
+        // slightly different keys are unioned by column name
+        let mut columns = FlattenedColumns::default();
+        for path in &self.paths {
+            self.read_json_file(path, &mut columns)?;
+        }
+        LogController::debug(&format!(
+            "Read {} JSON records with {} flattened columns",
+            columns.rows,
+            columns.names.len()
+        ));
+        Ok(columns.into_dataframe()?.lazy())
+    }
+    fn read_json_file(&self, path: &Path, columns: &mut FlattenedColumns) -> Result<(), QsvError> {
+        LogController::debug(&format!("Reading JSON file: {}", path.display()));
+        let reader = open_decompressed(path)?;
+        let mut reader = BufReader::new(reader);
+
+        // A '.json' file may hold a single array of records instead of one record per line
+        let read_error =
+            |e: std::io::Error| QsvError::Io(format!("reading JSON file {}: {e}", path.display()));
+        let starts_with_array = reader
+            .fill_buf()
+            .map_err(read_error)?
+            .iter()
+            .find(|b| !b.is_ascii_whitespace())
+            .is_some_and(|b| *b == b'[');
+        if starts_with_array {
+            let records: Vec<OrderedValue> = serde_json::from_reader(reader)
+                .map_err(|e| QsvError::Parse(format!("JSON array in {}: {e}", path.display())))?;
+            for (i, record) in records.into_iter().enumerate() {
+                push_json_record(columns, record, path, i + 1)?;
+            }
+        } else {
+            for (i, line) in reader.lines().enumerate() {
+                let line = line.map_err(read_error)?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let record = serde_json::from_str::<OrderedValue>(&line).map_err(|e| {
+                    QsvError::Parse(format!("JSON record at {}:{}: {e}", path.display(), i + 1))
+                })?;
+                push_json_record(columns, record, path, i + 1)?;
+            }
+        }
+        Ok(())
+    }
+}
+fn push_json_record(
+    columns: &mut FlattenedColumns,
+    record: OrderedValue,
+    path: &Path,
+    index: usize,
+) -> Result<(), QsvError> {
+    match record {
+        OrderedValue::Object(object) => {
+            columns.push_record(object);
+            Ok(())
+        }
+        _ => Err(QsvError::Parse(format!(
+            "JSON record {} in {}: not an object",
+            index,
+            path.display()
+        ))),
+    }
+}
@@ -1,22 +1,34 @@
 use chrono::{DateTime, Local};
 use log::{debug, error, info, warn};
+use std::cell::RefCell;
+thread_local! {
+    // Quilt stage the current thread is working on, shown in every log line
+    static STAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
 pub struct LogController;
 impl LogController {
     pub fn debug(msg: &str) {
-        let timestamp = Self::get_timestamp();
-        debug!("[{timestamp}] {msg}");
+        debug!("{}", Self::format(msg));
     }
     pub fn info(msg: &str) {
-        let timestamp = Self::get_timestamp();
-        info!("[{timestamp}] {msg}");
+        info!("{}", Self::format(msg));
     }
     pub fn warn(msg: &str) {
-        let timestamp = Self::get_timestamp();
-        warn!("[{timestamp}] {msg}");
+        warn!("{}", Self::format(msg));
     }
     pub fn error(msg: &str) {
+        error!("{}", Self::format(msg));
+    }
+    /// Attribute log messages from the current thread to a quilt stage
+    pub fn set_stage(stage: Option<&str>) {
+        STAGE.with(|s| *s.borrow_mut() = stage.map(String::from));
+    }
+    fn format(msg: &str) -> String {
         let timestamp = Self::get_timestamp();
-        error!("[{timestamp}] {msg}");
+        STAGE.with(|s| match s.borrow().as_deref() {
+            Some(stage) => format!("[{timestamp}] [{stage}] {msg}"),
+            None => format!("[{timestamp}] {msg}"),
+        })
     }
     fn get_timestamp() -> String {
         let now: DateTime<Local> = Local::now();
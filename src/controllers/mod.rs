@@ -1,5 +1,11 @@
 pub mod batch;
+pub mod cache;
 pub mod command;
+pub mod compression;
 pub mod csv;
 pub mod dataframe;
+pub mod datetime;
+pub mod epoch;
+pub mod expr;
+pub mod json;
 pub mod log;
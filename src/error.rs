@@ -0,0 +1,85 @@
+use polars::prelude::PolarsError;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Error returned by qsv operations.
+///
+/// Each variant maps to a distinct process exit code (see [`QsvError::exit_code`]),
+/// so scripts can tell e.g. a missing column from an unreadable file. Messages carry no
+/// `Error:` prefix, so they read well inside other error chains; the CLI adds one.
+#[derive(Debug)]
+pub enum QsvError {
+    /// Invalid command, option or argument value (exit code 1)
+    InvalidArgument(String),
+    /// A referenced column does not exist (exit code 2)
+    ColumnNotFound { column: String, operation: String },
+    /// A value, expression or file content could not be parsed (exit code 3)
+    Parse(String),
+    /// An input file does not exist (exit code 4)
+    FileNotFound(PathBuf),
+    /// Reading or writing a file failed (exit code 4)
+    Io(String),
+    /// Polars failed to plan or execute a query (exit code 5)
+    Polars(PolarsError),
+    /// Invalid quilt configuration (exit code 6)
+    Config(String),
+}
+
+impl QsvError {
+    pub fn column_not_found(column: &str, operation: &str) -> Self {
+        QsvError::ColumnNotFound {
+            column: column.to_string(),
+            operation: operation.to_string(),
+        }
+    }
+
+    /// Process exit code for this error
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            QsvError::InvalidArgument(_) => 1,
+            QsvError::ColumnNotFound { .. } => 2,
+            QsvError::Parse(_) => 3,
+            QsvError::FileNotFound(_) | QsvError::Io(_) => 4,
+            QsvError::Polars(_) => 5,
+            QsvError::Config(_) => 6,
+        }
+    }
+}
+
+impl fmt::Display for QsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QsvError::InvalidArgument(msg) => write!(f, "{msg}"),
+            QsvError::ColumnNotFound { column, operation } => write!(
+                f,
+                "Column '{column}' not found in DataFrame for {operation} operation"
+            ),
+            QsvError::Parse(msg) => write!(f, "failed to parse {msg}"),
+            QsvError::FileNotFound(path) => write!(f, "file not found: {}", path.display()),
+            QsvError::Io(msg) => write!(f, "I/O error: {msg}"),
+            QsvError::Polars(e) => write!(f, "{e}"),
+            QsvError::Config(msg) => write!(f, "{msg}"),
+        }
+    }
+}
+
+impl std::error::Error for QsvError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            QsvError::Polars(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<PolarsError> for QsvError {
+    fn from(e: PolarsError) -> Self {
+        QsvError::Polars(e)
+    }
+}
+
+impl From<std::io::Error> for QsvError {
+    fn from(e: std::io::Error) -> Self {
+        QsvError::Io(e.to_string())
+    }
+}
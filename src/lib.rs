@@ -0,0 +1,33 @@
+//! Quilter-CSV as a library.
+//!
+//! The same operations used by the `qsv` command line are available through
+//! [`DataFrameController`], which wraps a lazy Polars query and applies
+//! initializers, chainables and finalizers in a fluent style:
+//!
+//! ```no_run
+//! use qsv::DataFrameController;
+//! use std::path::PathBuf;
+//!
+//! fn main() -> Result<(), qsv::QsvError> {
+//!     let mut controller = DataFrameController::new();
+//!     controller
+//!         .load(&[PathBuf::from("data.csv")], ",", false, false, None)?
+//!         .filter("col1 > 1")?
+//!         .select(&["col1".to_string(), "str".to_string()])?
+//!         .head(10)?;
+//!     let df = controller.collect()?;
+//!     println!("{df}");
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Operations return [`QsvError`] instead of exiting the process; the CLI maps
+//! each variant to a documented exit code.
+pub mod controllers;
+pub mod error;
+pub mod operations;
+
+pub use controllers::dataframe::DataFrameController;
+pub use error::QsvError;
+pub use operations::finalizers::dump::DumpFormat;
+pub use operations::quilters::quilt::{quilt, QuiltOptions};
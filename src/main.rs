@@ -1,38 +1,36 @@
+use std::collections::HashMap;
 use std::env;
 use std::io::Write;
 use std::path::PathBuf;
 use std::process;
 
-mod controllers;
-mod operations;
+use polars::prelude::IntoLazy;
 
-use controllers::command::{
-    parse_batch_size, parse_commands, print_chainable_help, print_help, Command,
+use qsv::controllers::cache::{format_size, format_time, ResultCache, CACHE_DIR_ENV};
+use qsv::controllers::command::{
+    parse_batch_size, parse_column_names, parse_commands, parse_size, print_chainable_help,
+    print_help, sql_tables, Command, FINALIZER_COMMANDS,
 };
-use controllers::dataframe::DataFrameController;
-use once_cell::sync::Lazy;
-use regex::Regex;
-
-// Define static Regex patterns for column range parsing (both colon and hyphen notation)
-static RE_COL_RANGE_COLON: Lazy<Regex> = Lazy::new(|| {
-    // This regex captures colon notation: col1:col3 or col1:3
-    // p1: The prefix of the start of the range (e.g., "col")
-    // n1: The number of the start of the range (e.g., "1")
-    // p2: (Optional) The prefix of the end of the range if specified (e.g., "col" in "col1:col3")
-    // n2: (Conditional) The number of the end of the range if p2 is specified (e.g., "3" in "col1:col3")
-    // n3: (Conditional) The number of the end of the range if p2 is NOT specified (e.g., "3" in "col1:3")
-    Regex::new(r"^(?P<p1>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n1>\d+):(?:(?P<p2>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n2>\d+)|(?P<n3>\d+))$").unwrap()
-});
-
-static RE_COL_RANGE_HYPHEN: Lazy<Regex> = Lazy::new(|| {
-    // This regex captures hyphen notation: col1-col3 or col1-3
-    // p1: The prefix of the start of the range (e.g., "col")
-    // n1: The number of the start of the range (e.g., "1")
-    // p2: (Optional) The prefix of the end of the range if specified (e.g., "col" in "col1-col3")
-    // n2: (Conditional) The number of the end of the range if p2 is specified (e.g., "3" in "col1-col3")
-    // n3: (Conditional) The number of the end of the range if p2 is NOT specified (e.g., "3" in "col1-3")
-    Regex::new(r"^(?P<p1>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n1>\d+)-(?:(?P<p2>[a-zA-Z_][a-zA-Z_0-9]*)(?P<n2>\d+)|(?P<n3>\d+))$").unwrap()
-});
+use qsv::controllers::compression::Compression;
+use qsv::controllers::dataframe::DataFrameController;
+use qsv::controllers::datetime::{report_unparsed, DatetimeParser};
+use qsv::controllers::log::LogController;
+use qsv::error::QsvError;
+use qsv::operations::chainables::changetz::ChangetzOptions;
+use qsv::operations::chainables::join::DEFAULT_JOIN_SUFFIX;
+use qsv::operations::chainables::melt::{MELT_VALUE_NAME, MELT_VARIABLE_NAME};
+use qsv::operations::chainables::pivot::PivotOptions;
+use qsv::operations::chainables::timeline::TimelineOptions;
+use qsv::operations::finalizers::chart::{ChartOptions, ChartType};
+use qsv::operations::finalizers::dump::parse_dump_format;
+use qsv::operations::initializers::load;
+use qsv::operations::quilters::export::save_quilt;
+use qsv::operations::quilters::params::parse_param;
+use qsv::operations::quilters::schema::quilt_json_schema;
+use qsv::operations::quilters::validate::{invalid_quilt, validate_quilt};
+use qsv::QuiltOptions;
+
+mod repl;
 
 fn main() {
     // Initialize logger without timestamp (LogController provides high-precision timestamps)
@@ -70,128 +68,255 @@ fn main() {
     let commands = parse_commands(&args[1..]);
 
     if commands.is_empty() {
-        eprintln!("Error: No commands provided. Use the format: qsv load file.csv - select col1,col2 - head 5");
-        process::exit(1);
+        exit_with_error(QsvError::InvalidArgument(
+            "No commands provided. Use the format: qsv load file.csv - select col1,col2 - head 5"
+                .to_string(),
+        ));
+    }
+
+    // Interactive mode reads further commands from stdin
+    if commands[0].name == "repl" {
+        if commands.len() > 1 {
+            exit_with_error(QsvError::InvalidArgument(
+                "'repl' takes only the files to load; type further commands at its prompt"
+                    .to_string(),
+            ));
+        }
+        if let Err(e) = repl::run(&commands[0]) {
+            exit_with_error(e);
+        }
+        return;
+    }
+
+    if commands[0].name == "cache" {
+        if let Err(e) = run_cache_command(&commands[0]) {
+            exit_with_error(e);
+        }
+        return;
     }
 
     // Initialize dataframe controller
     let mut controller = DataFrameController::new();
 
     // Process commands sequentially
-    process_commands(&mut controller, &commands);
+    let result = process_commands(&mut controller, &commands);
+    report_unparsed();
+    if let Err(e) = result {
+        exit_with_error(e);
+    }
+}
+
+// Report an error and exit with its documented exit code
+fn exit_with_error(e: QsvError) -> ! {
+    eprintln!("Error: {e}");
+    process::exit(e.exit_code());
 }
 
 // Process all commands in sequence
-fn process_commands(controller: &mut DataFrameController, commands: &[Command]) {
-    for cmd in commands.iter() {
-        process_command(controller, cmd);
+fn process_commands(
+    controller: &mut DataFrameController,
+    commands: &[Command],
+) -> Result<(), QsvError> {
+    // Exporting a pipeline saves it as a quilt instead of running it
+    if let Some(pos) = commands.iter().position(|cmd| cmd.name == "toquilt") {
+        if pos + 1 != commands.len() {
+            return Err(QsvError::InvalidArgument(
+                "'toquilt' must be the last command of the pipeline".to_string(),
+            ));
+        }
+        return save_pipeline_quilt(&commands[..pos], &commands[pos]);
     }
 
-    // Check if the last command was a finalizer
-    if let Some(last_cmd) = commands.last() {
-        let finalizer_commands = [
-            "show",
-            "showtable",
-            "headers",
-            "stats",
-            "showquery",
-            "dump",
-            "dumpcache",
-            "partition",
-            "quilt",
-        ];
-
-        if !finalizer_commands.contains(&last_cmd.name.as_str()) {
-            // Last command was not a finalizer, so call showtable as default
-            if !controller.is_empty() {
-                controller.showtable();
-            }
+    // With a cache directory configured, the chainables may start from a cached result
+    let mut start = 0;
+    if commands.first().is_some_and(|cmd| cmd.name == "load") {
+        if let Some(cache) = ResultCache::from_env()? {
+            start = run_cached_prefix(controller, commands, &cache)?;
         }
     }
-}
 
-// Check if data is loaded
-fn check_data_loaded(controller: &DataFrameController, cmd_name: &str) {
-    if controller.is_empty() {
-        eprintln!("Error: No data loaded. Please load data first before using '{cmd_name}'.");
-        process::exit(1);
+    for cmd in commands[start..].iter() {
+        process_command(controller, cmd)?;
     }
-}
-
-// New parse_column_names function with range expansion
-fn parse_column_names(input: &str) -> Vec<String> {
-    let mut result = Vec::new();
 
-    for part in input.split(',') {
-        let part = part.trim();
-
-        // Handle quoted colon notation: "col1":"col3"
-        if part.starts_with('"') && part.contains(":") && part.ends_with('"') {
-            // Pass quoted colon range as-is to select.rs for proper processing
-            result.push(part.to_string());
-            continue;
+    // Without a finalizer (a quilt runs its own), print the result as a table
+    if let Some(last_cmd) = commands.last() {
+        let name = last_cmd.name.as_str();
+        if !FINALIZER_COMMANDS.contains(&name) && name != "quilt" && !controller.is_empty() {
+            controller.showtable()?;
         }
+    }
+    Ok(())
+}
 
-        // Try colon notation first (col1:col3), then hyphen notation (col1-col3)
-        let captures_opt = RE_COL_RANGE_COLON
-            .captures(part)
-            .or_else(|| RE_COL_RANGE_HYPHEN.captures(part));
-
-        if let Some(captures) = captures_opt {
-            let prefix1 = captures.name("p1").unwrap().as_str();
-            let num1: usize = captures.name("n1").unwrap().as_str().parse().unwrap();
+// Run the commands before the first finalizer, starting from the longest cached prefix and
+// caching what had to be computed; returns how many commands were run
+fn run_cached_prefix(
+    controller: &mut DataFrameController,
+    commands: &[Command],
+    cache: &ResultCache,
+) -> Result<usize, QsvError> {
+    let len = commands
+        .iter()
+        .position(|cmd| FINALIZER_COMMANDS.contains(&cmd.name.as_str()) || cmd.name == "quilt")
+        .unwrap_or(commands.len());
+    let prefix = &commands[..len];
+    let cached = match cache.lookup(prefix)? {
+        Some((cached, df)) => {
+            controller.set_df(df);
+            cached
+        }
+        None => 0,
+    };
+    if cached == len {
+        return Ok(len);
+    }
+    for (i, cmd) in prefix.iter().enumerate().skip(cached) {
+        process_command(controller, cmd)?;
+        // The parsed input is cached on its own too, for other pipelines over the same files
+        if i == 0 && len > 1 {
+            cache_result(controller, &prefix[..1], cache)?;
+        }
+    }
+    cache_result(controller, prefix, cache)?;
+    Ok(len)
+}
 
-            let (prefix2, num2) = if let Some(p2) = captures.name("p2") {
-                // Format: col1:col3 or col1-col3
-                let prefix2 = p2.as_str();
-                let num2: usize = captures.name("n2").unwrap().as_str().parse().unwrap();
-                (prefix2, num2)
-            } else {
-                // Format: col1:3 or col1-3
-                let num2: usize = captures.name("n3").unwrap().as_str().parse().unwrap();
-                (prefix1, num2)
-            };
+// Collect the current result and cache it; failing to write the cache is not an error
+fn cache_result(
+    controller: &mut DataFrameController,
+    commands: &[Command],
+    cache: &ResultCache,
+) -> Result<(), QsvError> {
+    let mut df = controller.collect()?;
+    if let Err(e) = cache.store(commands, &mut df) {
+        LogController::warn(&format!("Could not cache the result: {e}"));
+    }
+    controller.set_df(df.lazy());
+    Ok(())
+}
 
-            // Ensure both prefixes are the same
-            if prefix1 != prefix2 {
-                eprintln!(
-                    "Error: Mismatched prefixes in range '{part}'. Both sides must have the same prefix."
+// Manage the result cache: list, remove, clear or prune
+fn run_cache_command(cmd: &Command) -> Result<(), QsvError> {
+    qsv::controllers::command::validate_command_options(cmd)?;
+    let cache = ResultCache::from_env()?.ok_or_else(|| {
+        QsvError::InvalidArgument(format!(
+            "The result cache is off; set {CACHE_DIR_ENV} to the cache directory"
+        ))
+    })?;
+    match cmd.args.first().map(String::as_str) {
+        Some("list") => {
+            let listings = cache.list()?;
+            if listings.is_empty() {
+                println!("No cached results in {}", cache.dir().display());
+                return Ok(());
+            }
+            for listing in &listings {
+                println!(
+                    "{}  {:>8}  {}  {}{}",
+                    listing.entry.key,
+                    format_size(listing.size),
+                    format_time(listing.last_used),
+                    listing.entry.pipeline,
+                    if listing.stale { "  (stale)" } else { "" }
                 );
-                process::exit(1);
             }
-
-            // Generate the range
-            if num1 <= num2 {
-                for i in num1..=num2 {
-                    result.push(format!("{prefix1}{i}"));
+            println!(
+                "{} cached result(s), {} of {} in {}",
+                listings.len(),
+                format_size(listings.iter().map(|listing| listing.size).sum()),
+                format_size(cache.max_size()),
+                cache.dir().display()
+            );
+        }
+        Some("remove") => {
+            if cmd.args.len() < 2 {
+                return Err(QsvError::InvalidArgument(
+                    "'cache remove' requires the key(s) shown by 'cache list'".to_string(),
+                ));
+            }
+            for key in &cmd.args[1..] {
+                if !cache.remove(key)? {
+                    return Err(QsvError::InvalidArgument(format!(
+                        "No cached result with key '{key}'"
+                    )));
                 }
-            } else {
-                eprintln!("Error: Invalid range '{part}'. Start number must be <= end number.");
-                process::exit(1);
+                println!("Removed cached result {key}");
             }
-        } else {
-            // Not a range, add as-is
-            result.push(part.to_string());
         }
+        Some("clear") => {
+            let removed = cache.clear()?;
+            println!("Removed {removed} cached result(s)");
+        }
+        Some("prune") => {
+            let cache = match cmd.options.get("max_size").and_then(|v| v.as_deref()) {
+                Some(size) => ResultCache::new(
+                    cache.dir(),
+                    parse_size(size).map_err(QsvError::InvalidArgument)? as u64,
+                ),
+                None => cache,
+            };
+            let removed = cache.prune()?;
+            println!("Removed {removed} cached result(s)");
+        }
+        Some(other) => {
+            return Err(QsvError::InvalidArgument(format!(
+                "Unknown cache action '{other}'. Supported actions: list, remove, clear, prune"
+            )));
+        }
+        None => {
+            return Err(QsvError::InvalidArgument(
+                "'cache' command requires an action: list, remove, clear or prune".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// Save the commands before a `toquilt` command as a quilt file
+fn save_pipeline_quilt(pipeline: &[Command], cmd: &Command) -> Result<(), QsvError> {
+    qsv::controllers::command::validate_command_options(cmd)?;
+    let output_path = cmd.args.first().ok_or_else(|| {
+        QsvError::InvalidArgument("'toquilt' command requires an output file path".to_string())
+    })?;
+    let title = cmd.options.get("title").and_then(|v| v.as_deref());
+    save_quilt(pipeline, output_path, title)?;
+    println!("Saved quilt to {output_path}; run it with: qsv quilt {output_path}");
+    Ok(())
+}
+
+// Check if data is loaded
+fn check_data_loaded(controller: &DataFrameController, cmd_name: &str) -> Result<(), QsvError> {
+    if controller.is_empty() {
+        return Err(QsvError::InvalidArgument(format!(
+            "No data loaded. Please load data first before using '{cmd_name}'."
+        )));
     }
+    Ok(())
+}
 
-    result
+// How a time-aware command reads its datetime column: --input-format, --epoch and --strict
+fn datetime_parser(cmd: &Command) -> Result<DatetimeParser, QsvError> {
+    let option = |key: &str| cmd.options.get(key).and_then(|opt| opt.as_deref());
+    DatetimeParser::new(
+        option("input_format"),
+        option("epoch"),
+        cmd.options.contains_key("strict"),
+    )
 }
 
 // Process a single command
-fn process_command(controller: &mut DataFrameController, cmd: &Command) {
+fn process_command(controller: &mut DataFrameController, cmd: &Command) -> Result<(), QsvError> {
     // Validate command options
-    if let Err(error_msg) = controllers::command::validate_command_options(cmd) {
-        eprintln!("{error_msg}");
-        process::exit(1);
-    }
+    qsv::controllers::command::validate_command_options(cmd)?;
 
     match cmd.name.as_str() {
         // Initializers
         "load" => {
             if cmd.args.is_empty() {
-                eprintln!("Error: 'load' command requires at least one file path");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'load' command requires at least one file path".to_string(),
+                ));
             }
 
             let mut paths = Vec::new();
@@ -217,33 +342,33 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 paths.push(PathBuf::from(path_str));
             }
 
-            controller.load(&paths, &separator, low_memory, no_headers, chunk_size);
+            controller.load(&paths, &separator, low_memory, no_headers, chunk_size)?;
         }
 
         // Chainables
         "select" => {
-            check_data_loaded(controller, "select");
+            check_data_loaded(controller, "select")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'select' command requires column names");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'select' command requires column names".to_string(),
+                ));
             }
 
             // Parse as column names
             let colnames = if cmd.args.len() == 1 {
-                parse_column_names(&cmd.args[0])
+                parse_column_names(&cmd.args[0])?
             } else {
                 cmd.args.clone()
             };
-            controller.select(&colnames);
+            controller.select(&colnames)?;
         }
 
         "isin" => {
-            check_data_loaded(controller, "isin");
+            check_data_loaded(controller, "isin")?;
 
             if cmd.args.len() < 2 {
-                eprintln!("Error: 'isin' command requires a column name and at least one value string (e.g., isin colname val1,val2,val3)");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument("'isin' command requires a column name and at least one value string (e.g., isin colname val1,val2,val3)".to_string()));
             }
 
             let colname = &cmd.args[0];
@@ -256,34 +381,98 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 .collect();
 
             if values.is_empty() {
-                eprintln!("Error: 'isin' command requires at least one value after splitting the value string by comma.");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument("'isin' command requires at least one value after splitting the value string by comma.".to_string()));
             }
 
-            controller.isin(colname, &values);
+            controller.isin(colname, &values)?;
         }
 
         "contains" => {
-            check_data_loaded(controller, "contains");
+            check_data_loaded(controller, "contains")?;
 
             if cmd.args.len() < 2 {
-                eprintln!("Error: 'contains' command requires a column name and a pattern");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'contains' command requires a column name and a pattern".to_string(),
+                ));
             }
 
             let colname = &cmd.args[0];
             let pattern = &cmd.args[1];
             let ignorecase = cmd.options.contains_key("ignore_case");
 
-            controller.contains(colname, pattern, ignorecase);
+            controller.contains(colname, pattern, ignorecase)?;
+        }
+
+        "filter" => {
+            check_data_loaded(controller, "filter")?;
+
+            if cmd.args.is_empty() {
+                return Err(QsvError::InvalidArgument(
+                    "'filter' command requires an expression".to_string(),
+                ));
+            }
+
+            // Allow unquoted expressions split across several arguments
+            let expression = cmd.args.join(" ");
+            controller.filter(&expression)?;
+        }
+
+        "join" => {
+            check_data_loaded(controller, "join")?;
+
+            if cmd.args.is_empty() {
+                return Err(QsvError::InvalidArgument(
+                    "'join' command requires at least one file path for the right-hand side"
+                        .to_string(),
+                ));
+            }
+
+            let paths: Vec<PathBuf> = cmd.args.iter().map(PathBuf::from).collect();
+            let get_keys = |name: &str| -> Vec<String> {
+                cmd.options
+                    .get(name)
+                    .and_then(|v| v.as_ref())
+                    .map(|keys| {
+                        keys.split(',')
+                            .map(|k| k.trim().to_string())
+                            .filter(|k| !k.is_empty())
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+            let on = get_keys("on");
+            let (left_on, right_on) = if on.is_empty() {
+                (get_keys("left_on"), get_keys("right_on"))
+            } else {
+                (on.clone(), on)
+            };
+            let how = cmd
+                .options
+                .get("how")
+                .and_then(|v| v.as_deref())
+                .unwrap_or("inner");
+            let suffix = cmd
+                .options
+                .get("suffix")
+                .and_then(|v| v.as_deref())
+                .unwrap_or(DEFAULT_JOIN_SUFFIX);
+            let separator = cmd
+                .options
+                .get("separator")
+                .or_else(|| cmd.options.get("s"))
+                .and_then(|opt| opt.as_deref())
+                .unwrap_or(",");
+
+            controller.join(&paths, &left_on, &right_on, how, suffix, separator)?;
         }
 
         "sed" => {
-            check_data_loaded(controller, "sed");
+            check_data_loaded(controller, "sed")?;
 
             if cmd.args.len() < 2 {
-                eprintln!("Error: 'sed' command requires pattern and replacement");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'sed' command requires pattern and replacement".to_string(),
+                ));
             }
 
             let pattern = &cmd.args[0];
@@ -291,15 +480,16 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             let colname = cmd.options.get("column").and_then(|opt| opt.as_deref());
             let ignorecase = cmd.options.contains_key("ignore_case");
 
-            controller.sed(colname, pattern, replacement, ignorecase);
+            controller.sed(colname, pattern, replacement, ignorecase)?;
         }
 
         "grep" => {
-            check_data_loaded(controller, "grep");
+            check_data_loaded(controller, "grep")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'grep' command requires a pattern.");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'grep' command requires a pattern.".to_string(),
+                ));
             }
 
             let pattern = &cmd.args[0];
@@ -307,88 +497,131 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             let ignorecase = cmd.options.contains_key("ignore_case");
             let is_inverted = cmd.options.contains_key("invert_match");
 
-            controller.grep(pattern, ignorecase, is_inverted);
+            controller.grep(pattern, ignorecase, is_inverted)?;
         }
 
         "head" => {
-            check_data_loaded(controller, "head");
+            check_data_loaded(controller, "head")?;
 
             let number = if !cmd.args.is_empty() {
-                cmd.args[0].parse::<usize>().unwrap_or_else(|_| {
-                    eprintln!("Error: 'head' command requires a valid number");
-                    process::exit(1);
-                })
+                cmd.args[0].parse::<usize>().map_err(|_| {
+                    QsvError::InvalidArgument("'head' command requires a valid number".to_string())
+                })?
             } else if let Some(Some(n_str)) = cmd.options.get("number") {
-                n_str.parse::<usize>().unwrap_or_else(|_| {
-                    eprintln!(
-                        "Error: 'head' command requires a valid number for --number or -n option"
-                    );
-                    process::exit(1);
-                })
+                n_str.parse::<usize>().map_err(|_| {
+                    QsvError::InvalidArgument(
+                        "'head' command requires a valid number for --number or -n option"
+                            .to_string(),
+                    )
+                })?
             } else {
                 5 // Default value
             };
 
-            controller.head(number);
+            controller.head(number)?;
         }
 
         "tail" => {
-            check_data_loaded(controller, "tail");
+            check_data_loaded(controller, "tail")?;
 
             let number = if !cmd.args.is_empty() {
-                cmd.args[0].parse::<usize>().unwrap_or_else(|_| {
-                    eprintln!("Error: 'tail' command requires a valid number");
-                    process::exit(1);
-                })
+                cmd.args[0].parse::<usize>().map_err(|_| {
+                    QsvError::InvalidArgument("'tail' command requires a valid number".to_string())
+                })?
             } else if let Some(Some(n_str)) = cmd.options.get("number") {
-                n_str.parse::<usize>().unwrap_or_else(|_| {
-                    eprintln!(
-                        "Error: 'tail' command requires a valid number for --number or -n option"
-                    );
-                    process::exit(1);
-                })
+                n_str.parse::<usize>().map_err(|_| {
+                    QsvError::InvalidArgument(
+                        "'tail' command requires a valid number for --number or -n option"
+                            .to_string(),
+                    )
+                })?
             } else {
                 5 // Default value
             };
 
-            controller.tail(number);
+            controller.tail(number)?;
         }
 
         "sort" => {
-            check_data_loaded(controller, "sort");
+            check_data_loaded(controller, "sort")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'sort' command requires column names");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'sort' command requires column names".to_string(),
+                ));
             }
 
             let colnames = if cmd.args.len() == 1 {
-                parse_column_names(&cmd.args[0])
+                parse_column_names(&cmd.args[0])?
             } else {
                 cmd.args.clone()
             };
 
             let desc = cmd.options.contains_key("desc");
 
-            controller.sort(&colnames, desc);
+            controller.sort(&colnames, desc)?;
         }
 
         "count" => {
-            check_data_loaded(controller, "count");
-            controller.count();
+            check_data_loaded(controller, "count")?;
+            controller.count()?;
+        }
+
+        "groupby" => {
+            check_data_loaded(controller, "groupby")?;
+
+            if cmd.args.is_empty() {
+                return Err(QsvError::InvalidArgument(
+                    "'groupby' command requires group column names".to_string(),
+                ));
+            }
+
+            let keys = if cmd.args.len() == 1 {
+                parse_column_names(&cmd.args[0])?
+            } else {
+                cmd.args.clone()
+            };
+            let aggregations: Vec<String> = cmd
+                .options
+                .get("agg")
+                .and_then(|opt| opt.clone())
+                .into_iter()
+                .collect();
+
+            controller.groupby(&keys, &aggregations)?;
+        }
+
+        "sql" => {
+            check_data_loaded(controller, "sql")?;
+
+            if cmd.args.is_empty() {
+                return Err(QsvError::InvalidArgument(
+                    "'sql' command requires a query".to_string(),
+                ));
+            }
+
+            let query = cmd.args.join(" ");
+            let mut tables = HashMap::new();
+            for (name, path) in sql_tables(cmd)? {
+                let table = load::load(&[PathBuf::from(path)], ",", false, false, None)?;
+                tables.insert(name, table);
+            }
+
+            controller.sql(&query, &tables)?;
         }
 
         "uniq" => {
-            check_data_loaded(controller, "uniq");
-            controller.uniq();
+            check_data_loaded(controller, "uniq")?;
+            controller.uniq()?;
         }
 
         "changetz" => {
-            check_data_loaded(controller, "changetz");
+            check_data_loaded(controller, "changetz")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'changetz' command requires a column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'changetz' command requires a column name".to_string(),
+                ));
             }
 
             let colname = &cmd.args[0];
@@ -396,86 +629,87 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             let tz_from = match cmd.options.get("from_tz") {
                 Some(Some(tz)) => tz,
                 _ => {
-                    eprintln!("Error: 'changetz' command requires --from-tz option");
-                    process::exit(1);
+                    return Err(QsvError::InvalidArgument(
+                        "'changetz' command requires --from-tz option".to_string(),
+                    ));
                 }
             };
 
             let tz_to = match cmd.options.get("to_tz") {
                 Some(Some(tz)) => tz,
                 _ => {
-                    eprintln!("Error: 'changetz' command requires --to-tz option");
-                    process::exit(1);
+                    return Err(QsvError::InvalidArgument(
+                        "'changetz' command requires --to-tz option".to_string(),
+                    ));
                 }
             };
 
-            let input_format = cmd
-                .options
-                .get("input_format")
-                .and_then(|opt_val| opt_val.as_deref());
-            let output_format = cmd
-                .options
-                .get("output_format")
-                .and_then(|opt_val| opt_val.as_deref());
-            let ambiguous_time = cmd
-                .options
-                .get("ambiguous")
-                .and_then(|opt_val| opt_val.as_deref());
+            let defaults = ChangetzOptions::default();
+            let option_or = |key: &str, default: String| {
+                cmd.options
+                    .get(key)
+                    .and_then(|opt_val| opt_val.clone())
+                    .unwrap_or(default)
+            };
+            let options = ChangetzOptions {
+                parser: datetime_parser(cmd)?,
+                output_format: option_or("output_format", defaults.output_format),
+                ambiguous: option_or("ambiguous", defaults.ambiguous),
+            };
 
-            controller.changetz(
-                colname,
-                tz_from,
-                tz_to,
-                input_format,
-                output_format,
-                ambiguous_time,
-            );
+            controller.changetz(colname, tz_from, tz_to, &options)?;
         }
 
         "renamecol" => {
-            check_data_loaded(controller, "renamecol");
+            check_data_loaded(controller, "renamecol")?;
             if cmd.args.len() < 2 {
-                eprintln!("Error: 'renamecol' command requires the current column name and the new column name.");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'renamecol' command requires the current column name and the new column name."
+                        .to_string(),
+                ));
             }
             let colname = &cmd.args[0];
             let new_colname = &cmd.args[1];
-            controller.renamecol(colname, new_colname);
+            controller.renamecol(colname, new_colname)?;
         }
 
         "convert" => {
-            check_data_loaded(controller, "convert");
+            check_data_loaded(controller, "convert")?;
             if cmd.args.is_empty() {
-                eprintln!("Error: 'convert' command requires a column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'convert' command requires a column name".to_string(),
+                ));
             }
             let colname = &cmd.args[0];
 
             let from_format = match cmd.options.get("from") {
                 Some(Some(format)) => format,
                 _ => {
-                    eprintln!("Error: 'convert' command requires --from option");
-                    process::exit(1);
+                    return Err(QsvError::InvalidArgument(
+                        "'convert' command requires --from option".to_string(),
+                    ));
                 }
             };
 
             let to_format = match cmd.options.get("to") {
                 Some(Some(format)) => format,
                 _ => {
-                    eprintln!("Error: 'convert' command requires --to option");
-                    process::exit(1);
+                    return Err(QsvError::InvalidArgument(
+                        "'convert' command requires --to option".to_string(),
+                    ));
                 }
             };
 
-            controller.convert(colname, from_format, to_format);
+            controller.convert(colname, from_format, to_format)?;
         }
 
         "timeline" => {
-            check_data_loaded(controller, "timeline");
+            check_data_loaded(controller, "timeline")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'timeline' command requires a time column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'timeline' command requires a time column name".to_string(),
+                ));
             }
 
             let time_column = &cmd.args[0];
@@ -483,35 +717,48 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             let interval = match cmd.options.get("interval") {
                 Some(Some(interval)) => interval,
                 _ => {
-                    eprintln!("Error: 'timeline' command requires --interval option (e.g., --interval 1h)");
-                    process::exit(1);
+                    return Err(QsvError::InvalidArgument(
+                        "'timeline' command requires --interval option (e.g., --interval 1h)"
+                            .to_string(),
+                    ));
                 }
             };
 
-            // Determine aggregation type and column
-            let (agg_type, agg_column) = if let Some(Some(col)) = cmd.options.get("sum") {
-                ("sum", Some(col.as_str()))
-            } else if let Some(Some(col)) = cmd.options.get("avg") {
-                ("avg", Some(col.as_str()))
-            } else if let Some(Some(col)) = cmd.options.get("min") {
-                ("min", Some(col.as_str()))
-            } else if let Some(Some(col)) = cmd.options.get("max") {
-                ("max", Some(col.as_str()))
-            } else if let Some(Some(col)) = cmd.options.get("std") {
-                ("std", Some(col.as_str()))
-            } else {
-                ("count", None) // Default to count
+            // Each of --sum, --avg, --min, --max and --std takes a comma-separated column list
+            let mut aggregations = Vec::new();
+            for agg_type in ["sum", "avg", "min", "max", "std"] {
+                if let Some(Some(columns)) = cmd.options.get(agg_type) {
+                    aggregations.extend(
+                        columns
+                            .split(',')
+                            .map(|c| c.trim())
+                            .filter(|c| !c.is_empty())
+                            .map(|c| (agg_type.to_string(), c.to_string())),
+                    );
+                }
+            }
+            let option = |key: &str| cmd.options.get(key).and_then(|opt| opt.clone());
+            let options = TimelineOptions {
+                aggregations,
+                agg: option("agg").into_iter().collect(),
+                by: option("by"),
+                fill: cmd.options.contains_key("fill"),
+                start: option("start"),
+                end: option("end"),
+                week_start: option("week_start"),
+                tz: option("tz"),
             };
 
-            controller.timeline(time_column, interval, agg_type, agg_column);
+            controller.timeline(time_column, interval, &options, &datetime_parser(cmd)?)?;
         }
 
         "timeslice" => {
-            check_data_loaded(controller, "timeslice");
+            check_data_loaded(controller, "timeslice")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'timeslice' command requires a time column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'timeslice' command requires a time column name".to_string(),
+                ));
             }
 
             let time_column = &cmd.args[0];
@@ -520,21 +767,22 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             let end_time = cmd.options.get("end").and_then(|opt| opt.as_deref());
 
             if start_time.is_none() && end_time.is_none() {
-                eprintln!(
-                    "Error: 'timeslice' command requires at least one of --start or --end options"
-                );
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'timeslice' command requires at least one of --start or --end options"
+                        .to_string(),
+                ));
             }
 
-            controller.timeslice(time_column, start_time, end_time);
+            controller.timeslice(time_column, start_time, end_time, &datetime_parser(cmd)?)?;
         }
 
         "partition" => {
-            check_data_loaded(controller, "partition");
+            check_data_loaded(controller, "partition")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'partition' command requires a column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'partition' command requires a column name".to_string(),
+                ));
             }
 
             let colname = &cmd.args[0];
@@ -544,11 +792,18 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 "./partitions"
             };
 
-            controller.partition(colname, output_dir);
+            let compression = cmd
+                .options
+                .get("compression")
+                .and_then(|opt| opt.as_deref())
+                .map(Compression::from_name)
+                .transpose()?;
+
+            controller.partition(colname, output_dir, compression)?;
         }
 
         "pivot" => {
-            check_data_loaded(controller, "pivot");
+            check_data_loaded(controller, "pivot")?;
 
             let rows_str = cmd
                 .options
@@ -564,21 +819,21 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 .options
                 .get("values")
                 .and_then(|opt| opt.as_deref())
-                .unwrap_or_else(|| {
-                    eprintln!("Error: 'pivot' command requires --values option");
-                    process::exit(1);
-                });
-            let agg_func = cmd
+                .ok_or_else(|| {
+                    QsvError::InvalidArgument(
+                        "'pivot' command requires --values option".to_string(),
+                    )
+                })?;
+            let agg_str = cmd
                 .options
                 .get("agg")
                 .and_then(|opt| opt.as_deref())
                 .unwrap_or("sum");
 
             if rows_str.is_empty() && cols_str.is_empty() {
-                eprintln!(
-                    "Error: 'pivot' command requires at least one of --rows or --cols options"
-                );
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'pivot' command requires at least one of --rows or --cols options".to_string(),
+                ));
             }
 
             let rows: Vec<String> = if rows_str.is_empty() {
@@ -593,15 +848,49 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 cols_str.split(',').map(|s| s.trim().to_string()).collect()
             };
 
-            controller.pivot(&rows, &columns, values, agg_func);
+            let values: Vec<String> = values.split(',').map(|s| s.trim().to_string()).collect();
+            let agg_funcs: Vec<String> = agg_str.split(',').map(|s| s.trim().to_string()).collect();
+            let options = PivotOptions {
+                fill: cmd.options.get("fill").and_then(|opt| opt.clone()),
+                row_totals: cmd.options.contains_key("row_totals"),
+                col_totals: cmd.options.contains_key("col_totals"),
+            };
+
+            controller.pivot(&rows, &columns, &values, &agg_funcs, &options)?;
+        }
+
+        "melt" => {
+            check_data_loaded(controller, "melt")?;
+
+            let id_cols = match cmd.options.get("id").and_then(|opt| opt.as_deref()) {
+                Some(ids) => parse_column_names(ids)?,
+                None => Vec::new(),
+            };
+            let value_cols = match cmd.options.get("value_cols").and_then(|opt| opt.as_deref()) {
+                Some(cols) => parse_column_names(cols)?,
+                None => Vec::new(),
+            };
+            let variable_name = cmd
+                .options
+                .get("variable_name")
+                .and_then(|opt| opt.as_deref())
+                .unwrap_or(MELT_VARIABLE_NAME);
+            let value_name = cmd
+                .options
+                .get("value_name")
+                .and_then(|opt| opt.as_deref())
+                .unwrap_or(MELT_VALUE_NAME);
+
+            controller.melt(&id_cols, &value_cols, variable_name, value_name)?;
         }
 
         "timeround" => {
-            check_data_loaded(controller, "timeround");
+            check_data_loaded(controller, "timeround")?;
 
             if cmd.args.is_empty() {
-                eprintln!("Error: 'timeround' command requires a column name");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'timeround' command requires a column name".to_string(),
+                ));
             }
 
             let colname = &cmd.args[0];
@@ -610,21 +899,44 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 .options
                 .get("unit")
                 .and_then(|opt| opt.as_deref())
-                .unwrap_or_else(|| {
-                    eprintln!("Error: 'timeround' command requires --unit option (e.g., --unit d)");
-                    process::exit(1);
-                });
+                .ok_or_else(|| {
+                    QsvError::InvalidArgument(
+                        "'timeround' command requires --unit option (e.g., --unit d)".to_string(),
+                    )
+                })?;
 
             let output_colname = cmd.options.get("output").and_then(|opt| opt.as_deref());
 
-            controller.timeround(colname, unit, output_colname);
+            controller.timeround(colname, unit, output_colname, &datetime_parser(cmd)?)?;
+        }
+
+        "todatetime" => {
+            check_data_loaded(controller, "todatetime")?;
+
+            if cmd.args.is_empty() {
+                return Err(QsvError::InvalidArgument(
+                    "'todatetime' command requires a column name".to_string(),
+                ));
+            }
+
+            let colname = &cmd.args[0];
+            let tz = cmd.options.get("tz").and_then(|opt| opt.as_deref());
+
+            controller.todatetime(colname, tz, &datetime_parser(cmd)?)?;
         }
 
         // Quilters
         "quilt" => {
+            if cmd.options.contains_key("schema") {
+                let schema = serde_json::to_string_pretty(&quilt_json_schema())
+                    .map_err(|e| QsvError::Io(format!("writing quilt schema: {e}")))?;
+                println!("{schema}");
+                return Ok(());
+            }
             if cmd.args.is_empty() {
-                eprintln!("Error: 'quilt' command requires a config_path argument.");
-                process::exit(1);
+                return Err(QsvError::InvalidArgument(
+                    "'quilt' command requires a config_path argument.".to_string(),
+                ));
             }
             let config_path_str = &cmd.args[0];
 
@@ -640,57 +952,119 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
             };
 
             let output_path_str = cmd.options.get("output").and_then(|o| o.as_deref());
+            let mut options = QuiltOptions::default();
+            for spec in cmd.repeated_options.get("param").into_iter().flatten() {
+                let (name, value) = parse_param(spec)?;
+                options.params.insert(name, value);
+            }
+            if let Some(jobs) = cmd.options.get("jobs").and_then(|v| v.as_deref()) {
+                options.max_parallel = match jobs.parse::<usize>() {
+                    Ok(n) if n > 0 => Some(n),
+                    _ => {
+                        return Err(QsvError::InvalidArgument(format!(
+                            "Invalid value '{jobs}' for --jobs: expected a positive integer"
+                        )))
+                    }
+                };
+            }
+
+            if cmd.options.contains_key("validate") {
+                let problems = validate_quilt(config_path_str, &options)?;
+                if !problems.is_empty() {
+                    return Err(invalid_quilt(&problems));
+                }
+                println!("Quilt '{config_path_str}' is valid");
+                return Ok(());
+            }
 
             // quilt operation is destructive / stateful for the controller for now
-            operations::quilters::quilt::quilt(
+            qsv::quilt(
                 controller,
                 config_path_str,
                 cli_input_files,
                 output_path_str,
-            );
+                &options,
+            )?;
         }
 
         // Finalizers
         "showtable" => {
-            check_data_loaded(controller, "showtable");
-            controller.showtable();
+            check_data_loaded(controller, "showtable")?;
+            controller.showtable()?;
+        }
+
+        "chart" => {
+            check_data_loaded(controller, "chart")?;
+
+            let column = cmd.args.first().ok_or_else(|| {
+                QsvError::InvalidArgument(
+                    "'chart' command requires a numeric column to draw".to_string(),
+                )
+            })?;
+            let option = |key: &str| cmd.options.get(key).and_then(|opt| opt.clone());
+            let size = |key: &str| -> Result<Option<usize>, QsvError> {
+                option(key)
+                    .map(|value| {
+                        value.parse::<usize>().map_err(|_| {
+                            QsvError::InvalidArgument(format!(
+                                "Invalid value '{value}' for --{key}: expected a positive integer"
+                            ))
+                        })
+                    })
+                    .transpose()
+            };
+            let defaults = ChartOptions::default();
+            let options = ChartOptions {
+                chart_type: option("type")
+                    .map(|name| ChartType::from_name(&name))
+                    .transpose()?
+                    .unwrap_or_default(),
+                x: option("x"),
+                by: option("by"),
+                width: size("width")?.unwrap_or(defaults.width),
+                height: size("height")?,
+                bins: size("bins")?.unwrap_or(defaults.bins),
+                log: cmd.options.contains_key("log"),
+                ascii: cmd.options.contains_key("ascii"),
+            };
+
+            controller.chart(column, &options)?;
         }
 
         "headers" => {
-            check_data_loaded(controller, "headers");
+            check_data_loaded(controller, "headers")?;
             let plain = cmd.options.contains_key("plain");
-            controller.headers(plain);
+            controller.headers(plain)?;
         }
 
         "show" => {
-            check_data_loaded(controller, "show");
+            check_data_loaded(controller, "show")?;
             if let Some(batch_size_str) = cmd.options.get("batch_size").and_then(|v| v.as_ref()) {
                 match parse_batch_size(batch_size_str) {
                     Ok(batch_size) => {
-                        controller.show_with_batch_size(batch_size);
+                        controller.show_with_batch_size(batch_size)?;
                     }
                     Err(e) => {
-                        eprintln!("Error parsing batch-size: {e}");
-                        process::exit(1);
+                        return Err(QsvError::Parse(format!("batch-size: {e}")));
                     }
                 }
             } else {
-                controller.show();
+                controller.show()?;
             }
         }
 
         "stats" => {
-            check_data_loaded(controller, "stats");
-            controller.stats();
+            check_data_loaded(controller, "stats")?;
+            controller.stats()?;
         }
 
         "showquery" => {
-            check_data_loaded(controller, "showquery");
-            controller.showquery();
+            check_data_loaded(controller, "showquery")?;
+            controller.showquery()?;
         }
 
         "dump" => {
-            check_data_loaded(controller, "dump");
+            check_data_loaded(controller, "dump")?;
 
             let output_path = cmd
                 .options
@@ -703,8 +1077,14 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                 .get("separator")
                 .or_else(|| cmd.options.get("s"))
                 .and_then(|v| v.as_ref())
-                .and_then(|s| s.chars().next())
-                .unwrap_or(',');
+                .and_then(|s| s.chars().next());
+
+            let format = cmd
+                .options
+                .get("format")
+                .and_then(|v| v.as_ref())
+                .map(|f| parse_dump_format(f))
+                .transpose()?;
 
             if let Some(batch_size_str) = cmd.options.get("batch_size").and_then(|v| v.as_ref()) {
                 match parse_batch_size(batch_size_str) {
@@ -712,32 +1092,35 @@ fn process_command(controller: &mut DataFrameController, cmd: &Command) {
                         controller.dump_with_batch_size(
                             output_path.map(|s| s.as_str()),
                             separator,
+                            format,
                             batch_size,
-                        );
+                        )?;
                     }
                     Err(e) => {
-                        eprintln!("Error parsing batch_size: {e}");
-                        process::exit(1);
+                        return Err(QsvError::Parse(format!("batch_size: {e}")));
                     }
                 }
             } else {
-                controller.dump(output_path.map(|s| s.as_str()), Some(separator));
+                controller.dump(output_path.map(|s| s.as_str()), separator, format)?;
             }
         }
         "dumpcache" => {
-            check_data_loaded(controller, "dumpcache");
+            check_data_loaded(controller, "dumpcache")?;
             let output_path = cmd
                 .options
                 .get("output")
                 .and_then(|opt_val| opt_val.as_deref());
-            controller.dumpcache(output_path);
+            controller.dumpcache(output_path)?;
         }
 
         // Unsupported commands
         _ => {
-            eprintln!("Error: Unknown command '{}'", cmd.name);
             print_help();
-            process::exit(1);
+            return Err(QsvError::InvalidArgument(format!(
+                "Unknown command '{}'",
+                cmd.name
+            )));
         }
     }
+    Ok(())
 }
@@ -1,165 +1,40 @@
+use crate::controllers::datetime::{parse_timezone, polars_timezone, DatetimeParser};
 use crate::controllers::log::LogController;
-use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use crate::error::QsvError;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Tz;
-use dtparse::parse as dtparse_parse;
-use once_cell::sync::Lazy;
 use polars::prelude::*;
-use regex::Regex;
 
-static FUZZY_DATETIME_PATTERNS: Lazy<Vec<Regex>> = Lazy::new(|| {
-    let patterns = [
-        // Month name with day and year: "January 15th, 2023 at 2:30 PM"
-        r"(?i)(?:on\s+)?(?:january|february|march|april|may|june|july|august|september|october|november|december)\s+\d{1,2}(?:st|nd|rd|th)?,?\s+\d{4}(?:\s+at\s+)?\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
-        // Short month: "Jan 15, 2023 2:30 PM"
-        r"(?i)(?:on\s+)?(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)\s+\d{1,2},?\s+\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
-        // ISO-like in text: "2023-01-15 14:30:00"
-        r"\d{4}-\d{1,2}-\d{1,2}\s+\d{1,2}:\d{2}(?::\d{2})?",
-        // US date format: "1/15/2023 2:30 PM"
-        r"\d{1,2}/\d{1,2}/\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
-        // Day month year: "Friday Jan 13 2023 9:00 AM"
-        r"(?i)(?:monday|tuesday|wednesday|thursday|friday|saturday|sunday)\s+(?:jan|feb|mar|apr|may|jun|jul|aug|sep|oct|nov|dec)\s+\d{1,2}\s+\d{4}\s+\d{1,2}:\d{2}(?::\d{2})?\s*(?:AM|PM)?",
-    ];
-    patterns.iter().map(|p| Regex::new(p).unwrap()).collect()
-});
-
-/// Parse datetime string with comprehensive format support including fuzzy parsing
-fn parse_datetime_auto(s: &str) -> Option<NaiveDateTime> {
-    let s = s.trim();
-    if s.is_empty() {
-        return None;
-    }
-    // First try dtparse for maximum flexibility (similar to Python dateutil.parser)
-    match dtparse_parse(s) {
-        Ok((dt, _)) => {
-            LogController::debug(&format!("Successfully parsed '{s}' using dtparse"));
-            return Some(dt);
-        }
-        Err(e) => {
-            LogController::debug(&format!("dtparse failed for '{s}': {e}"));
-        }
-    }
-    // Try fuzzy parsing with regex extraction
-    if let Some(extracted) = extract_datetime_fuzzy(s) {
-        LogController::debug(&format!(
-            "Extracted datetime '{extracted}' from fuzzy text '{s}'"
-        ));
-        if let Some(dt) = parse_extracted_datetime(&extracted) {
-            return Some(dt);
-        }
-    }
-    // Fallback to manual format detection for edge cases
-    let formats = [
-        // ISO 8601 formats
-        "%Y-%m-%dT%H:%M:%S%.f",
-        "%Y-%m-%dT%H:%M:%S",
-        "%Y-%m-%d %H:%M:%S%.f",
-        "%Y-%m-%d %H:%M:%S",
-        "%Y-%m-%d",
-        // US formats
-        "%m/%d/%Y %H:%M:%S%.f",
-        "%m/%d/%Y %H:%M:%S",
-        "%m/%d/%Y",
-        // EU formats
-        "%d/%m/%Y %H:%M:%S%.f",
-        "%d/%m/%Y %H:%M:%S",
-        "%d/%m/%Y",
-        // Alternative separators
-        "%Y/%m/%d %H:%M:%S%.f",
-        "%Y/%m/%d %H:%M:%S",
-        "%Y/%m/%d",
-        // Month name formats (common in logs)
-        "%d %b %Y %H:%M:%S", // 15 Jan 2023 14:30:25
-        "%b %d %Y %H:%M:%S", // Jan 15 2023 14:30:25
-        "%d %B %Y %H:%M:%S", // 15 January 2023 14:30:25
-        "%B %d %Y %H:%M:%S", // January 15 2023 14:30:25
-        "%d-%b-%Y %H:%M:%S", // 15-Jan-2023 14:30:25
-        "%d %b %Y",          // 15 Jan 2023
-        "%b %d %Y",          // Jan 15 2023
-        // Log formats
-        "%a %b %d %H:%M:%S %Y",  // Mon Jan 15 14:30:25 2023
-        "%a, %d %b %Y %H:%M:%S", // Mon, 15 Jan 2023 14:30:25
-        // Unix timestamp formats
-        "%s",    // 1674659425
-        "%s%.f", // 1674659425.123
-        // Windows Event Log formats
-        "%m/%d/%Y %I:%M:%S %p", // 1/15/2023 2:30:25 PM
-        "%Y-%m-%d %I:%M:%S %p", // 2023-01-15 2:30:25 PM
-    ];
-    for fmt in &formats {
-        if let Ok(dt) = NaiveDateTime::parse_from_str(s, fmt) {
-            LogController::debug(&format!("Parsed '{s}' with format '{fmt}'"));
-            return Some(dt);
-        }
-    }
-    LogController::warn(&format!("Failed to parse datetime: '{s}'"));
-    None
+/// How `changetz` reads and writes datetime values
+#[derive(Debug, Clone)]
+pub struct ChangetzOptions {
+    /// How input values are parsed
+    pub parser: DatetimeParser,
+    /// strftime format of a text output, or `auto` to keep a datetime column in the target
+    /// zone
+    pub output_format: String,
+    /// `earliest` or `latest` occurrence of times repeated by DST transitions
+    pub ambiguous: String,
 }
-/// Extract datetime patterns from fuzzy text using regex
-fn extract_datetime_fuzzy(text: &str) -> Option<String> {
-    for re in FUZZY_DATETIME_PATTERNS.iter() {
-        if let Some(captures) = re.find(text) {
-            return Some(captures.as_str().to_string());
-        }
-    }
-    None
-}
-/// Parse extracted datetime string using multiple formats
-fn parse_extracted_datetime(extracted: &str) -> Option<NaiveDateTime> {
-    // Clean up the extracted string
-    let cleaned = extracted
-        .replace(" at ", " ")
-        .replace("st,", ",")
-        .replace("nd,", ",")
-        .replace("rd,", ",")
-        .replace("th,", ",")
-        .replace("st ", " ")
-        .replace("nd ", " ")
-        .replace("rd ", " ")
-        .replace("th ", " ");
-    // Try dtparse again on the cleaned extracted text
-    if let Ok((dt, _)) = dtparse_parse(&cleaned) {
-        LogController::debug(&format!("Parsed extracted '{cleaned}' using dtparse"));
-        return Some(dt);
-    }
-    // Formats specifically for extracted patterns
-    let formats = [
-        "%B %d, %Y %I:%M:%S %p",   // January 15, 2023 2:30:00 PM
-        "%B %d, %Y %I:%M %p",      // January 15, 2023 2:30 PM
-        "%b %d, %Y %I:%M:%S %p",   // Jan 15, 2023 2:30:00 PM
-        "%b %d, %Y %I:%M %p",      // Jan 15, 2023 2:30 PM
-        "%Y-%m-%d %H:%M:%S",       // 2023-01-15 14:30:00
-        "%Y-%m-%d %H:%M",          // 2023-01-15 14:30
-        "%m/%d/%Y %I:%M:%S %p",    // 1/15/2023 2:30:00 PM
-        "%m/%d/%Y %I:%M %p",       // 1/15/2023 2:30 PM
-        "%A %b %d %Y %I:%M:%S %p", // Friday Jan 13 2023 9:00:00 AM
-        "%A %b %d %Y %I:%M %p",    // Friday Jan 13 2023 9:00 AM
-    ];
-    for fmt in &formats {
-        if let Ok(dt) = NaiveDateTime::parse_from_str(&cleaned, fmt) {
-            LogController::debug(&format!("Parsed extracted '{cleaned}' with format '{fmt}'"));
-            return Some(dt);
+
+impl Default for ChangetzOptions {
+    fn default() -> Self {
+        Self {
+            parser: DatetimeParser::default(),
+            output_format: "auto".to_string(),
+            ambiguous: "earliest".to_string(),
         }
     }
-    None
 }
+
 /// Convert timezone with proper error handling
 fn convert_timezone(
-    datetime_str: &str,
+    naive_dt: NaiveDateTime,
     from_tz: &str,
-    to_tz: &str,
-    input_format: &str,
-    output_format: &str,
-    ambiguous: &str,
-) -> Option<String> {
-    if datetime_str.trim().is_empty() {
-        return Some(String::new());
-    }
-    // Parse datetime
-    let naive_dt = if input_format == "auto" {
-        parse_datetime_auto(datetime_str)?
-    } else {
-        NaiveDateTime::parse_from_str(datetime_str, input_format).ok()?
-    };
+    to_tz: Tz,
+    options: &ChangetzOptions,
+) -> Option<DateTime<Tz>> {
+    let ambiguous = options.ambiguous.as_str();
     // Handle source timezone
     let utc_dt = if from_tz.to_lowercase() == "local" {
         match Local.from_local_datetime(&naive_dt) {
@@ -180,74 +55,79 @@ fn convert_timezone(
         }
     };
     // Convert to target timezone
-    let to_tz_parsed: Tz = to_tz.parse().ok()?;
-    let target_dt = utc_dt.with_timezone(&to_tz_parsed);
-    // Format output
-    let format = if output_format == "auto" {
-        "%Y-%m-%dT%H:%M:%S%.6f%:z" // ISO8601 with microsecond precision
-    } else {
-        output_format
-    };
-    Some(target_dt.format(format).to_string())
+    Some(utc_dt.with_timezone(&to_tz))
 }
 pub fn changetz(
     df: &LazyFrame,
     colname: &str,
     from_tz: &str,
     to_tz: &str,
-    input_format: &str,
-    output_format: &str,
-    ambiguous_time: &str,
-) -> LazyFrame {
+    options: &ChangetzOptions,
+) -> Result<LazyFrame, QsvError> {
     // Validate column exists by checking the schema
-    if df.clone().collect_schema().unwrap().get(colname).is_none() {
-        eprintln!("Error: Column '{colname}' not found for changetz operation");
-        std::process::exit(1);
-    }
+    let schema = df.clone().collect_schema()?;
+    let dtype = schema
+        .get(colname)
+        .ok_or_else(|| QsvError::column_not_found(colname, "changetz"))?;
 
     // Validate timezones
     if from_tz.to_lowercase() != "local" && from_tz.parse::<Tz>().is_err() {
-        eprintln!("Error: Invalid source timezone '{from_tz}'");
-        std::process::exit(1);
-    }
-    if to_tz.parse::<Tz>().is_err() {
-        eprintln!("Error: Invalid target timezone '{to_tz}'");
-        std::process::exit(1);
+        return Err(QsvError::InvalidArgument(format!(
+            "Invalid source timezone '{from_tz}'"
+        )));
     }
+    let to_tz = parse_timezone(to_tz)
+        .map_err(|_| QsvError::InvalidArgument(format!("Invalid target timezone '{to_tz}'")))?;
+    // Zoned datetimes hold UTC instants whatever --from-tz says
+    let from_tz = match dtype {
+        DataType::Datetime(_, Some(zone)) => {
+            LogController::debug(&format!(
+                "Column '{colname}' is already in {zone}; ignoring --from-tz {from_tz}"
+            ));
+            "UTC"
+        }
+        _ => from_tz,
+    };
     LogController::debug(&format!(
-        "Converting timezone for column '{colname}': {from_tz} → {to_tz} (format: {input_format} → {output_format}, ambiguous: {ambiguous_time})"
+        "Converting timezone for column '{colname}': {from_tz} → {to_tz} (input: {:?}, output format: {}, ambiguous: {})",
+        options.parser, options.output_format, options.ambiguous
     ));
+    // Without an explicit output format the column stays a datetime, in the target zone
+    let output_dtype = if options.output_format == "auto" {
+        DataType::Datetime(TimeUnit::Microseconds, Some(polars_timezone(to_tz)?))
+    } else {
+        DataType::String
+    };
     // Clone parameters for closure
     let from_tz = from_tz.to_string();
-    let to_tz = to_tz.to_string();
-    let input_format = input_format.to_string();
-    let output_format = output_format.to_string();
-    let ambiguous_time = ambiguous_time.to_string();
+    let options = options.clone();
+    let output = output_dtype.clone();
     // Apply timezone conversion
-    df.clone().with_column(
+    Ok(df.clone().with_column(
         col(colname)
             .map(
                 move |s| {
-                    let ca = s.str()?;
-                    let converted: StringChunked = ca
+                    let converted = options
+                        .parser
+                        .parse_column(&s, "changetz")?
                         .into_iter()
-                        .map(|opt_str| {
-                            opt_str.and_then(|datetime_str| {
-                                convert_timezone(
-                                    datetime_str,
-                                    &from_tz,
-                                    &to_tz,
-                                    &input_format,
-                                    &output_format,
-                                    &ambiguous_time,
-                                )
-                            })
-                        })
-                        .collect();
-                    Ok(Some(converted.into_series().into()))
+                        .map(|dt| convert_timezone(dt?, &from_tz, to_tz, &options));
+                    let converted = if output == DataType::String {
+                        converted
+                            .map(|dt| Some(dt?.format(&options.output_format).to_string()))
+                            .collect::<StringChunked>()
+                            .into_series()
+                    } else {
+                        converted
+                            .map(|dt| Some(dt?.timestamp_micros()))
+                            .collect::<Int64Chunked>()
+                            .into_series()
+                            .cast(&output)?
+                    };
+                    Ok(Some(converted.with_name(s.name().clone()).into()))
                 },
-                GetOutput::from_type(DataType::String),
+                GetOutput::from_type(output_dtype),
             )
             .alias(colname),
-    )
+    ))
 }
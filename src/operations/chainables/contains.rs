@@ -1,19 +1,18 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 use regex;
 
-pub fn contains(df: &LazyFrame, colname: &str, pattern: &str, ignorecase: bool) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for contains operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn contains(
+    df: &LazyFrame,
+    colname: &str,
+    pattern: &str,
+    ignorecase: bool,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     if !schema.iter_names().any(|s| s == colname) {
-        eprintln!("Error: Column '{colname}' not found in DataFrame for contains operation");
-        std::process::exit(1);
+        return Err(QsvError::column_not_found(colname, "contains"));
     }
 
     LogController::debug(&format!(
@@ -36,5 +35,5 @@ pub fn contains(df: &LazyFrame, colname: &str, pattern: &str, ignorecase: bool)
             .contains(lit(pattern), true) // literal=true for exact string match
     };
 
-    df.clone().filter(expr)
+    Ok(df.clone().filter(expr))
 }
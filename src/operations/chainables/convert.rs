@@ -1,25 +1,24 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 use serde_json::Value as JsonValue;
 use serde_xml_rs::from_str as xml_from_str;
 use serde_yml;
 
-pub fn convert(df: &LazyFrame, colname: &str, from_format: &str, to_format: &str) -> LazyFrame {
+pub fn convert(
+    df: &LazyFrame,
+    colname: &str,
+    from_format: &str,
+    to_format: &str,
+) -> Result<LazyFrame, QsvError> {
     LogController::debug(&format!(
         "Converting column '{colname}' from {from_format} to {to_format}"
     ));
 
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for convert operation: {e}");
-            std::process::exit(1);
-        }
-    };
+    let schema = df.clone().collect_schema()?;
 
     if !schema.iter_names().any(|s| s == colname) {
-        eprintln!("Error: Column '{colname}' not found in DataFrame for convert operation");
-        std::process::exit(1);
+        return Err(QsvError::column_not_found(colname, "convert"));
     }
 
     // Create the conversion expression - replace the original column
@@ -47,7 +46,7 @@ pub fn convert(df: &LazyFrame, colname: &str, from_format: &str, to_format: &str
             GetOutput::from_type(DataType::String),
         )
         .alias(colname); // Use original column name to replace it
-    df.clone().with_column(conversion_expr)
+    Ok(df.clone().with_column(conversion_expr))
 }
 fn convert_format(input_str: &str, from_format: &str, to_format: &str) -> String {
     match (
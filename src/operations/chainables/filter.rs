@@ -0,0 +1,472 @@
+use crate::controllers::datetime::{bound_for, parse_datetime_auto, DatetimeParser};
+use crate::controllers::expr::typed_literal;
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use once_cell::sync::Lazy;
+use polars::prelude::*;
+use regex::Regex;
+
+// Unquoted date/datetime literals: 2023-01-01, 2023-01-01T12:00:00, 2023-01-01T12:00:00.123Z
+static RE_DATETIME_LITERAL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^\d{4}-\d{2}-\d{2}(T\d{2}:\d{2}(:\d{2}(\.\d+)?)?Z?)?$").unwrap());
+
+pub fn filter(df: &LazyFrame, expression: &str) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
+
+    LogController::debug(&format!("Applying filter: expression='{expression}'"));
+
+    let node = parse_expression(expression)
+        .map_err(|e| QsvError::Parse(format!("filter expression '{expression}': {e}")))?;
+    let predicate = compile(&node, &schema)?;
+
+    Ok(df.clone().filter(predicate))
+}
+
+// -- tokenizer --
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Column(String), // `quoted column`
+    Str(String),
+    Number(String),
+    DateTime(String),
+    Op(&'static str),
+    LParen,
+    RParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        match c {
+            '(' => {
+                tokens.push(Token::LParen);
+                i += 1;
+            }
+            ')' => {
+                tokens.push(Token::RParen);
+                i += 1;
+            }
+            '"' | '\'' => {
+                let (text, next) = read_quoted(&chars, i)?;
+                tokens.push(Token::Str(text));
+                i = next;
+            }
+            '`' => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|ch| *ch == '`')
+                    .ok_or("unterminated `quoted` column name")?;
+                tokens.push(Token::Column(chars[i + 1..i + 1 + end].iter().collect()));
+                i += end + 2;
+            }
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let next = chars.get(i + 1).copied();
+                let op = match (c, next) {
+                    ('=', Some('=')) => "==",
+                    ('=', Some('~')) => "=~",
+                    ('=', _) => "=",
+                    ('!', Some('=')) => "!=",
+                    ('!', Some('~')) => "!~",
+                    ('!', _) => "!",
+                    ('<', Some('=')) => "<=",
+                    ('<', Some('>')) => "<>",
+                    ('<', _) => "<",
+                    ('>', Some('=')) => ">=",
+                    ('>', _) => ">",
+                    ('&', Some('&')) => "&&",
+                    ('|', Some('|')) => "||",
+                    _ => return Err(format!("unexpected character '{c}'")),
+                };
+                tokens.push(Token::Op(op));
+                i += op.len();
+            }
+            _ if c.is_ascii_digit() || is_signed_number_start(&chars, i, &tokens) => {
+                let start = i;
+                i += 1;
+                while i < chars.len()
+                    && (chars[i].is_ascii_alphanumeric()
+                        || matches!(chars[i], '.' | ':' | '-' | '+'))
+                {
+                    i += 1;
+                }
+                let text: String = chars[start..i].iter().collect();
+                if RE_DATETIME_LITERAL.is_match(&text) {
+                    tokens.push(Token::DateTime(text));
+                } else {
+                    tokens.push(Token::Number(text));
+                }
+            }
+            _ if c.is_alphabetic() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.'))
+                {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+                // dt"2023-01-01 12:00:00" is a datetime literal
+                if word == "dt" && matches!(chars.get(i), Some('"') | Some('\'')) {
+                    let (text, next) = read_quoted(&chars, i)?;
+                    tokens.push(Token::DateTime(text));
+                    i = next;
+                } else {
+                    tokens.push(Token::Ident(word));
+                }
+            }
+            _ => return Err(format!("unexpected character '{c}'")),
+        }
+    }
+    Ok(tokens)
+}
+
+// A leading '-' or '+' is a sign only where an operand is expected
+fn is_signed_number_start(chars: &[char], i: usize, tokens: &[Token]) -> bool {
+    matches!(chars[i], '-' | '+')
+        && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())
+        && !matches!(
+            tokens.last(),
+            Some(Token::Ident(_))
+                | Some(Token::Column(_))
+                | Some(Token::Str(_))
+                | Some(Token::Number(_))
+                | Some(Token::DateTime(_))
+                | Some(Token::RParen)
+        )
+}
+
+fn read_quoted(chars: &[char], start: usize) -> Result<(String, usize), String> {
+    let quote = chars[start];
+    let mut text = String::new();
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if i + 1 < chars.len() => {
+                text.push(match chars[i + 1] {
+                    'n' => '\n',
+                    't' => '\t',
+                    other => other,
+                });
+                i += 2;
+            }
+            c if c == quote => return Ok((text, i + 1)),
+            c => {
+                text.push(c);
+                i += 1;
+            }
+        }
+    }
+    Err("unterminated string literal".to_string())
+}
+
+// -- parser --
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CmpOp {
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Column(String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    DateTime(String),
+    Bool(bool),
+    Null,
+    Not(Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Compare(Box<Node>, CmpOp, Box<Node>),
+    IsNull(Box<Node>, bool),
+    Regex(Box<Node>, String, bool),
+}
+
+fn parse_expression(input: &str) -> Result<Node, String> {
+    let tokens = tokenize(input)?;
+    if tokens.is_empty() {
+        return Err("empty expression".to_string());
+    }
+    let mut parser = Parser { tokens, pos: 0 };
+    let node = parser.parse_or()?;
+    match parser.peek() {
+        None => Ok(node),
+        Some(token) => Err(format!("unexpected {}", describe(token))),
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Ident(w)) if w.eq_ignore_ascii_case(keyword)) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_op(&mut self, ops: &[&str]) -> Option<&'static str> {
+        match self.peek() {
+            Some(Token::Op(op)) if ops.contains(op) => {
+                let op = *op;
+                self.pos += 1;
+                Some(op)
+            }
+            _ => None,
+        }
+    }
+
+    // or := and (("or" | "||") and)*
+    fn parse_or(&mut self) -> Result<Node, String> {
+        let mut node = self.parse_and()?;
+        while self.eat_keyword("or") || self.eat_op(&["||"]).is_some() {
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Ok(node)
+    }
+
+    // and := not (("and" | "&&") not)*
+    fn parse_and(&mut self) -> Result<Node, String> {
+        let mut node = self.parse_not()?;
+        while self.eat_keyword("and") || self.eat_op(&["&&"]).is_some() {
+            node = Node::And(Box::new(node), Box::new(self.parse_not()?));
+        }
+        Ok(node)
+    }
+
+    // not := ("not" | "!") not | comparison
+    fn parse_not(&mut self) -> Result<Node, String> {
+        if self.eat_keyword("not") || self.eat_op(&["!"]).is_some() {
+            return Ok(Node::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    // comparison := primary [cmp primary | "is" ["not"] "null" | ("=~" | "!~") string]
+    fn parse_comparison(&mut self) -> Result<Node, String> {
+        let left = self.parse_primary()?;
+        if self.eat_keyword("is") {
+            let negated = self.eat_keyword("not");
+            if !self.eat_keyword("null") {
+                return Err("expected 'null' after 'is'".to_string());
+            }
+            return Ok(Node::IsNull(Box::new(left), negated));
+        }
+        if let Some(op) = self.eat_op(&["=~", "!~"]) {
+            return match self.next() {
+                Some(Token::Str(pattern)) => {
+                    if let Err(e) = Regex::new(&pattern) {
+                        return Err(format!("invalid regex '{pattern}': {e}"));
+                    }
+                    Ok(Node::Regex(Box::new(left), pattern, op == "!~"))
+                }
+                _ => Err(format!("expected a quoted regex pattern after '{op}'")),
+            };
+        }
+        let op = match self.eat_op(&["==", "=", "!=", "<>", "<", "<=", ">", ">="]) {
+            Some("==") | Some("=") => CmpOp::Eq,
+            Some("!=") | Some("<>") => CmpOp::NotEq,
+            Some("<") => CmpOp::Lt,
+            Some("<=") => CmpOp::LtEq,
+            Some(">") => CmpOp::Gt,
+            Some(">=") => CmpOp::GtEq,
+            _ => return Ok(left),
+        };
+        let right = self.parse_primary()?;
+        Ok(Node::Compare(Box::new(left), op, Box::new(right)))
+    }
+
+    // primary := column | literal | "(" or ")"
+    fn parse_primary(&mut self) -> Result<Node, String> {
+        match self.next() {
+            Some(Token::LParen) => {
+                let node = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(node),
+                    _ => Err("missing closing ')'".to_string()),
+                }
+            }
+            Some(Token::Column(name)) => Ok(Node::Column(name)),
+            Some(Token::Str(s)) => Ok(Node::Str(s)),
+            Some(Token::DateTime(s)) => Ok(Node::DateTime(s)),
+            Some(Token::Number(n)) => {
+                if let Ok(i) = n.parse::<i64>() {
+                    Ok(Node::Int(i))
+                } else if let Ok(f) = n.parse::<f64>() {
+                    Ok(Node::Float(f))
+                } else {
+                    Err(format!("invalid number '{n}'"))
+                }
+            }
+            Some(Token::Ident(word)) => match word.to_lowercase().as_str() {
+                "true" => Ok(Node::Bool(true)),
+                "false" => Ok(Node::Bool(false)),
+                "null" => Ok(Node::Null),
+                "and" | "or" | "not" | "is" => Err(format!("unexpected keyword '{word}'")),
+                _ => Ok(Node::Column(word)),
+            },
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("unexpected end of expression".to_string()),
+        }
+    }
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Ident(w) => format!("'{w}'"),
+        Token::Column(c) => format!("`{c}`"),
+        Token::Str(s) => format!("\"{s}\""),
+        Token::Number(n) | Token::DateTime(n) => format!("'{n}'"),
+        Token::Op(op) => format!("'{op}'"),
+        Token::LParen => "'('".to_string(),
+        Token::RParen => "')'".to_string(),
+    }
+}
+
+// -- compiler --
+
+fn compile(node: &Node, schema: &Schema) -> Result<Expr, QsvError> {
+    match node {
+        Node::Not(inner) => Ok(compile(inner, schema)?.not()),
+        Node::And(l, r) => Ok(compile(l, schema)?.and(compile(r, schema)?)),
+        Node::Or(l, r) => Ok(compile(l, schema)?.or(compile(r, schema)?)),
+        Node::IsNull(inner, negated) => {
+            let expr = compile_operand(inner, schema)?;
+            Ok(if *negated {
+                expr.is_not_null()
+            } else {
+                expr.is_null()
+            })
+        }
+        Node::Regex(inner, pattern, negated) => {
+            let matched = compile_operand(inner, schema)?
+                .cast(DataType::String)
+                .str()
+                .contains(lit(pattern.as_str()), false);
+            Ok(if *negated { matched.not() } else { matched })
+        }
+        Node::Compare(l, op, r) => compile_comparison(l, *op, r, schema),
+        other => compile_operand(other, schema),
+    }
+}
+
+fn compile_operand(node: &Node, schema: &Schema) -> Result<Expr, QsvError> {
+    match node {
+        Node::Column(name) => {
+            if schema.get(name).is_none() {
+                return Err(QsvError::column_not_found(name, "filter"));
+            }
+            Ok(col(name.as_str()))
+        }
+        Node::Int(i) => Ok(typed_literal(*i)),
+        Node::Float(f) => Ok(typed_literal(*f)),
+        Node::Str(s) => Ok(lit(s.as_str())),
+        Node::Bool(b) => Ok(lit(*b)),
+        Node::Null => Ok(lit(NULL)),
+        Node::DateTime(s) => datetime_literal(s, None),
+        other => compile(other, schema),
+    }
+}
+
+fn compile_comparison(
+    left: &Node,
+    op: CmpOp,
+    right: &Node,
+    schema: &Schema,
+) -> Result<Expr, QsvError> {
+    let column_dtype = |node: &Node| match node {
+        Node::Column(name) => schema.get(name).cloned(),
+        _ => None,
+    };
+    let is_temporal = |dtype: &Option<DataType>| {
+        matches!(dtype, Some(DataType::Datetime(_, _)) | Some(DataType::Date))
+    };
+    let left_dtype = column_dtype(left);
+    let right_dtype = column_dtype(right);
+    // Literals compared with a zoned datetime column are wall-clock times in its zone
+    let temporal_dtype = [&left_dtype, &right_dtype]
+        .into_iter()
+        .find(|dtype| is_temporal(dtype))
+        .and_then(Option::as_ref);
+
+    // Datetime comparisons: compare everything as millisecond datetimes
+    let datetime_context = matches!(left, Node::DateTime(_))
+        || matches!(right, Node::DateTime(_))
+        || (is_temporal(&left_dtype) && matches!(right, Node::Str(_)))
+        || (is_temporal(&right_dtype) && matches!(left, Node::Str(_)));
+    // Numeric literals against string columns: compare numerically (non-numeric values become null)
+    let numeric_context = matches!(left, Node::Int(_) | Node::Float(_))
+        || matches!(right, Node::Int(_) | Node::Float(_));
+
+    let coerce = |node: &Node, dtype: &Option<DataType>| -> Result<Expr, QsvError> {
+        if datetime_context {
+            return match node {
+                Node::DateTime(s) | Node::Str(s) => datetime_literal(s, temporal_dtype),
+                Node::Column(_) => Ok(datetime_column(compile_operand(node, schema)?, dtype)),
+                other => compile_operand(other, schema),
+            };
+        }
+        let expr = compile_operand(node, schema)?;
+        if numeric_context && matches!(dtype, Some(DataType::String)) {
+            return Ok(expr.cast(DataType::Float64));
+        }
+        Ok(expr)
+    };
+    let l = coerce(left, &left_dtype)?;
+    let r = coerce(right, &right_dtype)?;
+
+    Ok(match op {
+        CmpOp::Eq => l.eq(r),
+        CmpOp::NotEq => l.neq(r),
+        CmpOp::Lt => l.lt(r),
+        CmpOp::LtEq => l.lt_eq(r),
+        CmpOp::Gt => l.gt(r),
+        CmpOp::GtEq => l.gt_eq(r),
+    })
+}
+
+fn datetime_literal(text: &str, column_dtype: Option<&DataType>) -> Result<Expr, QsvError> {
+    match parse_datetime_auto(text).and_then(|dt| bound_for(dt, column_dtype)) {
+        Some(dt) => Ok(typed_literal(dt.and_utc().timestamp_millis())
+            .cast(DataType::Datetime(TimeUnit::Milliseconds, None))),
+        None => Err(QsvError::Parse(format!("datetime literal '{text}'"))),
+    }
+}
+
+fn datetime_column(expr: Expr, dtype: &Option<DataType>) -> Expr {
+    match dtype {
+        Some(DataType::String) => {
+            DatetimeParser::default().to_datetime_expr(expr, TimeUnit::Milliseconds, "filter")
+        }
+        _ => expr.cast(DataType::Datetime(TimeUnit::Milliseconds, None)),
+    }
+}
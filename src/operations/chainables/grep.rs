@@ -1,14 +1,14 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
-pub fn grep(df: &LazyFrame, pattern: &str, ignorecase: bool, is_inverted: bool) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for grep operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn grep(
+    df: &LazyFrame,
+    pattern: &str,
+    ignorecase: bool,
+    is_inverted: bool,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     let all_column_names: Vec<String> = schema.iter_names().map(|s| s.to_string()).collect();
 
@@ -38,8 +38,8 @@ pub fn grep(df: &LazyFrame, pattern: &str, ignorecase: bool, is_inverted: bool)
         .unwrap_or_else(|| lit(false));
 
     if is_inverted {
-        df.clone().filter(filter_expr.not())
+        Ok(df.clone().filter(filter_expr.not()))
     } else {
-        df.clone().filter(filter_expr)
+        Ok(df.clone().filter(filter_expr))
     }
 }
@@ -0,0 +1,240 @@
+use crate::controllers::expr::typed_literal;
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use once_cell::sync::Lazy;
+use polars::prelude::*;
+use regex::Regex;
+
+// `func(args)` optionally followed by `as alias`
+static RE_AGGREGATION: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r#"(?is)^(?P<func>[a-z_][a-z_0-9]*)\s*\((?P<args>.*)\)(?:\s+as\s+(?P<alias>"[^"]+"|'[^']+'|\S+))?$"#)
+        .unwrap()
+});
+// Percentile shorthand such as `p95(col)`
+static RE_PERCENTILE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"^p(?P<pct>\d{1,2}(\.\d+)?)$").unwrap());
+
+/// Separator used by `concat` when none is given
+pub const GROUPBY_CONCAT_SEPARATOR: &str = ",";
+
+pub fn groupby(
+    df: &LazyFrame,
+    keys: &[String],
+    aggregations: &[String],
+) -> Result<LazyFrame, QsvError> {
+    LogController::debug(&format!(
+        "Grouping by {keys:?} with aggregations: {aggregations:?}"
+    ));
+
+    if keys.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "At least one group key column must be specified for groupby".to_string(),
+        ));
+    }
+
+    let schema = df.clone().collect_schema()?;
+    for key in keys {
+        if !schema.iter_names().any(|s| s == key) {
+            return Err(QsvError::column_not_found(key, "groupby"));
+        }
+    }
+
+    // Each entry may hold several comma-separated aggregations
+    let mut specs: Vec<String> = aggregations
+        .iter()
+        .flat_map(|a| split_aggregations(a))
+        .collect();
+    if specs.is_empty() {
+        specs.push("count(*)".to_string());
+    }
+
+    let mut agg_exprs = Vec::with_capacity(specs.len());
+    let mut output_names: Vec<String> = keys.to_vec();
+    for spec in &specs {
+        let (name, expr) = parse_aggregation(spec, &schema, "groupby")?;
+        if output_names.contains(&name) {
+            return Err(QsvError::InvalidArgument(format!(
+                "Duplicate output column '{name}' in groupby; use 'as <name>' to rename aggregation '{spec}'"
+            )));
+        }
+        agg_exprs.push(expr.alias(name.as_str()));
+        output_names.push(name);
+    }
+
+    LogController::debug(&format!("Groupby produces columns: {output_names:?}"));
+
+    Ok(df
+        .clone()
+        .group_by(keys.iter().map(|k| col(k.as_str())).collect::<Vec<_>>())
+        .agg(agg_exprs)
+        .sort(
+            keys.iter().map(|k| k.as_str()).collect::<Vec<_>>(),
+            SortMultipleOptions::default().with_nulls_last(true),
+        ))
+}
+
+/// Split on commas that are not inside parentheses or quotes
+pub fn split_aggregations(input: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut quote: Option<char> = None;
+    for c in input.chars() {
+        match (c, quote) {
+            ('\'' | '"', None) => quote = Some(c),
+            (q, Some(open)) if q == open => quote = None,
+            ('(', None) => depth += 1,
+            (')', None) => depth = depth.saturating_sub(1),
+            (',', None) if depth == 0 => {
+                parts.push(current.trim().to_string());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    parts.push(current.trim().to_string());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Parse one `func(column) [as name]` aggregation into its output name and expression;
+/// `operation` names the command in column-not-found errors
+/// Whether an aggregation counts or sums, so a group without rows aggregates to 0
+pub fn is_additive_aggregation(spec: &str) -> bool {
+    RE_AGGREGATION.captures(spec).is_some_and(|captures| {
+        matches!(
+            captures["func"].to_lowercase().as_str(),
+            "count" | "len" | "n_unique" | "nunique" | "count_distinct" | "sum"
+        )
+    })
+}
+
+pub fn parse_aggregation(
+    spec: &str,
+    schema: &Schema,
+    operation: &str,
+) -> Result<(String, Expr), QsvError> {
+    let captures = RE_AGGREGATION.captures(spec).ok_or_else(|| {
+        QsvError::Parse(format!(
+            "aggregation '{spec}': expected 'function(column)' optionally followed by 'as <name>'"
+        ))
+    })?;
+    let func = captures["func"].to_lowercase();
+    let args = split_aggregations(&captures["args"]);
+    let column = args.first().map(|a| unquote(a)).unwrap_or_default();
+    let param = args.get(1).map(|a| unquote(a));
+
+    if column != "*" && !schema.iter_names().any(|s| s == column.as_str()) {
+        return Err(QsvError::column_not_found(&column, operation));
+    }
+    if column == "*" && func != "count" && func != "len" {
+        return Err(QsvError::InvalidArgument(format!(
+            "Aggregation '{func}' needs a column, '*' is only valid for count"
+        )));
+    }
+
+    let c = || col(column.as_str());
+    let (default_name, expr) = match func.as_str() {
+        "count" | "len" if column == "*" => ("count".to_string(), len()),
+        "count" => (format!("count_{column}"), c().count()),
+        "n_unique" | "nunique" | "count_distinct" => {
+            (format!("n_unique_{column}"), c().n_unique())
+        }
+        "sum" => (format!("sum_{column}"), c().sum()),
+        "mean" | "avg" => (format!("mean_{column}"), c().mean()),
+        "min" => (format!("min_{column}"), c().min()),
+        "max" => (format!("max_{column}"), c().max()),
+        "median" => (format!("median_{column}"), c().median()),
+        "std" => (format!("std_{column}"), c().std(1)),
+        "var" => (format!("var_{column}"), c().var(1)),
+        "first" => (format!("first_{column}"), c().first()),
+        "last" => (format!("last_{column}"), c().last()),
+        "mode" => (
+            format!("mode_{column}"),
+            // Ties resolve to the smallest value so results are stable
+            c().mode().sort(SortOptions::default()).first(),
+        ),
+        "list" | "collect" => (
+            format!("list_{column}"),
+            concat_str(
+                [
+                    lit("["),
+                    c().cast(DataType::String).implode().list().join(lit(", "), true),
+                    lit("]"),
+                ],
+                "",
+                false,
+            ),
+        ),
+        "concat" | "string_agg" => {
+            let separator = param.unwrap_or_else(|| GROUPBY_CONCAT_SEPARATOR.to_string());
+            (
+                format!("concat_{column}"),
+                c().cast(DataType::String).str().join(&separator, true),
+            )
+        }
+        "percentile" | "quantile" => {
+            let raw = param.ok_or_else(|| {
+                QsvError::InvalidArgument(format!(
+                    "Aggregation '{func}' needs a second argument, e.g. {func}({column}, {})",
+                    if func == "percentile" { "95" } else { "0.95" }
+                ))
+            })?;
+            let value: f64 = raw.parse().map_err(|_| {
+                QsvError::Parse(format!("{func} '{raw}' in aggregation '{spec}'"))
+            })?;
+            let q = if func == "percentile" { value / 100.0 } else { value };
+            (format!("p{}_{column}", format_percent(q * 100.0)), quantile(c(), q)?)
+        }
+        _ => match RE_PERCENTILE.captures(&func) {
+            Some(p) => {
+                let pct: f64 = p["pct"].parse().map_err(|_| {
+                    QsvError::Parse(format!("percentile in aggregation '{spec}'"))
+                })?;
+                (format!("{func}_{column}"), quantile(c(), pct / 100.0)?)
+            }
+            None => {
+                return Err(QsvError::InvalidArgument(format!(
+                    "Unknown aggregation function '{func}'. Supported: count, n_unique, sum, mean, min, max, median, std, var, first, last, mode, list, concat, percentile, quantile, p<N>"
+                )))
+            }
+        },
+    };
+
+    let name = captures
+        .name("alias")
+        .map(|a| unquote(a.as_str()))
+        .unwrap_or(default_name);
+    Ok((name, expr))
+}
+
+fn quantile(expr: Expr, q: f64) -> Result<Expr, QsvError> {
+    if !(0.0..=1.0).contains(&q) {
+        return Err(QsvError::InvalidArgument(format!(
+            "Quantile {q} is out of range; percentiles must be within 0-100"
+        )));
+    }
+    Ok(expr.quantile(typed_literal(q), QuantileMethod::Linear))
+}
+
+// 95.0 -> "95", 99.9 -> "99.9"
+fn format_percent(pct: f64) -> String {
+    let rounded = (pct * 1000.0).round() / 1000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{rounded}")
+    }
+}
+
+fn unquote(s: &str) -> String {
+    let s = s.trim();
+    if s.len() >= 2
+        && ((s.starts_with('"') && s.ends_with('"')) || (s.starts_with('\'') && s.ends_with('\'')))
+    {
+        s[1..s.len() - 1].to_string()
+    } else {
+        s.to_string()
+    }
+}
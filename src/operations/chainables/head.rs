@@ -1,6 +1,7 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
-pub fn head(df: &LazyFrame, n: usize) -> LazyFrame {
+pub fn head(df: &LazyFrame, n: usize) -> Result<LazyFrame, QsvError> {
     LogController::debug(&format!("Applying head: n={n}"));
-    df.clone().slice(0, n as u32)
+    Ok(df.clone().slice(0, n as u32))
 }
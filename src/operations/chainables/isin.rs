@@ -1,19 +1,13 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
-pub fn isin(df: &LazyFrame, colname: &str, values: &[String]) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for isin operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn isin(df: &LazyFrame, colname: &str, values: &[String]) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
-    if !schema.iter_names().any(|s| s == colname) {
-        eprintln!("Error: Column '{colname}' not found in DataFrame for isin operation");
-        std::process::exit(1);
-    }
+    let Some(col_dtype) = schema.get(colname) else {
+        return Err(QsvError::column_not_found(colname, "isin"));
+    };
 
     LogController::debug(&format!(
         "Applying isin: column={colname} values={values:?}"
@@ -21,12 +15,9 @@ pub fn isin(df: &LazyFrame, colname: &str, values: &[String]) -> LazyFrame {
 
     if values.is_empty() {
         LogController::debug("Empty values list for isin, returning empty result");
-        return df.clone().filter(lit(false));
+        return Ok(df.clone().filter(lit(false)));
     }
 
-    // Get the column data type
-    let col_dtype = schema.get(colname).unwrap();
-
     // Build filter expression efficiently using fold instead of manual iteration
     let filter_expr = if matches!(
         col_dtype,
@@ -51,5 +42,5 @@ pub fn isin(df: &LazyFrame, colname: &str, values: &[String]) -> LazyFrame {
             .unwrap_or_else(|| lit(false))
     };
 
-    df.clone().filter(filter_expr)
+    Ok(df.clone().filter(filter_expr))
 }
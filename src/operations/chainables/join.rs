@@ -0,0 +1,301 @@
+use crate::controllers::datetime::DatetimeParser;
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use crate::operations::initializers::load;
+use polars::prelude::*;
+use std::path::PathBuf;
+
+/// Default suffix appended to right-hand columns whose names clash with the left side
+pub const DEFAULT_JOIN_SUFFIX: &str = "_right";
+
+/// Parse a join type name (inner, left, right, full/outer, semi, anti, cross)
+pub fn parse_join_type(how: &str) -> Option<JoinType> {
+    match how.to_lowercase().as_str() {
+        "inner" => Some(JoinType::Inner),
+        "left" => Some(JoinType::Left),
+        "right" => Some(JoinType::Right),
+        "full" | "outer" => Some(JoinType::Full),
+        "semi" => Some(JoinType::Semi),
+        "anti" => Some(JoinType::Anti),
+        "cross" => Some(JoinType::Cross),
+        _ => None,
+    }
+}
+
+pub fn join(
+    df: &LazyFrame,
+    right_paths: &[PathBuf],
+    left_on: &[String],
+    right_on: &[String],
+    how: &str,
+    suffix: &str,
+    separator: &str,
+) -> Result<LazyFrame, QsvError> {
+    let join_type = parse_join_type(how).ok_or_else(|| {
+        QsvError::InvalidArgument(format!(
+            "Unsupported join type '{how}'. Supported types: inner, left, right, full, semi, anti, cross"
+        ))
+    })?;
+
+    LogController::debug(&format!(
+        "Loading right-hand side for join: {}",
+        right_paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    let right_df = load::load(right_paths, separator, false, false, None)?;
+
+    join_frames(df, &right_df, left_on, right_on, join_type, suffix, true)
+}
+
+/// Join two frames on the given keys; used by the `join` chainable and quilt join stages.
+///
+/// `coalesce` merges the key columns of a full join into one, as the other join types do.
+pub fn join_frames(
+    left: &LazyFrame,
+    right: &LazyFrame,
+    left_on: &[String],
+    right_on: &[String],
+    join_type: JoinType,
+    suffix: &str,
+    coalesce: bool,
+) -> Result<LazyFrame, QsvError> {
+    LogController::debug(&format!(
+        "Applying join: how={join_type:?} left_on={left_on:?} right_on={right_on:?} suffix='{suffix}'"
+    ));
+
+    if matches!(join_type, JoinType::Cross) {
+        if !left_on.is_empty() || !right_on.is_empty() {
+            LogController::warn("Join keys are ignored for cross joins");
+        }
+        return Ok(left
+            .clone()
+            .join_builder()
+            .with(right.clone())
+            .how(JoinType::Cross)
+            .suffix(suffix)
+            .finish());
+    }
+
+    if left_on.is_empty() || right_on.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "Join requires key columns (--on, or --left-on and --right-on)".to_string(),
+        ));
+    }
+    if left_on.len() != right_on.len() {
+        return Err(QsvError::InvalidArgument(format!(
+            "Join requires the same number of left and right keys (got {} and {})",
+            left_on.len(),
+            right_on.len()
+        )));
+    }
+
+    let left_schema = left.clone().collect_schema()?;
+    let right_schema = right.clone().collect_schema()?;
+    for key in left_on {
+        if left_schema.get(key).is_none() {
+            return Err(QsvError::column_not_found(key, "join (left)"));
+        }
+    }
+    for key in right_on {
+        if right_schema.get(key).is_none() {
+            return Err(QsvError::column_not_found(key, "join (right)"));
+        }
+    }
+
+    // Keys with different types on each side (e.g. Int64 vs String) are compared as strings
+    let mut left_exprs = Vec::with_capacity(left_on.len());
+    let mut right_exprs = Vec::with_capacity(right_on.len());
+    for (l, r) in left_on.iter().zip(right_on) {
+        if left_schema.get(l) == right_schema.get(r) {
+            left_exprs.push(col(l.as_str()));
+            right_exprs.push(col(r.as_str()));
+        } else {
+            LogController::debug(&format!(
+                "Join key types differ for '{l}' and '{r}'; comparing as strings"
+            ));
+            left_exprs.push(col(l.as_str()).cast(DataType::String));
+            right_exprs.push(col(r.as_str()).cast(DataType::String));
+        }
+    }
+
+    let mut builder = left
+        .clone()
+        .join_builder()
+        .with(right.clone())
+        .left_on(left_exprs)
+        .right_on(right_exprs)
+        .how(join_type.clone())
+        .suffix(suffix);
+    if coalesce && matches!(join_type, JoinType::Full) {
+        builder = builder.coalesce(JoinCoalesce::CoalesceColumns);
+    }
+    Ok(builder.finish())
+}
+
+/// Options of an as-of join, which matches each left row with the nearest right row by key
+#[derive(Debug, Clone, Default)]
+pub struct AsofJoinOptions {
+    /// Right row to match: `backward` (the last one at or before the left key, the default),
+    /// `forward` or `nearest`
+    pub strategy: Option<String>,
+    /// Maximum distance between the keys: a duration such as `5m` or `1h30m` for
+    /// timestamps, a number for numeric keys
+    pub tolerance: Option<String>,
+    /// Columns that must be equal on both sides, e.g. the host of a login
+    pub by_left: Vec<String>,
+    pub by_right: Vec<String>,
+    /// How string keys, and numeric keys with an epoch, are read as datetimes
+    pub parser: DatetimeParser,
+}
+
+// Temporary sorted keys; the original key columns are kept as they are
+const ASOF_LEFT_KEY: &str = "__qsv_asof_left";
+const ASOF_RIGHT_KEY: &str = "__qsv_asof_right";
+
+pub fn parse_asof_strategy(strategy: &str) -> Option<AsofStrategy> {
+    match strategy.to_lowercase().as_str() {
+        "backward" => Some(AsofStrategy::Backward),
+        "forward" => Some(AsofStrategy::Forward),
+        "nearest" => Some(AsofStrategy::Nearest),
+        _ => None,
+    }
+}
+
+/// As-of join of two frames on one ordered key, typically a timestamp.
+///
+/// String keys are parsed as datetimes like those of the other time-aware operations. The result is sorted by the left key, and left
+/// rows without a match within the tolerance get null right-hand columns.
+pub fn asof_join_frames(
+    left: &LazyFrame,
+    right: &LazyFrame,
+    left_on: &str,
+    right_on: &str,
+    options: &AsofJoinOptions,
+    suffix: &str,
+) -> Result<LazyFrame, QsvError> {
+    LogController::debug(&format!(
+        "Applying as-of join: left_on={left_on} right_on={right_on} options={options:?} suffix='{suffix}'"
+    ));
+
+    let strategy = match options.strategy.as_deref() {
+        Some(s) => parse_asof_strategy(s).ok_or_else(|| {
+            QsvError::InvalidArgument(format!(
+                "Unsupported as-of join strategy '{s}'. Supported strategies: backward, forward, nearest"
+            ))
+        })?,
+        None => AsofStrategy::Backward,
+    };
+    if options.by_left.len() != options.by_right.len() {
+        return Err(QsvError::InvalidArgument(format!(
+            "As-of join requires the same number of left and right 'by' columns (got {} and {})",
+            options.by_left.len(),
+            options.by_right.len()
+        )));
+    }
+
+    let left_schema = left.clone().collect_schema()?;
+    let right_schema = right.clone().collect_schema()?;
+    for key in std::iter::once(left_on).chain(options.by_left.iter().map(|s| s.as_str())) {
+        if left_schema.get(key).is_none() {
+            return Err(QsvError::column_not_found(key, "join (left)"));
+        }
+    }
+    for key in std::iter::once(right_on).chain(options.by_right.iter().map(|s| s.as_str())) {
+        if right_schema.get(key).is_none() {
+            return Err(QsvError::column_not_found(key, "join (right)"));
+        }
+    }
+
+    let temporal = options.parser.epoch.is_some()
+        || [left_schema.get(left_on), right_schema.get(right_on)]
+            .iter()
+            .any(|dtype| dtype.is_some_and(|d| d.is_string() || d.is_temporal()));
+    let (tolerance, tolerance_str) = match options.tolerance.as_deref() {
+        None => (None, None),
+        Some(t) if temporal => {
+            polars::prelude::Duration::try_parse(t)
+                .map_err(|_| QsvError::Parse(format!("as-of join tolerance '{t}'")))?;
+            (None, Some(PlSmallStr::from_str(t)))
+        }
+        Some(t) => {
+            let value = match t.parse::<i64>() {
+                Ok(i) => AnyValue::Int64(i),
+                Err(_) => AnyValue::Float64(
+                    t.parse::<f64>()
+                        .map_err(|_| QsvError::Parse(format!("as-of join tolerance '{t}'")))?,
+                ),
+            };
+            (Some(value), None)
+        }
+    };
+    let by = |columns: &[String]| {
+        (!columns.is_empty()).then(|| columns.iter().map(PlSmallStr::from).collect())
+    };
+    let asof_options = AsOfOptions {
+        strategy,
+        tolerance,
+        tolerance_str,
+        left_by: by(&options.by_left),
+        right_by: by(&options.by_right),
+        allow_eq: true,
+        // Both sides are sorted above
+        check_sortedness: false,
+    };
+
+    let left_keyed = left
+        .clone()
+        .with_column(
+            asof_key(left_on, left_schema.get(left_on), temporal, &options.parser)
+                .alias(ASOF_LEFT_KEY),
+        )
+        .sort(
+            [ASOF_LEFT_KEY],
+            SortMultipleOptions::default().with_nulls_last(true),
+        );
+    // Right rows without a usable key can never match
+    let right_keyed = right
+        .clone()
+        .with_column(
+            asof_key(
+                right_on,
+                right_schema.get(right_on),
+                temporal,
+                &options.parser,
+            )
+            .alias(ASOF_RIGHT_KEY),
+        )
+        .filter(col(ASOF_RIGHT_KEY).is_not_null())
+        .sort([ASOF_RIGHT_KEY], SortMultipleOptions::default());
+
+    Ok(left_keyed
+        .join_builder()
+        .with(right_keyed)
+        .left_on([col(ASOF_LEFT_KEY)])
+        .right_on([col(ASOF_RIGHT_KEY)])
+        .how(JoinType::AsOf(asof_options))
+        .suffix(suffix)
+        .finish()
+        .drop([ASOF_LEFT_KEY, ASOF_RIGHT_KEY]))
+}
+
+fn asof_key(
+    column: &str,
+    dtype: Option<&DataType>,
+    temporal: bool,
+    parser: &DatetimeParser,
+) -> Expr {
+    if !temporal {
+        return col(column);
+    }
+    // Numbers next to a datetime key are milliseconds unless an epoch says otherwise
+    if parser.epoch.is_none() && dtype.is_some_and(|d| d.is_primitive_numeric()) {
+        return col(column).cast(DataType::Datetime(TimeUnit::Milliseconds, None));
+    }
+    // Unparsable values become null and never match; they are reported like elsewhere
+    parser
+        .to_datetime_expr(col(column), TimeUnit::Milliseconds, "join")
+        .cast(DataType::Datetime(TimeUnit::Milliseconds, None))
+}
@@ -0,0 +1,102 @@
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use crate::operations::chainables::select::expand_column_names;
+use polars::prelude::*;
+
+/// Default name of the column holding the melted column names
+pub const MELT_VARIABLE_NAME: &str = "variable";
+/// Default name of the column holding the melted values
+pub const MELT_VALUE_NAME: &str = "value";
+
+pub fn melt(
+    df: &LazyFrame,
+    id_cols: &[String],
+    value_cols: &[String],
+    variable_name: &str,
+    value_name: &str,
+) -> Result<LazyFrame, QsvError> {
+    LogController::debug(&format!(
+        "Melting with id columns: {id_cols:?}, value columns: {value_cols:?}, variable name: {variable_name}, value name: {value_name}"
+    ));
+
+    let schema = df.clone().collect_schema()?;
+    let available_columns: Vec<String> = schema.iter_names().map(|s| s.to_string()).collect();
+
+    let id_cols = expand_column_names(id_cols, &available_columns)?;
+    let value_cols = expand_column_names(value_cols, &available_columns)?;
+
+    // Validate columns exist
+    for colname in id_cols.iter().chain(value_cols.iter()) {
+        if !available_columns.contains(colname) {
+            return Err(QsvError::column_not_found(colname, "melt"));
+        }
+    }
+
+    // Without explicit value columns every non-id column is melted
+    let value_cols: Vec<String> = if value_cols.is_empty() {
+        available_columns
+            .iter()
+            .filter(|c| !id_cols.contains(c))
+            .cloned()
+            .collect()
+    } else {
+        value_cols
+    };
+    if value_cols.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "No columns left to melt; every column is an id column".to_string(),
+        ));
+    }
+    if let Some(overlap) = value_cols.iter().find(|c| id_cols.contains(c)) {
+        return Err(QsvError::InvalidArgument(format!(
+            "Column '{overlap}' cannot be both an id column and a value column"
+        )));
+    }
+    for name in [variable_name, value_name] {
+        if id_cols.iter().any(|c| c == name) {
+            return Err(QsvError::InvalidArgument(format!(
+                "Melt output column '{name}' clashes with an id column"
+            )));
+        }
+    }
+    if variable_name == value_name {
+        return Err(QsvError::InvalidArgument(format!(
+            "Melt variable and value columns must have different names, both are '{variable_name}'"
+        )));
+    }
+
+    // Value columns of different types are melted as strings
+    let first_dtype = schema.get(value_cols[0].as_str()).cloned();
+    let mixed_types = value_cols
+        .iter()
+        .any(|c| schema.get(c.as_str()).cloned() != first_dtype);
+    let df = if mixed_types {
+        LogController::debug("Melt value columns have mixed types, casting them to strings");
+        df.clone().with_columns(
+            value_cols
+                .iter()
+                .map(|c| col(c.as_str()).cast(DataType::String))
+                .collect::<Vec<_>>(),
+        )
+    } else {
+        df.clone()
+    };
+
+    let args = UnpivotArgsDSL {
+        on: value_cols
+            .iter()
+            .map(|c| Selector::from(c.as_str()))
+            .collect(),
+        index: id_cols.iter().map(|c| Selector::from(c.as_str())).collect(),
+        variable_name: Some(variable_name.into()),
+        value_name: Some(value_name.into()),
+    };
+
+    LogController::debug(&format!(
+        "Melt operation completed: {} id columns, {} value columns",
+        id_cols.len(),
+        value_cols.len()
+    ));
+
+    Ok(df.unpivot(args))
+}
@@ -2,16 +2,22 @@ pub mod changetz;
 pub mod contains;
 pub mod convert;
 pub mod count;
+pub mod filter;
 pub mod grep;
+pub mod groupby;
 pub mod head;
 pub mod isin;
+pub mod join;
+pub mod melt;
 pub mod pivot;
 pub mod renamecol;
 pub mod sed;
 pub mod select;
 pub mod sort;
+pub mod sql;
 pub mod tail;
 pub mod timeline;
 pub mod timeround;
 pub mod timeslice;
+pub mod todatetime;
 pub mod uniq;
@@ -1,80 +1,242 @@
+use crate::controllers::expr::typed_literal;
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
+/// Label used for the totals row and totals columns
+pub const PIVOT_TOTAL_LABEL: &str = "Total";
+
+/// Optional pivot behaviour beyond the row/column/value layout
+#[derive(Debug, Clone, Default)]
+pub struct PivotOptions {
+    /// Value for cells with no matching rows (left empty when unset)
+    pub fill: Option<String>,
+    /// Append a total column per value/aggregation, aggregated across all pivot columns
+    pub row_totals: bool,
+    /// Append a total row, aggregated across all row groups
+    pub col_totals: bool,
+}
+
 pub fn pivot(
     df: &LazyFrame,
     rows: &[String],
     columns: &[String],
-    values: &str,
-    agg_func: &str,
-) -> LazyFrame {
+    values: &[String],
+    agg_funcs: &[String],
+    options: &PivotOptions,
+) -> Result<LazyFrame, QsvError> {
     LogController::debug(&format!(
-        "Creating pivot table with rows: {rows:?}, columns: {columns:?}, values: {values}, aggregation: {agg_func}"
+        "Creating pivot table with rows: {rows:?}, columns: {columns:?}, values: {values:?}, aggregation: {agg_funcs:?}, options: {options:?}"
     ));
 
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for pivot operation: {e}");
-            std::process::exit(1);
-        }
-    };
+    if rows.is_empty() && columns.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "At least one of --rows or --cols must be specified for pivot".to_string(),
+        ));
+    }
+    if values.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "At least one value column must be specified for pivot".to_string(),
+        ));
+    }
+
+    let schema = df.clone().collect_schema()?;
 
     // Validate columns exist
-    for col in rows
-        .iter()
-        .chain(columns.iter())
-        .chain(std::iter::once(&values.to_string()))
-    {
+    for col in rows.iter().chain(columns.iter()).chain(values.iter()) {
         if !schema.iter_names().any(|s| s == col) {
-            eprintln!("Error: Column '{col}' not found in DataFrame for pivot operation");
-            std::process::exit(1);
+            return Err(QsvError::column_not_found(col, "pivot"));
         }
     }
 
-    // For now, implement a simple pivot using group_by and aggregation
-    // This is a simplified version - full pivot table functionality would be more complex
-    if rows.is_empty() && columns.is_empty() {
-        eprintln!("Error: At least one of --rows or --cols must be specified for pivot");
-        std::process::exit(1);
-    }
+    let agg_funcs: Vec<String> = if agg_funcs.is_empty() {
+        vec!["sum".to_string()]
+    } else {
+        agg_funcs.to_vec()
+    };
+    let metrics: Vec<(&String, &String)> = values
+        .iter()
+        .flat_map(|value| agg_funcs.iter().map(move |agg| (value, agg)))
+        .collect();
 
-    // Create group by columns
-    let mut group_cols = Vec::new();
-    group_cols.extend(rows.iter().map(col));
-    group_cols.extend(columns.iter().map(col));
-
-    // Create aggregation expression
-    let value_col = col(values);
-    let agg_expr = match agg_func {
-        "sum" => value_col.sum(),
-        "mean" => value_col.mean(),
-        "count" => value_col.count(),
-        "min" => value_col.min(),
-        "max" => value_col.max(),
-        "median" => value_col.median(),
-        "std" => value_col.std(1),
-        _ => {
-            LogController::warn(&format!(
-                "Unknown aggregation function '{agg_func}', using sum"
+    // Output cells as (column name, aggregation); without pivot columns each
+    // value/aggregation pair becomes a `{value}_{agg}` column
+    let mut cells: Vec<(String, Expr)> = Vec::new();
+    if columns.is_empty() {
+        for (value, agg) in &metrics {
+            cells.push((
+                format!("{value}_{agg}"),
+                aggregate(col(value.as_str()), agg),
             ));
-            value_col.sum()
         }
+    } else {
+        // Each distinct combination of the pivot columns becomes its own output column(s)
+        let keys = df
+            .clone()
+            .select(
+                columns
+                    .iter()
+                    .map(|c| col(c.as_str()).cast(DataType::String))
+                    .collect::<Vec<_>>(),
+            )
+            .unique_stable(None, UniqueKeepStrategy::First)
+            .sort(
+                columns.iter().map(|c| c.as_str()).collect::<Vec<_>>(),
+                SortMultipleOptions::default().with_nulls_last(true),
+            )
+            .collect()?;
+        LogController::debug(&format!("Pivot produces {} column groups", keys.height()));
+
+        let key_columns = keys.get_columns();
+        for row_idx in 0..keys.height() {
+            let mut labels = Vec::with_capacity(key_columns.len());
+            let mut mask: Option<Expr> = None;
+            for (key_name, key_column) in columns.iter().zip(key_columns) {
+                let key_value = key_column.str()?.get(row_idx);
+                let condition = match key_value {
+                    Some(v) => col(key_name.as_str())
+                        .cast(DataType::String)
+                        .eq(lit(v.to_string())),
+                    None => col(key_name.as_str()).is_null(),
+                };
+                labels.push(key_value.unwrap_or("null").to_string());
+                mask = Some(match mask {
+                    Some(m) => m.and(condition),
+                    None => condition,
+                });
+            }
+            let label = labels.join("_");
+            let mask = mask.expect("pivot columns are not empty");
+            for (value, agg) in &metrics {
+                // Cells without any matching rows stay empty (null) rather than e.g. a zero sum
+                let cell = when(mask.clone().any(true))
+                    .then(aggregate(col(value.as_str()).filter(mask.clone()), agg))
+                    .otherwise(lit(NULL));
+                cells.push((pivot_column_name(&label, value, agg, metrics.len()), cell));
+            }
+        }
+        if options.row_totals {
+            for (value, agg) in &metrics {
+                cells.push((
+                    pivot_column_name(PIVOT_TOTAL_LABEL, value, agg, metrics.len()),
+                    aggregate(col(value.as_str()), agg),
+                ));
+            }
+        }
+    }
+
+    let cell_exprs: Vec<Expr> = cells
+        .iter()
+        .map(|(name, expr)| expr.clone().alias(name.as_str()))
+        .collect();
+    let mut result = if rows.is_empty() {
+        df.clone().select(cell_exprs.clone())
+    } else {
+        df.clone()
+            .group_by(rows.iter().map(|r| col(r.as_str())).collect::<Vec<_>>())
+            .agg(cell_exprs.clone())
+            .sort(
+                rows.iter().map(|r| r.as_str()).collect::<Vec<_>>(),
+                SortMultipleOptions::default().with_nulls_last(true),
+            )
     };
 
-    // Group by and aggregate
-    let result = df
-        .clone()
-        .group_by(group_cols)
-        .agg([agg_expr.alias(format!("{values}_{agg_func}"))]);
+    if options.col_totals {
+        if rows.is_empty() {
+            LogController::warn("Pivot column totals need --rows; ignoring --col-totals");
+        } else {
+            // The total row labels its first row column and leaves the others empty
+            let row_keys: Vec<Expr> = rows
+                .iter()
+                .map(|r| col(r.as_str()).cast(DataType::String))
+                .collect();
+            let total_keys: Vec<Expr> = rows
+                .iter()
+                .enumerate()
+                .map(|(i, r)| {
+                    let key = if i == 0 {
+                        lit(PIVOT_TOTAL_LABEL)
+                    } else {
+                        lit(NULL).cast(DataType::String)
+                    };
+                    key.alias(r.as_str())
+                })
+                .collect();
+            let grouped = result.with_columns(row_keys);
+            let totals = df
+                .clone()
+                .select(cell_exprs)
+                .with_columns(total_keys)
+                .select(
+                    rows.iter()
+                        .map(|r| col(r.as_str()))
+                        .chain(cells.iter().map(|(name, _)| col(name.as_str())))
+                        .collect::<Vec<_>>(),
+                );
+            result = concat([grouped, totals], UnionArgs::default())?;
+        }
+    }
+
+    if let Some(fill) = &options.fill {
+        result = fill_missing(result, rows, fill)?;
+    }
 
     LogController::debug(&format!(
-        "Pivot operation completed: {} rows, {} columns, {} values, {} aggregation",
+        "Pivot operation completed: {} rows, {} columns, {} values, {} aggregations",
         rows.len(),
         columns.len(),
-        values,
-        agg_func
+        values.len(),
+        agg_funcs.len()
     ));
 
-    result
+    Ok(result)
+}
+
+fn aggregate(expr: Expr, agg_func: &str) -> Expr {
+    match agg_func {
+        "sum" => expr.sum(),
+        "mean" => expr.mean(),
+        "count" => expr.count(),
+        "min" => expr.min(),
+        "max" => expr.max(),
+        "median" => expr.median(),
+        "std" => expr.std(1),
+        _ => {
+            LogController::warn(&format!(
+                "Unknown aggregation function '{agg_func}', using sum"
+            ));
+            expr.sum()
+        }
+    }
+}
+
+// A single value/aggregation keeps the bare pivot label (e.g. `Laptop`);
+// several are told apart as `{value}_{agg}_{label}`
+fn pivot_column_name(label: &str, value: &str, agg_func: &str, metric_count: usize) -> String {
+    if metric_count == 1 {
+        label.to_string()
+    } else {
+        format!("{value}_{agg_func}_{label}")
+    }
+}
+
+// Fill empty cells; numeric fills keep numeric columns, anything else turns cells into strings
+fn fill_missing(df: LazyFrame, rows: &[String], fill: &str) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
+    let fill_exprs: Vec<Expr> = schema
+        .iter_names()
+        .filter(|name| !rows.iter().any(|r| r == name.as_str()))
+        .map(|name| {
+            let cell = col(name.as_str());
+            let filled = if let Ok(i) = fill.parse::<i64>() {
+                cell.fill_null(typed_literal(i))
+            } else if let Ok(f) = fill.parse::<f64>() {
+                cell.fill_null(typed_literal(f))
+            } else {
+                cell.cast(DataType::String).fill_null(lit(fill.to_string()))
+            };
+            filled.alias(name.as_str())
+        })
+        .collect();
+    Ok(df.with_columns(fill_exprs))
 }
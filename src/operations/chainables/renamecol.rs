@@ -1,18 +1,16 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
-pub fn renamecol(df: &LazyFrame, old_colname: &str, new_colname: &str) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for renamecol operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn renamecol(
+    df: &LazyFrame,
+    old_colname: &str,
+    new_colname: &str,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     if !schema.iter_names().any(|s| s == old_colname) {
-        eprintln!("Error: Column '{old_colname}' not found in DataFrame for renamecol operation");
-        std::process::exit(1);
+        return Err(QsvError::column_not_found(old_colname, "renamecol"));
     }
 
     LogController::debug(&format!(
@@ -31,5 +29,5 @@ pub fn renamecol(df: &LazyFrame, old_colname: &str, new_colname: &str) -> LazyFr
         })
         .collect();
 
-    df.clone().select(all_columns)
+    Ok(df.clone().select(all_columns))
 }
@@ -1,35 +1,59 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
-pub fn select(df: &LazyFrame, colnames: &[String]) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for select operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn select(df: &LazyFrame, colnames: &[String]) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     let available_columns: Vec<String> = schema.iter_names().map(|s| s.to_string()).collect();
+    let expanded_colnames = expand_column_names(colnames, &available_columns)?;
+
+    // Validate all expanded column names exist
+    for colname in &expanded_colnames {
+        if !schema.iter_names().any(|s| s == colname) {
+            return Err(QsvError::column_not_found(colname, "select"));
+        }
+    }
+
+    let mut selected_cols: Vec<Expr> = Vec::new();
+    for name in &expanded_colnames {
+        if available_columns.contains(name) {
+            selected_cols.push(col(name));
+        } else {
+            LogController::warn(&format!("Column '{name}' not found in DataFrame."));
+        }
+    }
+
+    if selected_cols.is_empty() {
+        LogController::warn("No valid columns selected. Returning original DataFrame.");
+        return Ok(df.clone());
+    }
+
+    Ok(df.clone().select(&selected_cols))
+}
 
-    // Expand column names to handle colon notation, quoted colon notation, and numeric indices
+/// Expand colon notation, quoted colon notation and numeric indices into column names
+pub fn expand_column_names(
+    colnames: &[String],
+    available_columns: &[String],
+) -> Result<Vec<String>, QsvError> {
     let mut expanded_colnames = Vec::new();
     for colname in colnames {
         if colname.contains(':') && !colname.starts_with('"') {
             // Check if it's a numeric range (e.g., "1:3")
             if is_numeric_range(colname) {
-                let range_cols = parse_numeric_range(colname, &available_columns);
+                let range_cols = parse_numeric_range(colname, available_columns);
                 expanded_colnames.extend(range_cols);
             } else {
                 // Handle regular colon-separated range (col1:col3)
-                let range_cols = parse_colon_range(colname, &available_columns);
+                let range_cols = parse_colon_range(colname, available_columns);
                 expanded_colnames.extend(range_cols);
             }
         } else if colname.starts_with('"') && colname.contains(":") && colname.ends_with('"') {
             // Handle quoted colon notation: "col1":"col3"
             let inner = &colname[1..colname.len() - 1]; // Remove outer quotes
             if let Some((start_col, end_col)) = inner.split_once(":") {
-                let range_cols = parse_quoted_colon_range(start_col, end_col, &available_columns);
+                let range_cols = parse_quoted_colon_range(start_col, end_col, available_columns);
                 expanded_colnames.extend(range_cols);
             } else {
                 expanded_colnames.push(colname.clone());
@@ -37,41 +61,19 @@ pub fn select(df: &LazyFrame, colnames: &[String]) -> LazyFrame {
         } else {
             // Check if it's a single numeric index
             if is_numeric_index(colname) {
-                if let Some(col_name) = parse_single_numeric_index(colname, &available_columns) {
+                if let Some(col_name) = parse_single_numeric_index(colname, available_columns) {
                     expanded_colnames.push(col_name);
                 } else {
-                    eprintln!("Error: Invalid column index '{colname}'");
-                    std::process::exit(1);
+                    return Err(QsvError::InvalidArgument(format!(
+                        "Invalid column index '{colname}'"
+                    )));
                 }
             } else {
                 expanded_colnames.push(colname.clone());
             }
         }
     }
-
-    // Validate all expanded column names exist
-    for colname in &expanded_colnames {
-        if !schema.iter_names().any(|s| s == colname) {
-            eprintln!("Error: Column '{colname}' not found in DataFrame for select operation");
-            std::process::exit(1);
-        }
-    }
-
-    let mut selected_cols: Vec<Expr> = Vec::new();
-    for name in &expanded_colnames {
-        if available_columns.contains(name) {
-            selected_cols.push(col(name));
-        } else {
-            LogController::warn(&format!("Column '{name}' not found in DataFrame."));
-        }
-    }
-
-    if selected_cols.is_empty() {
-        LogController::warn("No valid columns selected. Returning original DataFrame.");
-        return df.clone();
-    }
-
-    df.clone().select(&selected_cols)
+    Ok(expanded_colnames)
 }
 // Helper function to check if a string is a numeric index
 fn is_numeric_index(s: &str) -> bool {
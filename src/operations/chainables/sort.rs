@@ -1,19 +1,13 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
-pub fn sort(df: &LazyFrame, colnames: &[String], desc: bool) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for sort operation: {e}");
-            std::process::exit(1);
-        }
-    };
+pub fn sort(df: &LazyFrame, colnames: &[String], desc: bool) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     for colname in colnames {
         if !schema.iter_names().any(|s| s == colname) {
-            eprintln!("Error: Column '{colname}' not found in DataFrame for sort operation");
-            std::process::exit(1);
+            return Err(QsvError::column_not_found(colname, "sort"));
         }
     }
 
@@ -24,5 +18,5 @@ pub fn sort(df: &LazyFrame, colnames: &[String], desc: bool) -> LazyFrame {
     let sort_exprs: Vec<Expr> = colnames.iter().map(col).collect();
     let sort_options = SortMultipleOptions::default().with_order_descending(desc);
 
-    df.clone().sort_by_exprs(sort_exprs, sort_options)
+    Ok(df.clone().sort_by_exprs(sort_exprs, sort_options))
 }
@@ -0,0 +1,43 @@
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use polars::prelude::*;
+use polars::sql::SQLContext;
+use std::collections::HashMap;
+
+/// Table name the current data is registered under
+pub const SQL_SELF_TABLE: &str = "self";
+
+/// Run a SQL query against the current data, registered as `self`, plus any extra named tables
+pub fn sql(
+    df: &LazyFrame,
+    query: &str,
+    tables: &HashMap<String, LazyFrame>,
+) -> Result<LazyFrame, QsvError> {
+    LogController::debug(&format!(
+        "Running SQL query: {query} (extra tables: {:?})",
+        tables.keys().collect::<Vec<_>>()
+    ));
+
+    if query.trim().is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "SQL query must not be empty".to_string(),
+        ));
+    }
+
+    let mut context = SQLContext::new();
+    for (name, table) in tables {
+        if name == SQL_SELF_TABLE {
+            LogController::warn(&format!(
+                "Table name '{SQL_SELF_TABLE}' is reserved for the current data; ignoring the other table"
+            ));
+            continue;
+        }
+        context.register(name, table.clone());
+    }
+    context.register(SQL_SELF_TABLE, df.clone());
+
+    context.execute(query).map_err(|e| match e {
+        PolarsError::ColumnNotFound(column) => QsvError::column_not_found(&column, "sql"),
+        e => QsvError::Parse(format!("SQL query: {e}")),
+    })
+}
@@ -1,6 +1,7 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
-pub fn tail(df: &LazyFrame, n: usize) -> LazyFrame {
+pub fn tail(df: &LazyFrame, n: usize) -> Result<LazyFrame, QsvError> {
     LogController::debug(&format!("Applying tail: n={n}"));
-    df.clone().tail(n as u32)
+    Ok(df.clone().tail(n as u32))
 }
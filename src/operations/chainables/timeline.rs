@@ -1,170 +1,383 @@
+use crate::controllers::datetime::{
+    bound_for, localize, parse_datetime_auto, parse_timezone, polars_timezone, DatetimeParser,
+};
 use crate::controllers::log::LogController;
-use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use crate::error::QsvError;
+use crate::operations::chainables::groupby::{
+    is_additive_aggregation, parse_aggregation, split_aggregations,
+};
+use chrono::{DateTime, Days, Months, NaiveDateTime, TimeDelta, Weekday};
+use chrono_tz::Tz;
 use polars::prelude::*;
 
+/// Most buckets a filled timeline may hold, so a mistyped `--start` cannot exhaust memory
+pub const MAX_FILLED_BUCKETS: usize = 1_000_000;
+
+/// Aggregations, series and gap filling of a timeline beyond its time column and interval
+#[derive(Debug, Clone, Default)]
+pub struct TimelineOptions {
+    /// `(function, column)` pairs from `--sum`, `--avg`, `--min`, `--max` and `--std`
+    pub aggregations: Vec<(String, String)>,
+    /// Aggregations in `groupby --agg` syntax, e.g. `p95(latency) as p95`
+    pub agg: Vec<String>,
+    /// Column splitting the timeline into one series per value
+    pub by: Option<String>,
+    /// Emit empty buckets with a zero count between the first and last bucket
+    pub fill: bool,
+    /// First bucket to emit; implies `fill`
+    pub start: Option<String>,
+    /// Last bucket to emit; implies `fill`
+    pub end: Option<String>,
+    /// First day of week buckets (Monday when unset)
+    pub week_start: Option<String>,
+    /// Time zone whose calendar the buckets follow (the zone of the column when unset)
+    pub tz: Option<String>,
+}
+
+// The length of one bucket in wall-clock time
+#[derive(Debug, Clone, Copy)]
+enum Step {
+    Fixed(TimeDelta),
+    Days(u64),
+    Months(u32),
+}
+
 pub fn timeline(
     df: &LazyFrame,
     time_column: &str,
     interval: &str,
-    agg_type: &str,
-    agg_column: Option<&str>,
-) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for timeline operation: {e}");
-            std::process::exit(1);
-        }
-    };
+    options: &TimelineOptions,
+    parser: &DatetimeParser,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
 
     if !schema.iter_names().any(|s| s == time_column) {
-        eprintln!(
-            "Error: Time column '{time_column}' not found in DataFrame for timeline operation"
-        );
-        std::process::exit(1);
+        return Err(QsvError::column_not_found(time_column, "timeline"));
     }
-
-    // Parse interval (e.g., "1h", "5m", "30s")
-    let interval_duration = parse_interval(interval);
-    if interval_duration.is_none() {
-        eprintln!("Error: Invalid interval format '{interval}'. Use format like '1h', '5m', '30s'");
-        std::process::exit(1);
+    if let Some(by) = &options.by {
+        if !schema.iter_names().any(|s| s == by) {
+            return Err(QsvError::column_not_found(by, "timeline"));
+        }
     }
-    let interval_duration = interval_duration.unwrap();
+
+    // Parse interval (e.g., "1h", "5m", "30s", "1w", "1mo")
+    let (every, step) = parse_interval(interval).ok_or_else(|| {
+        QsvError::InvalidArgument(format!(
+            "Invalid interval format '{interval}'. Use format like '500ms', '30s', '5m', '1h', '1d', '1w', '1mo', '1y'"
+        ))
+    })?;
+    let week_shift = match options.week_start.as_deref() {
+        None => 0,
+        Some(_) if !every.ends_with('w') => {
+            return Err(QsvError::InvalidArgument(format!(
+                "--week-start only applies to week intervals such as '1w', not '{interval}'"
+            )));
+        }
+        Some(day) => {
+            let day: Weekday = day.parse().map_err(|_| {
+                QsvError::InvalidArgument(format!(
+                    "Invalid week start '{day}'. Use a weekday such as mon or sun"
+                ))
+            })?;
+            // Days from the week start to the following Monday, where polars weeks begin
+            (7 - day.num_days_from_monday()) % 7
+        }
+    };
+    let tz = options.tz.as_deref().map(parse_timezone).transpose()?;
 
     LogController::debug(&format!(
-        "Creating timeline: column={time_column}, interval={interval}, aggregation={agg_type}"
+        "Creating timeline: column={time_column}, interval={interval}, options={options:?}"
     ));
 
-    // Convert to LazyFrame and perform timeline aggregation
+    // Buckets are datetimes rounded down to the interval, counted from the Unix epoch in the
+    // wall-clock time of the column (its own zone for zoned datetimes, or --tz)
     let bucket_column_name = format!("timeline_{interval}");
-    let timeline_expr = col(time_column)
-        .cast(DataType::String)
-        .map(
-            move |s_col: Column| {
-                let ca = s_col.str()?;
-                let mut timeline_buckets: Vec<Option<String>> = Vec::with_capacity(ca.len());
-                for opt_time_str in ca.into_iter() {
-                    if let Some(time_str) = opt_time_str {
-                        if let Some(bucket) = time_to_bucket(time_str, interval_duration) {
-                            timeline_buckets.push(Some(bucket));
-                        } else {
-                            timeline_buckets.push(None);
-                        }
-                    } else {
-                        timeline_buckets.push(None);
-                    }
-                }
-                Ok(Some(
-                    Series::new("timeline_bucket".into(), timeline_buckets).into(),
-                ))
-            },
-            GetOutput::from_type(DataType::String),
-        )
-        .alias(&bucket_column_name);
+    let mut time = parser.to_datetime_expr(col(time_column), TimeUnit::Microseconds, "timeline");
+    if let Some(tz) = tz {
+        // Naive times are read as UTC, zoned ones converted
+        time = time.cast(DataType::Datetime(
+            TimeUnit::Microseconds,
+            Some(polars_timezone(tz)?),
+        ));
+    }
+    let timeline_expr = bucket(time, &every, week_shift).alias(&bucket_column_name);
 
-    let mut agg_exprs = Vec::with_capacity(if agg_column.is_some() { 2 } else { 1 });
-    agg_exprs.push(len().alias("count"));
+    let mut keys = vec![bucket_column_name];
+    keys.extend(options.by.iter().cloned());
 
-    // Add aggregation column if specified
-    if let Some(agg_col) = agg_column {
+    let mut aggregations = vec![("count".to_string(), len())];
+    // Counts and sums of empty buckets are 0; the other aggregations stay empty
+    let mut additive = vec!["count".to_string()];
+    for (agg_type, agg_col) in &options.aggregations {
         if !schema.iter_names().any(|s| s == agg_col) {
-            eprintln!("Error: Aggregation column '{agg_col}' not found in DataFrame");
-            std::process::exit(1);
+            return Err(QsvError::column_not_found(agg_col, "timeline aggregation"));
         }
-        let agg_expr = match agg_type {
-            "sum" => col(agg_col)
-                .cast(DataType::Float64)
-                .sum()
-                .alias(format!("sum_{agg_col}")),
-            "avg" => col(agg_col)
-                .cast(DataType::Float64)
-                .mean()
-                .alias(format!("avg_{agg_col}")),
-            "min" => col(agg_col)
-                .cast(DataType::Float64)
-                .min()
-                .alias(format!("min_{agg_col}")),
-            "max" => col(agg_col)
-                .cast(DataType::Float64)
-                .max()
-                .alias(format!("max_{agg_col}")),
-            "std" => col(agg_col)
-                .cast(DataType::Float64)
-                .std(1)
-                .alias(format!("std_{agg_col}")),
+        let values = col(agg_col.as_str()).cast(DataType::Float64);
+        let expr = match agg_type.as_str() {
+            "sum" => values.sum(),
+            "avg" => values.mean(),
+            "min" => values.min(),
+            "max" => values.max(),
+            "std" => values.std(1),
             _ => {
-                eprintln!(
-                    "Error: Unsupported aggregation type '{agg_type}'. Use: sum, avg, min, max, std"
-                );
-                std::process::exit(1);
+                return Err(QsvError::InvalidArgument(format!(
+                    "Unsupported aggregation type '{agg_type}'. Use: sum, avg, min, max, std"
+                )));
             }
         };
-        agg_exprs.push(agg_expr);
+        if agg_type == "sum" {
+            additive.push(format!("{agg_type}_{agg_col}"));
+        }
+        aggregations.push((format!("{agg_type}_{agg_col}"), expr));
+    }
+    for spec in options.agg.iter().flat_map(|a| split_aggregations(a)) {
+        let (name, expr) = parse_aggregation(&spec, &schema, "timeline")?;
+        if is_additive_aggregation(&spec) {
+            additive.push(name.clone());
+        }
+        aggregations.push((name, expr));
+    }
+
+    let mut output_names = keys.clone();
+    let mut agg_exprs = Vec::with_capacity(aggregations.len());
+    for (name, expr) in aggregations {
+        if output_names.contains(&name) {
+            return Err(QsvError::InvalidArgument(format!(
+                "Duplicate output column '{name}' in timeline; use --agg with 'as <name>' to rename it"
+            )));
+        }
+        agg_exprs.push(expr.alias(name.as_str()));
+        output_names.push(name);
     }
 
-    df.clone()
+    let aggregated = df
+        .clone()
         .with_column(timeline_expr)
-        .group_by([col(&bucket_column_name)])
+        .group_by(keys.iter().map(|k| col(k.as_str())).collect::<Vec<_>>())
         .agg(agg_exprs)
-        .sort([&bucket_column_name], SortMultipleOptions::default())
-}
-fn parse_interval(interval: &str) -> Option<Duration> {
-    if interval.is_empty() {
-        return None;
+        .sort(
+            keys.iter().map(|k| k.as_str()).collect::<Vec<_>>(),
+            SortMultipleOptions::default(),
+        );
+
+    if !options.fill && options.start.is_none() && options.end.is_none() {
+        return Ok(aggregated);
     }
+    fill_buckets(
+        aggregated.collect()?,
+        &keys,
+        &additive,
+        &every,
+        step,
+        week_shift,
+        options,
+    )
+}
+
+// An interval as a polars duration string, e.g. `1h`, `30m`, `5s`, `1d`, `1w`, `1mo` or
+// `500ms`, and the length of its buckets
+fn parse_interval(interval: &str) -> Option<(String, Step)> {
     let (num_str, unit) = if let Some(stripped) = interval.strip_suffix("ms") {
         (stripped, "ms")
+    } else if let Some(stripped) = interval.strip_suffix("mo") {
+        (stripped, "mo")
     } else {
-        (
-            &interval[..interval.len() - 1],
-            &interval[interval.len() - 1..],
-        )
+        interval.split_at(interval.len() - interval.chars().last()?.len_utf8())
     };
-    let num: i64 = num_str.parse().ok()?;
-    match unit {
-        "s" => Some(Duration::seconds(num)),
-        "m" => Some(Duration::minutes(num)),
-        "h" => Some(Duration::hours(num)),
-        "d" => Some(Duration::days(num)),
-        "ms" => Some(Duration::milliseconds(num)),
-        _ => None,
+    let num: u32 = num_str.parse().ok()?;
+    if num == 0 {
+        return None;
     }
+    let step = match unit {
+        "ms" => Step::Fixed(TimeDelta::milliseconds(num.into())),
+        "s" => Step::Fixed(TimeDelta::seconds(num.into())),
+        "m" => Step::Fixed(TimeDelta::minutes(num.into())),
+        "h" => Step::Fixed(TimeDelta::hours(num.into())),
+        "d" => Step::Days(num.into()),
+        "w" => Step::Days(7 * u64::from(num)),
+        "mo" => Step::Months(num),
+        "y" => Step::Months(num.checked_mul(12)?),
+        _ => return None,
+    };
+    Some((format!("{num}{unit}"), step))
 }
-fn time_to_bucket(time_str: &str, interval: Duration) -> Option<String> {
-    // Try multiple datetime formats
-    let formats = [
-        "%Y-%m-%d %H:%M:%S%.f",
-        "%Y-%m-%d %H:%M:%S",
-        "%Y-%m-%dT%H:%M:%S%.f",
-        "%Y-%m-%dT%H:%M:%S",
-        "%Y/%m/%d %H:%M:%S",
-        "%d/%b/%Y:%H:%M:%S", // Apache log format
-        "%Y-%m-%d",
-        "%H:%M:%S",
-    ];
-    let mut parsed_time: Option<NaiveDateTime> = None;
-    for format in &formats {
-        if let Ok(dt) = NaiveDateTime::parse_from_str(time_str, format) {
-            parsed_time = Some(dt);
-            break;
-        }
+
+// Round times down to the start of their bucket; weeks start `week_shift` days before Monday
+fn bucket(time: Expr, every: &str, week_shift: u32) -> Expr {
+    if week_shift == 0 {
+        return time.dt().truncate(lit(every.to_string()));
     }
-    if parsed_time.is_none() {
-        // Try parsing as timestamp
-        if let Ok(timestamp) = time_str.parse::<i64>() {
-            parsed_time = DateTime::from_timestamp(timestamp, 0).map(|dt| dt.naive_utc());
+    time.dt()
+        .offset_by(lit(format!("{week_shift}d")))
+        .dt()
+        .truncate(lit(every.to_string()))
+        .dt()
+        .offset_by(lit(format!("-{week_shift}d")))
+}
+
+// The start of the buckets holding the given microsecond instants
+fn snap(
+    instants: Vec<i64>,
+    dtype: &DataType,
+    every: &str,
+    week_shift: u32,
+) -> Result<Vec<i64>, QsvError> {
+    let times = Int64Chunked::from_vec("time".into(), instants)
+        .into_series()
+        .cast(dtype)?;
+    let snapped = DataFrame::new(vec![times.into()])?
+        .lazy()
+        .select([bucket(col("time"), every, week_shift)])
+        .collect()?;
+    Ok(snapped
+        .column("time")?
+        .datetime()?
+        .into_iter()
+        .flatten()
+        .collect())
+}
+
+// Add a row for every empty bucket between the first and last bucket, or between the buckets
+// holding --start and --end, in each series of --by; its `additive` columns are 0
+fn fill_buckets(
+    aggregated: DataFrame,
+    keys: &[String],
+    additive: &[String],
+    every: &str,
+    step: Step,
+    week_shift: u32,
+    options: &TimelineOptions,
+) -> Result<LazyFrame, QsvError> {
+    let bucket_column_name = &keys[0];
+    let buckets = aggregated.column(bucket_column_name)?;
+    let dtype = buckets.dtype().clone();
+    let tz: Option<Tz> = match &dtype {
+        DataType::Datetime(_, Some(zone)) => zone.parse().ok(),
+        _ => None,
+    };
+    let observed: Vec<i64> = buckets.datetime()?.into_iter().flatten().collect();
+
+    let bound = |text: &str, option: &str| -> Result<i64, QsvError> {
+        let instant = parse_datetime_auto(text)
+            .and_then(|dt| bound_for(dt, Some(&dtype)))
+            .ok_or_else(|| QsvError::Parse(format!("timeline --{option} '{text}'")))?;
+        snap(
+            vec![instant.and_utc().timestamp_micros()],
+            &dtype,
+            every,
+            week_shift,
+        )?
+        .first()
+        .copied()
+        .ok_or_else(|| QsvError::Parse(format!("timeline --{option} '{text}'")))
+    };
+    let first = match &options.start {
+        Some(start) => Some(bound(start, "start")?),
+        None => observed.iter().min().copied(),
+    };
+    let last = match &options.end {
+        Some(end) => Some(bound(end, "end")?),
+        None => observed.iter().max().copied(),
+    };
+    let (Some(first), Some(last)) = (first, last) else {
+        // No bucket to start or end the range from
+        return Ok(aggregated.lazy());
+    };
+
+    // Step through wall-clock time so days, weeks and months follow the calendar of the zone
+    let to_local = |us: i64| {
+        DateTime::from_timestamp_micros(us).map(|utc| match tz {
+            Some(tz) => utc.with_timezone(&tz).naive_local(),
+            None => utc.naive_utc(),
+        })
+    };
+    let to_instant = |local: NaiveDateTime| {
+        match tz {
+            Some(tz) => localize(local, tz, false),
+            None => Some(local),
+        }
+        .map(|dt| dt.and_utc().timestamp_micros())
+    };
+    let (Some(origin), Some(end)) = (to_local(first), to_local(last)) else {
+        return Ok(aggregated.lazy());
+    };
+    let mut candidates = Vec::new();
+    for k in 0u32.. {
+        let local = match step {
+            Step::Fixed(delta) => delta
+                .checked_mul(k as i32)
+                .and_then(|offset| origin.checked_add_signed(offset)),
+            Step::Days(days) => origin.checked_add_days(Days::new(days * u64::from(k))),
+            Step::Months(months) => months
+                .checked_mul(k)
+                .and_then(|months| origin.checked_add_months(Months::new(months))),
+        };
+        match local {
+            Some(local) if local <= end => {}
+            _ => break,
+        }
+        if k as usize == MAX_FILLED_BUCKETS {
+            return Err(QsvError::InvalidArgument(format!(
+                "Filling the timeline would create more than {MAX_FILLED_BUCKETS} buckets; use a longer --interval or a narrower --start/--end"
+            )));
         }
+        // Wall-clock times skipped by a DST transition have no bucket
+        candidates.extend(local.and_then(to_instant));
     }
-    let dt = parsed_time?;
-    let dt_utc = DateTime::<Utc>::from_naive_utc_and_offset(dt, Utc);
-    // Round down to interval boundary
-    let interval_seconds = interval.num_seconds();
-    if interval_seconds <= 0 {
-        return None;
+
+    // Snapping keeps generated buckets identical to the ones polars computed
+    let mut all = snap(candidates, &dtype, every, week_shift)?;
+    all.extend(observed);
+    all.retain(|us| (first..=last).contains(us));
+    all.sort_unstable();
+    all.dedup();
+    LogController::debug(&format!("Filling timeline with {} buckets", all.len()));
+
+    let grid = DataFrame::new(vec![Int64Chunked::from_vec(
+        bucket_column_name.as_str().into(),
+        all,
+    )
+    .into_series()
+    .cast(&dtype)?
+    .into()])?
+    .lazy();
+    let grid = match &options.by {
+        Some(by) => grid.cross_join(
+            aggregated
+                .clone()
+                .lazy()
+                .select([col(by.as_str()).unique()]),
+            None,
+        ),
+        None => grid,
+    };
+    let key_exprs: Vec<Expr> = keys.iter().map(|k| col(k.as_str())).collect();
+    let filled = grid
+        .join_builder()
+        .with(aggregated.clone().lazy())
+        .left_on(key_exprs.clone())
+        .right_on(key_exprs)
+        .how(JoinType::Left)
+        .join_nulls(true)
+        .finish()
+        .with_columns(
+            additive
+                .iter()
+                .map(|name| col(name.as_str()).fill_null(lit(0)))
+                .collect::<Vec<_>>(),
+        )
+        .with_column(col("count").cast(IDX_DTYPE))
+        .sort(
+            keys.iter().map(|k| k.as_str()).collect::<Vec<_>>(),
+            SortMultipleOptions::default(),
+        );
+
+    if options.start.is_some() || options.end.is_some() {
+        return Ok(filled);
     }
-    let timestamp = dt_utc.timestamp();
-    let bucket_timestamp = (timestamp / interval_seconds) * interval_seconds;
-    let bucket_dt = DateTime::from_timestamp(bucket_timestamp, 0)?;
-    Some(bucket_dt.format("%Y-%m-%d %H:%M:%S").to_string())
+    // Rows whose time could not be parsed keep their empty bucket in front
+    let unparsed = aggregated
+        .lazy()
+        .filter(col(bucket_column_name.as_str()).is_null());
+    Ok(concat([unparsed, filled], UnionArgs::default())?)
 }
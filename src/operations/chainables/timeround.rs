@@ -1,38 +1,48 @@
+use crate::controllers::datetime::DatetimeParser;
+use crate::error::QsvError;
 use polars::prelude::*;
 pub fn timeround(
     df: &LazyFrame,
     colname: &str,
     unit: &str,
     output_colname: Option<&str>,
-) -> LazyFrame {
-    // Convert unit shorthand to polars duration format and determine output format
-    let (duration, format) = match unit {
-        "y" | "year" => ("1y", "%Y"),
-        "M" | "month" => ("1mo", "%Y-%m"),
-        "d" | "day" => ("1d", "%Y-%m-%d"),
-        "h" | "hour" => ("1h", "%Y-%m-%d %H"),
-        "m" | "minute" => ("1m", "%Y-%m-%d %H:%M"),
-        "s" | "second" => ("1s", "%Y-%m-%d %H:%M:%S"),
+    parser: &DatetimeParser,
+) -> Result<LazyFrame, QsvError> {
+    // Convert unit shorthand to polars duration format; whole days and coarser become dates
+    let (duration, as_date) = match unit {
+        "y" | "year" => ("1y", true),
+        "M" | "month" => ("1mo", true),
+        "d" | "day" => ("1d", true),
+        "h" | "hour" => ("1h", false),
+        "m" | "minute" => ("1m", false),
+        "s" | "second" => ("1s", false),
         _ => {
-            eprintln!("Error: Invalid time unit '{unit}'. Use: y/year, M/month, d/day, h/hour, m/minute, s/second");
-            std::process::exit(1);
+            return Err(QsvError::InvalidArgument(format!(
+                "Invalid time unit '{unit}'. Use: y/year, M/month, d/day, h/hour, m/minute, s/second"
+            )));
         }
     };
     let output_col = output_colname.unwrap_or(colname);
-    df.clone().with_columns([col(colname)
-        .str()
-        .to_datetime(
-            Some(TimeUnit::Microseconds),
-            None,
-            StrptimeOptions {
-                format: None, // Auto-detect format
-                ..Default::default()
-            },
-            lit("raise"),
-        )
-        .dt()
-        .truncate(lit(duration))
+    let rounded = parser
+        .to_datetime_expr(col(colname), TimeUnit::Microseconds, "timeround")
         .dt()
-        .to_string(format)
-        .alias(output_col)])
+        .truncate(lit(duration));
+    let zoned = matches!(
+        df.clone().collect_schema()?.get(colname),
+        Some(DataType::Datetime(_, Some(_)))
+    );
+    let rounded = match (as_date, zoned) {
+        // Casting a zoned datetime gives its UTC date; go through its local date instead
+        (true, true) => rounded
+            .dt()
+            .to_string("%Y-%m-%d")
+            .str()
+            .to_date(StrptimeOptions {
+                format: Some("%Y-%m-%d".into()),
+                ..Default::default()
+            }),
+        (true, false) => rounded.cast(DataType::Date),
+        (false, _) => rounded,
+    };
+    Ok(df.clone().with_columns([rounded.alias(output_col)]))
 }
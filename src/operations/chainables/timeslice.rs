@@ -1,4 +1,6 @@
+use crate::controllers::datetime::{bound_for, parse_datetime_auto, DatetimeParser};
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 
 pub fn timeslice(
@@ -6,21 +8,13 @@ pub fn timeslice(
     time_column: &str,
     start_time: Option<&str>,
     end_time: Option<&str>,
-) -> LazyFrame {
-    let schema = match df.clone().collect_schema() {
-        Ok(s) => s,
-        Err(e) => {
-            eprintln!("Error getting schema for timeslice operation: {e}");
-            std::process::exit(1);
-        }
-    };
-
-    if !schema.iter_names().any(|s| s == time_column) {
-        eprintln!(
-            "Error: Time column '{time_column}' not found in DataFrame for timeslice operation"
-        );
-        std::process::exit(1);
-    }
+    parser: &DatetimeParser,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
+    let dtype = schema
+        .get(time_column)
+        .cloned()
+        .ok_or_else(|| QsvError::column_not_found(time_column, "timeslice"))?;
 
     LogController::debug(&format!(
         "Creating timeslice: column={time_column}, start={start_time:?}, end={end_time:?}"
@@ -29,16 +23,11 @@ pub fn timeslice(
     // Start with the original dataframe
     let mut result_df = df.clone();
 
-    // Convert the time column to datetime for efficient comparison
-    // Try multiple formats automatically with Polars
-    let time_col_expr = col(time_column)
-        .str()
-        .to_datetime(
-            Some(TimeUnit::Milliseconds),
-            None,
-            StrptimeOptions::default(),
-            lit("raise"),
-        )
+    // Convert the time column to datetime for efficient comparison; zoned datetimes compare
+    // as UTC instants
+    let time_col_expr = parser
+        .to_datetime_expr(col(time_column), TimeUnit::Milliseconds, "timeslice")
+        .cast(DataType::Datetime(TimeUnit::Milliseconds, None))
         .alias("_temp_datetime");
 
     // Add the converted datetime column temporarily
@@ -49,13 +38,8 @@ pub fn timeslice(
         LogController::debug(&format!("Applying start time filter: {start}"));
 
         // Parse start time to timestamp
-        let start_datetime = match parse_datetime_string(start) {
-            Some(dt) => dt,
-            None => {
-                eprintln!("Error: Could not parse start time '{start}'");
-                std::process::exit(1);
-            }
-        };
+        let start_datetime = parse_bound(start, &dtype)
+            .ok_or_else(|| QsvError::Parse(format!("start time '{start}'")))?;
 
         let start_filter = col("_temp_datetime").gt_eq(lit(start_datetime));
         result_df = result_df.filter(start_filter);
@@ -66,13 +50,8 @@ pub fn timeslice(
         LogController::debug(&format!("Applying end time filter: {end}"));
 
         // Parse end time to timestamp
-        let end_datetime = match parse_datetime_string(end) {
-            Some(dt) => dt,
-            None => {
-                eprintln!("Error: Could not parse end time '{end}'");
-                std::process::exit(1);
-            }
-        };
+        let end_datetime =
+            parse_bound(end, &dtype).ok_or_else(|| QsvError::Parse(format!("end time '{end}'")))?;
 
         let end_filter = col("_temp_datetime").lt_eq(lit(end_datetime));
         result_df = result_df.filter(end_filter);
@@ -80,34 +59,11 @@ pub fn timeslice(
 
     // Remove the temporary datetime column
     let original_columns: Vec<String> = schema.iter_names().map(|s| s.to_string()).collect();
-    result_df.select([cols(original_columns)])
+    Ok(result_df.select([cols(original_columns)]))
 }
 
-fn parse_datetime_string(time_str: &str) -> Option<i64> {
-    use chrono::NaiveDateTime;
-
-    // Try multiple datetime formats
-    let formats = [
-        "%Y-%m-%d %H:%M:%S%.f",
-        "%Y-%m-%d %H:%M:%S",
-        "%Y-%m-%dT%H:%M:%S%.f",
-        "%Y-%m-%dT%H:%M:%S",
-        "%Y/%m/%d %H:%M:%S",
-        "%d/%b/%Y:%H:%M:%S", // Apache log format
-        "%Y-%m-%d",
-        "%H:%M:%S",
-    ];
-
-    for format in &formats {
-        if let Ok(dt) = NaiveDateTime::parse_from_str(time_str, format) {
-            return Some(dt.and_utc().timestamp_millis());
-        }
-    }
-
-    // Try parsing as timestamp
-    if let Ok(timestamp) = time_str.parse::<i64>() {
-        return Some(timestamp * 1000); // Convert to milliseconds
-    }
-
-    None
+/// Milliseconds since the Unix epoch of a `--start`/`--end` bound, read as a wall-clock time
+/// in the zone of a zoned time column
+fn parse_bound(time_str: &str, dtype: &DataType) -> Option<i64> {
+    bound_for(parse_datetime_auto(time_str)?, Some(dtype)).map(|dt| dt.and_utc().timestamp_millis())
 }
@@ -0,0 +1,69 @@
+use crate::controllers::datetime::{localize, parse_timezone, polars_timezone, DatetimeParser};
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use chrono::DateTime;
+use chrono_tz::Tz;
+use polars::prelude::*;
+
+/// Turn a column into a polars `Datetime` with a time zone.
+///
+/// Text, dates and naive datetimes are wall-clock times in `tz` (UTC by default). Epoch
+/// timestamps and zoned datetimes already name instants and are only converted to `tz`.
+pub fn todatetime(
+    df: &LazyFrame,
+    colname: &str,
+    tz: Option<&str>,
+    parser: &DatetimeParser,
+) -> Result<LazyFrame, QsvError> {
+    let schema = df.clone().collect_schema()?;
+    let dtype = schema
+        .get(colname)
+        .cloned()
+        .ok_or_else(|| QsvError::column_not_found(colname, "todatetime"))?;
+    let tz = tz.map(parse_timezone).transpose()?;
+
+    LogController::debug(&format!(
+        "Converting column '{colname}' ({dtype}) to a datetime in {} (input: {parser:?})",
+        tz.map_or("its own time zone or UTC", |tz| tz.name())
+    ));
+
+    let expr = if let DataType::Datetime(_, Some(zone)) = &dtype {
+        let zone = match tz {
+            Some(tz) => polars_timezone(tz)?,
+            None => zone.clone(),
+        };
+        col(colname).cast(DataType::Datetime(TimeUnit::Microseconds, Some(zone)))
+    } else {
+        let tz = tz.unwrap_or(Tz::UTC);
+        let target = DataType::Datetime(TimeUnit::Microseconds, Some(polars_timezone(tz)?));
+        let parsed = parser.to_datetime_expr(col(colname), TimeUnit::Microseconds, "todatetime");
+        if parser.epoch.is_some() || tz == Tz::UTC {
+            // Casting a naive datetime to a zone reads it as UTC
+            parsed.cast(target)
+        } else {
+            let output = target.clone();
+            parsed.map(
+                move |c: Column| {
+                    let instants: Int64Chunked = c
+                        .datetime()?
+                        .into_iter()
+                        .map(|us| {
+                            let wall_clock = DateTime::from_timestamp_micros(us?)?.naive_utc();
+                            localize(wall_clock, tz, false)
+                                .map(|dt| dt.and_utc().timestamp_micros())
+                        })
+                        .collect();
+                    Ok(Some(
+                        instants
+                            .with_name(c.name().clone())
+                            .into_series()
+                            .cast(&output)?
+                            .into(),
+                    ))
+                },
+                GetOutput::from_type(target),
+            )
+        }
+    };
+    Ok(df.clone().with_column(expr.alias(colname)))
+}
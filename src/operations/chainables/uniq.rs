@@ -1,6 +1,7 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
-pub fn uniq(df: &LazyFrame) -> LazyFrame {
+pub fn uniq(df: &LazyFrame) -> Result<LazyFrame, QsvError> {
     LogController::debug("Applying uniq - removing duplicates based on all columns");
-    df.clone().unique_stable(None, UniqueKeepStrategy::First)
+    Ok(df.clone().unique_stable(None, UniqueKeepStrategy::First))
 }
@@ -0,0 +1,522 @@
+use crate::controllers::datetime::format_datetimes;
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Width of a chart in characters, labels and values included, when `--width` is not given
+pub const DEFAULT_CHART_WIDTH: usize = 80;
+/// Number of histogram bins when `--bins` is not given
+pub const DEFAULT_HISTOGRAM_BINS: usize = 10;
+// Bars and sparklines never shrink below this many cells, whatever the labels take
+const MIN_PLOT_WIDTH: usize = 10;
+
+/// How the values of a chart are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChartType {
+    /// One horizontal bar per row (per label and series with `--by`)
+    #[default]
+    Bar,
+    /// Horizontal bars counting the values falling into equal-width bins
+    Hist,
+    /// One sparkline per series, left to right in row order
+    Spark,
+}
+
+impl ChartType {
+    pub const NAMES: &'static str = "bar, hist, spark";
+
+    pub fn from_name(name: &str) -> Result<Self, QsvError> {
+        match name.to_lowercase().as_str() {
+            "bar" | "bars" => Ok(Self::Bar),
+            "hist" | "histogram" => Ok(Self::Hist),
+            "spark" | "sparkline" => Ok(Self::Spark),
+            _ => Err(QsvError::InvalidArgument(format!(
+                "Unsupported chart type '{name}'. Use: {}",
+                Self::NAMES
+            ))),
+        }
+    }
+}
+
+/// Layout and scale of a chart beyond the column it draws
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub chart_type: ChartType,
+    /// Column labelling each bar or sparkline point (the first other column when unset)
+    pub x: Option<String>,
+    /// Column splitting the values into one series per value
+    pub by: Option<String>,
+    /// Width of each line in characters
+    pub width: usize,
+    /// Rows of a sparkline, or the most bars a bar chart or histogram draws
+    pub height: Option<usize>,
+    /// Number of histogram bins
+    pub bins: usize,
+    /// Scale lengths by `ln(1 + value)` so small values stay visible next to spikes
+    pub log: bool,
+    /// Draw with ASCII characters only
+    pub ascii: bool,
+}
+
+impl Default for ChartOptions {
+    fn default() -> Self {
+        Self {
+            chart_type: ChartType::default(),
+            x: None,
+            by: None,
+            width: DEFAULT_CHART_WIDTH,
+            height: None,
+            bins: DEFAULT_HISTOGRAM_BINS,
+            log: false,
+            ascii: false,
+        }
+    }
+}
+
+// Values by label (rows) and series (columns); labels keep row order
+struct ChartData {
+    labels: Vec<String>,
+    series: Vec<String>,
+    values: Vec<Vec<Option<f64>>>,
+}
+
+// The characters a chart is drawn with
+struct Glyphs {
+    // Partial cells of a bar from 1/n to n/n, the last one being a full cell
+    bar: &'static [char],
+    // Sparkline levels from empty to a full cell
+    spark: &'static [char],
+    axis: char,
+    arrow: &'static str,
+}
+
+const UNICODE: Glyphs = Glyphs {
+    bar: &['▏', '▎', '▍', '▌', '▋', '▊', '▉', '█'],
+    spark: &[' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'],
+    axis: '│',
+    arrow: "→",
+};
+const ASCII: Glyphs = Glyphs {
+    bar: &['#'],
+    spark: &[' ', '.', ':', '-', '=', '+', '*', '#'],
+    axis: '|',
+    arrow: "->",
+};
+
+pub fn chart(df: &LazyFrame, column: &str, options: &ChartOptions) -> Result<(), QsvError> {
+    print!("{}", render_chart(df, column, options)?);
+    Ok(())
+}
+
+/// Draw `column` as text, one string holding every line of the chart
+pub fn render_chart(
+    df: &LazyFrame,
+    column: &str,
+    options: &ChartOptions,
+) -> Result<String, QsvError> {
+    LogController::debug(&format!(
+        "Charting column '{column}' with options: {options:?}"
+    ));
+
+    let schema = df.clone().collect_schema()?;
+    let dtype = schema
+        .get(column)
+        .ok_or_else(|| QsvError::column_not_found(column, "chart"))?;
+    if !dtype.is_primitive_numeric() {
+        return Err(QsvError::InvalidArgument(format!(
+            "chart needs a numeric column, but '{column}' is {dtype}"
+        )));
+    }
+    for name in options.x.iter().chain(options.by.iter()) {
+        if !schema.iter_names().any(|s| s == name) {
+            return Err(QsvError::column_not_found(name, "chart"));
+        }
+    }
+    if options.width == 0 || options.bins == 0 || options.height == Some(0) {
+        return Err(QsvError::InvalidArgument(
+            "chart --width, --height and --bins must be positive".to_string(),
+        ));
+    }
+
+    // Without --x, bars are labelled by the first other column, e.g. the bucket of a timeline
+    let label_column = options.x.clone().or_else(|| {
+        schema
+            .iter_names()
+            .find(|name| name.as_str() != column && Some(name.as_str()) != options.by.as_deref())
+            .map(|name| name.to_string())
+    });
+
+    let mut columns = vec![col(column).cast(DataType::Float64)];
+    columns.extend(
+        label_column
+            .iter()
+            .chain(options.by.iter())
+            .map(|name| col(name.as_str())),
+    );
+    let frame = format_datetimes(&df.clone().select(columns))?
+        .with_columns(
+            label_column
+                .iter()
+                .chain(options.by.iter())
+                .map(|name| col(name.as_str()).cast(DataType::String))
+                .collect::<Vec<_>>(),
+        )
+        .collect()?;
+
+    let values: Vec<Option<f64>> = frame.column(column)?.f64()?.into_iter().collect();
+    let text_column = |name: &Option<String>| -> Result<Option<Vec<String>>, QsvError> {
+        name.as_ref()
+            .map(|name| {
+                Ok(frame
+                    .column(name)?
+                    .str()?
+                    .into_iter()
+                    .map(|s| s.unwrap_or("null").to_string())
+                    .collect())
+            })
+            .transpose()
+    };
+    let labels = text_column(&label_column)?
+        .unwrap_or_else(|| (1..=values.len()).map(|i| i.to_string()).collect());
+    let series = text_column(&options.by)?;
+
+    let mut title = match options.chart_type {
+        ChartType::Hist => format!("{column} histogram"),
+        _ => format!("{column} by {}", label_column.as_deref().unwrap_or("row")),
+    };
+    if let Some(by) = &options.by {
+        title.push_str(&format!(", per {by}"));
+    }
+    if options.log {
+        title.push_str(" (log scale)");
+    }
+
+    let glyphs = if options.ascii { &ASCII } else { &UNICODE };
+    Ok(match options.chart_type {
+        ChartType::Bar => {
+            let data = match &series {
+                Some(series) => by_series(&labels, series, &values),
+                None => ChartData {
+                    labels,
+                    series: vec![column.to_string()],
+                    values: values.into_iter().map(|v| vec![v]).collect(),
+                },
+            };
+            render_bars(&title, &data, options, glyphs)
+        }
+        ChartType::Hist => {
+            let series = series.unwrap_or_else(|| vec![column.to_string(); values.len()]);
+            let data = histogram(&series, &values, options.bins);
+            render_bars(&title, &data, options, glyphs)
+        }
+        ChartType::Spark => {
+            let series = series.unwrap_or_else(|| vec![column.to_string(); values.len()]);
+            render_sparklines(
+                &title,
+                &by_series(&labels, &series, &values),
+                options,
+                glyphs,
+            )
+        }
+    })
+}
+
+// Pivot rows into one column per series; values repeated for a label and series are added
+fn by_series(labels: &[String], series: &[String], values: &[Option<f64>]) -> ChartData {
+    let mut data = ChartData {
+        labels: Vec::new(),
+        series: Vec::new(),
+        values: Vec::new(),
+    };
+    let mut rows: HashMap<&str, usize> = HashMap::new();
+    for ((label, name), value) in labels.iter().zip(series).zip(values) {
+        let s = match data.series.iter().position(|s| s == name) {
+            Some(s) => s,
+            None => {
+                data.series.push(name.clone());
+                data.values.iter_mut().for_each(|row| row.push(None));
+                data.series.len() - 1
+            }
+        };
+        let row = *rows.entry(label.as_str()).or_insert_with(|| {
+            data.labels.push(label.clone());
+            data.values.push(vec![None; data.series.len()]);
+            data.labels.len() - 1
+        });
+        let cell = &mut data.values[row][s];
+        *cell = match (*cell, value) {
+            (Some(a), Some(b)) => Some(a + b),
+            (a, b) => a.or(*b),
+        };
+    }
+    data
+}
+
+// Count the finite values of each series falling into `bins` equal-width bins shared by all
+fn histogram(series: &[String], values: &[Option<f64>], bins: usize) -> ChartData {
+    let finite = || values.iter().flatten().filter(|v| v.is_finite());
+    let (lo, hi) = finite().fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+        (lo.min(v), hi.max(v))
+    });
+    let mut names: Vec<String> = Vec::new();
+    for name in series {
+        if !names.contains(name) {
+            names.push(name.clone());
+        }
+    }
+    if lo > hi {
+        return ChartData {
+            labels: Vec::new(),
+            series: names,
+            values: Vec::new(),
+        };
+    }
+
+    // A column holding one value gets one bin
+    let bins = if lo == hi { 1 } else { bins };
+    let step = (hi - lo) / bins as f64;
+    let mut counts = vec![vec![0.0; names.len()]; bins];
+    for (name, value) in series.iter().zip(values) {
+        let Some(v) = value.filter(|v| v.is_finite()) else {
+            continue;
+        };
+        let bin = if step > 0.0 {
+            (((v - lo) / step) as usize).min(bins - 1)
+        } else {
+            0
+        };
+        let s = names.iter().position(|n| n == name).unwrap_or(0);
+        counts[bin][s] += 1.0;
+    }
+    let labels = (0..bins)
+        .map(|bin| {
+            let start = lo + step * bin as f64;
+            if bin + 1 == bins {
+                format!("[{}, {}]", format_number(start), format_number(hi))
+            } else {
+                let end = lo + step * (bin + 1) as f64;
+                format!("[{}, {})", format_number(start), format_number(end))
+            }
+        })
+        .collect();
+    ChartData {
+        labels,
+        series: names,
+        values: counts
+            .into_iter()
+            .map(|row| row.into_iter().map(Some).collect())
+            .collect(),
+    }
+}
+
+// Map values onto 0..=1 of the largest one, or of ln(1 + value) on a log scale; bars start at
+// zero, so negative values have no length
+fn scaler(data: &ChartData, log: bool) -> impl Fn(Option<f64>) -> f64 {
+    let transform = move |v: f64| if log { v.max(0.0).ln_1p() } else { v.max(0.0) };
+    let max = data
+        .values
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|v| v.is_finite())
+        .map(|&v| transform(v))
+        .fold(0.0, f64::max);
+    move |value| match value {
+        Some(v) if v.is_finite() && max > 0.0 => transform(v) / max,
+        _ => 0.0,
+    }
+}
+
+fn render_bars(title: &str, data: &ChartData, options: &ChartOptions, glyphs: &Glyphs) -> String {
+    let mut out = format!("{title}\n");
+    if data.labels.is_empty() {
+        out.push_str("(no values)\n");
+        return out;
+    }
+    let shown = options.height.unwrap_or(usize::MAX).min(data.labels.len());
+    let show_series = options.by.is_some();
+    let label_width = text_width(data.labels.iter().take(shown));
+    let series_width = if show_series {
+        text_width(data.series.iter())
+    } else {
+        0
+    };
+    let value_texts: Vec<Vec<String>> = data.values[..shown]
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|v| v.map(format_number).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    let value_width = text_width(value_texts.iter().flatten());
+    let margin = label_width + usize::from(show_series) * (series_width + 1) + value_width + 3;
+    let plot_width = options.width.saturating_sub(margin).max(MIN_PLOT_WIDTH);
+    let scale = scaler(data, options.log);
+
+    for (row, label) in data.labels.iter().take(shown).enumerate() {
+        for (s, name) in data.series.iter().enumerate() {
+            // A label is written once above the bars of all its series
+            let label = if s == 0 { label.as_str() } else { "" };
+            let mut line = pad(label, label_width);
+            if show_series {
+                line.push(' ');
+                line.push_str(&pad(name, series_width));
+            }
+            line.push(' ');
+            line.push(glyphs.axis);
+            line.push_str(&bar(scale(data.values[row][s]), plot_width, glyphs));
+            line.push(' ');
+            line.push_str(&value_texts[row][s]);
+            out.push_str(line.trim_end());
+            out.push('\n');
+        }
+    }
+    if shown < data.labels.len() {
+        out.push_str(&format!(
+            "... {} more; raise --height to draw them\n",
+            data.labels.len() - shown
+        ));
+    }
+    out
+}
+
+fn render_sparklines(
+    title: &str,
+    data: &ChartData,
+    options: &ChartOptions,
+    glyphs: &Glyphs,
+) -> String {
+    let mut out = String::new();
+    if data.labels.is_empty() {
+        out.push_str(&format!("{title}\n(no values)\n"));
+        return out;
+    }
+    let height = options.height.unwrap_or(1);
+    let name_width = text_width(data.series.iter());
+    let summaries: Vec<String> = (0..data.series.len())
+        .map(|s| {
+            let column = data.values.iter().filter_map(|row| row[s]);
+            let (lo, hi) = column.fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            });
+            if lo > hi {
+                "no values".to_string()
+            } else {
+                format!("min {} max {}", format_number(lo), format_number(hi))
+            }
+        })
+        .collect();
+    let summary_width = text_width(summaries.iter());
+    let cells = options
+        .width
+        .saturating_sub(name_width + summary_width + 3)
+        .max(MIN_PLOT_WIDTH);
+
+    // Squeeze long series into the width, keeping the largest value of the rows of each cell
+    let per_cell = data.labels.len().div_ceil(cells);
+    let points: Vec<Vec<Option<f64>>> = data
+        .values
+        .chunks(per_cell)
+        .map(|rows| {
+            (0..data.series.len())
+                .map(|s| rows.iter().filter_map(|row| row[s]).reduce(f64::max))
+                .collect()
+        })
+        .collect();
+    let squeezed = ChartData {
+        labels: Vec::new(),
+        series: data.series.clone(),
+        values: points,
+    };
+    let scale = scaler(&squeezed, options.log);
+
+    out.push_str(title);
+    if per_cell > 1 {
+        out.push_str(&format!(", largest of {per_cell} rows per cell"));
+    }
+    out.push('\n');
+    let steps = glyphs.spark.len() - 1;
+    for (s, name) in data.series.iter().enumerate() {
+        let levels: Vec<Option<usize>> = squeezed
+            .values
+            .iter()
+            .map(|row| row[s].map(|v| level(scale(Some(v)), height * steps)))
+            .collect();
+        for line in 0..height {
+            let base = (height - 1 - line) * steps;
+            let last = line + 1 == height;
+            let mut text = pad(if last { name } else { "" }, name_width);
+            text.push(' ');
+            text.push(glyphs.axis);
+            text.extend(levels.iter().map(|level| match level {
+                Some(level) => glyphs.spark[level.saturating_sub(base).min(steps)],
+                None => ' ',
+            }));
+            if last {
+                text.push_str("  ");
+                text.push_str(&summaries[s]);
+            }
+            out.push_str(text.trim_end());
+            out.push('\n');
+        }
+    }
+    let first = &data.labels[0];
+    let last = &data.labels[data.labels.len() - 1];
+    let range = if data.labels.len() == 1 {
+        first.clone()
+    } else {
+        format!("{first} {} {last}", glyphs.arrow)
+    };
+    out.push_str(&format!("{} {range}\n", " ".repeat(name_width + 1)));
+    out
+}
+
+// A bar of `fraction` of `width` cells; values above zero always get a sliver
+fn bar(fraction: f64, width: usize, glyphs: &Glyphs) -> String {
+    let parts = glyphs.bar.len();
+    let units = level(fraction, width * parts);
+    let mut text = String::with_capacity(width);
+    for _ in 0..units / parts {
+        text.push(glyphs.bar[parts - 1]);
+    }
+    let partial = units % parts;
+    if partial > 0 {
+        text.push(glyphs.bar[partial - 1]);
+    }
+    text
+}
+
+// `fraction` of `steps`, rounded, with at least one step for anything above zero
+fn level(fraction: f64, steps: usize) -> usize {
+    let level = (fraction * steps as f64).round() as usize;
+    if fraction > 0.0 {
+        level.max(1)
+    } else {
+        level
+    }
+}
+
+fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{value:.0}");
+    }
+    let text = if value.abs() < 1.0 {
+        format!("{value:.4}")
+    } else {
+        format!("{value:.2}")
+    };
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn text_width<'a>(texts: impl Iterator<Item = &'a String>) -> usize {
+    texts.map(|t| t.chars().count()).max().unwrap_or(0)
+}
+
+fn pad(text: &str, width: usize) -> String {
+    format!(
+        "{text}{}",
+        " ".repeat(width.saturating_sub(text.chars().count()))
+    )
+}
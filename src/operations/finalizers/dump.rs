@@ -1,80 +1,206 @@
 use crate::controllers::batch::calculate_batch_size;
+use crate::controllers::compression::{
+    create_output, data_extension, CompressedWriter, Compression,
+};
+use crate::controllers::datetime::format_datetimes;
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use chrono::Local;
 use polars::prelude::*;
-use std::fs::File;
-use std::io::{BufWriter, Write};
-use std::path::PathBuf;
+use serde_json::{Number, Value as JsonValue};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
-pub fn dump(df: &LazyFrame, output_path_opt: Option<&str>, separator: char) {
-    let output_path_str = output_path_opt.map(|p| p.to_string()).unwrap_or_else(|| {
-        let now = Local::now();
-        format!("dump_{}.csv", now.format("%Y%m%d_%H%M%S"))
-    });
+/// Output formats supported by `dump`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DumpFormat {
+    Csv,
+    Tsv,
+    Ndjson,
+    Json,
+    Parquet,
+    Ipc,
+    Markdown,
+    Html,
+}
+
+impl DumpFormat {
+    /// Parse a `--format` value
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "csv" => Some(Self::Csv),
+            "tsv" => Some(Self::Tsv),
+            "ndjson" | "jsonl" => Some(Self::Ndjson),
+            "json" => Some(Self::Json),
+            "parquet" => Some(Self::Parquet),
+            "ipc" | "arrow" | "feather" => Some(Self::Ipc),
+            "markdown" | "md" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+
+    /// Guess the format from the output file extension, looking past a compression
+    /// extension such as `.gz` (defaults to CSV)
+    pub fn from_path(path: &Path) -> Self {
+        match data_extension(path).as_deref() {
+            Some("tsv") | Some("tab") => Self::Tsv,
+            Some("ndjson") | Some("jsonl") => Self::Ndjson,
+            Some("json") => Self::Json,
+            Some("parquet") | Some("pq") => Self::Parquet,
+            Some("arrow") | Some("ipc") | Some("feather") => Self::Ipc,
+            Some("md") | Some("markdown") => Self::Markdown,
+            Some("html") | Some("htm") => Self::Html,
+            _ => Self::Csv,
+        }
+    }
+
+    /// File extension used for auto-generated output names
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Csv => "csv",
+            Self::Tsv => "tsv",
+            Self::Ndjson => "ndjson",
+            Self::Json => "json",
+            Self::Parquet => "parquet",
+            Self::Ipc => "arrow",
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+
+    /// Whether the format stores column types, so datetimes are written as datetimes
+    fn is_typed(&self) -> bool {
+        matches!(self, Self::Parquet | Self::Ipc)
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Csv => "CSV",
+            Self::Tsv => "TSV",
+            Self::Ndjson => "NDJSON",
+            Self::Json => "JSON",
+            Self::Parquet => "Parquet",
+            Self::Ipc => "Arrow IPC",
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+        }
+    }
+}
+
+/// Parse a `--format` value, rejecting unknown formats
+pub fn parse_dump_format(name: &str) -> Result<DumpFormat, QsvError> {
+    DumpFormat::from_name(name).ok_or_else(|| {
+        QsvError::InvalidArgument(format!(
+            "Unsupported dump format '{name}'. Supported formats: csv, tsv, ndjson, json, parquet, ipc, markdown, html"
+        ))
+    })
+}
+
+/// Generate the default timestamped output file name for a format
+pub fn default_output_path(format: Option<DumpFormat>) -> String {
+    let now = Local::now();
+    format!(
+        "dump_{}.{}",
+        now.format("%Y%m%d_%H%M%S"),
+        format.unwrap_or(DumpFormat::Csv).extension()
+    )
+}
+
+// Resolve the output path and format shared by both dump entry points
+fn resolve_output(
+    output_path_opt: Option<&str>,
+    format: Option<DumpFormat>,
+) -> Result<(String, DumpFormat), QsvError> {
+    let output_path_str = output_path_opt
+        .map(|p| p.to_string())
+        .unwrap_or_else(|| default_output_path(format));
 
     if output_path_str == "-" {
-        eprintln!("Error: The 'dump' command requires a file path. To print to stdout, use the 'show' command instead.");
-        return;
+        return Err(QsvError::InvalidArgument(
+            "The 'dump' command requires a file path. To print to stdout, use the 'show' command instead.".to_string(),
+        ));
+    }
+    let format = format.unwrap_or_else(|| DumpFormat::from_path(Path::new(&output_path_str)));
+    if matches!(format, DumpFormat::Parquet | DumpFormat::Ipc) {
+        if let Some(codec) = Compression::from_path(Path::new(&output_path_str)) {
+            return Err(QsvError::InvalidArgument(format!(
+                "{} output is compressed internally and cannot be written as {}; drop the '.{}' extension",
+                format.name(),
+                codec.name(),
+                codec.extension()
+            )));
+        }
     }
-    LogController::debug(&format!("Dumping DataFrame to CSV: {output_path_str}"));
-    dump_traditional(df, &output_path_str, separator);
+    Ok((output_path_str, format))
+}
+
+pub fn dump(
+    df: &LazyFrame,
+    output_path_opt: Option<&str>,
+    separator: Option<char>,
+    format: Option<DumpFormat>,
+) -> Result<(), QsvError> {
+    let (output_path_str, format) = resolve_output(output_path_opt, format)?;
+    LogController::debug(&format!(
+        "Dumping DataFrame to {}: {output_path_str}",
+        format.name()
+    ));
+    let df = &text_datetimes(df, format)?;
+    dump_traditional(df, &output_path_str, separator, format)
 }
 
 pub fn dump_with_batch_size(
     df: &LazyFrame,
     output_path_opt: Option<&str>,
-    separator: char,
+    separator: Option<char>,
+    format: Option<DumpFormat>,
     batch_size_bytes: usize,
-) {
-    let output_path_str = output_path_opt.map(|p| p.to_string()).unwrap_or_else(|| {
-        let now = Local::now();
-        format!("dump_{}.csv", now.format("%Y%m%d_%H%M%S"))
-    });
-
-    if output_path_str == "-" {
-        eprintln!("Error: The 'dump' command requires a file path. To print to stdout, use the 'show' command instead.");
-        return;
-    }
+) -> Result<(), QsvError> {
+    let (output_path_str, format) = resolve_output(output_path_opt, format)?;
+    let df = &text_datetimes(df, format)?;
 
     LogController::debug(&format!(
         "Dumping DataFrame with batch size: {}MB",
         batch_size_bytes / 1_048_576
     ));
 
-    LogController::debug(&format!("Dumping DataFrame to CSV: {output_path_str}"));
+    LogController::debug(&format!(
+        "Dumping DataFrame to {}: {output_path_str}",
+        format.name()
+    ));
     let output_path = PathBuf::from(&output_path_str);
 
-    match File::create(&output_path) {
-        Ok(file) => {
-            let writer = BufWriter::new(file);
-            if let Err(e) = dump_streaming_internal(df, writer, separator, batch_size_bytes) {
-                LogController::debug(&format!("Streaming dump failed: {e}"));
-                LogController::info("Falling back to traditional dump method");
-                // Fallback needs to be handled carefully as the file might be partially written
-                // For simplicity, we let dump_traditional overwrite the file.
-                dump_traditional(df, &output_path_str, separator);
-            } else {
-                LogController::info(&format!(
-                    "Successfully dumped large dataset to: {}",
-                    output_path.display()
-                ));
-            }
-        }
-        Err(e) => {
-            eprintln!(
-                "Error: Failed to create file '{}': {}",
-                output_path.display(),
-                e
-            );
-        }
+    let writer = create_output(&output_path)?;
+    if let Err(e) = dump_streaming_internal(df, writer, separator, format, batch_size_bytes) {
+        LogController::debug(&format!("Streaming dump failed: {e}"));
+        LogController::info("Falling back to traditional dump method");
+        // Fallback needs to be handled carefully as the file might be partially written
+        // For simplicity, we let dump_traditional overwrite the file.
+        return dump_traditional(df, &output_path_str, separator, format);
+    }
+    LogController::info(&format!(
+        "Successfully dumped large dataset to: {}",
+        output_path.display()
+    ));
+    Ok(())
+}
+
+// Text formats write datetimes formatted like `show`; typed formats keep them as they are
+fn text_datetimes(df: &LazyFrame, format: DumpFormat) -> Result<LazyFrame, QsvError> {
+    if format.is_typed() {
+        Ok(df.clone())
+    } else {
+        format_datetimes(df)
     }
 }
 
 /// Stream dump for large datasets to any writer (file or stdout)
 fn dump_streaming_internal<W: Write>(
     df: &LazyFrame,
-    mut writer: W,
-    separator: char,
+    writer: CompressedWriter<W>,
+    separator: Option<char>,
+    format: DumpFormat,
     batch_size_bytes: usize,
 ) -> Result<(), Box<dyn std::error::Error>> {
     let batch_size_rows = calculate_batch_size(df, batch_size_bytes)?;
@@ -84,9 +210,10 @@ fn dump_streaming_internal<W: Write>(
         batch_size_bytes / 1_048_576
     ));
 
+    let schema = df.clone().collect_schema()?;
+    let mut sink = DumpSink::new(writer, format, separator, &schema)?;
     let mut current_offset = 0;
     let mut total_rows = 0;
-    let mut header_written = false;
 
     loop {
         let mut batch_df = df
@@ -104,20 +231,7 @@ fn dump_streaming_internal<W: Write>(
             current_offset + batch_df.height()
         ));
 
-        // Use a temporary buffer to write each batch, then write buffer to the writer
-        // Estimate buffer size: ~100 bytes per row on average for CSV output
-        let estimated_buffer_size = batch_df.height() * 100;
-        let mut buf = Vec::with_capacity(estimated_buffer_size);
-        CsvWriter::new(&mut buf)
-            .include_header(!header_written) // Write header only for the first batch
-            .with_separator(separator as u8)
-            .finish(&mut batch_df)?;
-
-        writer.write_all(&buf)?;
-
-        if !header_written {
-            header_written = true;
-        }
+        sink.write_batch(&mut batch_df)?;
 
         let processed_rows = batch_df.height();
         total_rows += processed_rows;
@@ -128,44 +242,296 @@ fn dump_streaming_internal<W: Write>(
         }
     }
 
-    writer.flush()?;
+    if let Some(writer) = sink.finish()? {
+        writer.finish()?;
+    }
     LogController::info(&format!("Successfully streamed {total_rows} rows"));
     Ok(())
 }
 
 /// Traditional dump method (fallback for simple cases or stdout)
-fn dump_traditional(df: &LazyFrame, output_path_str: &str, separator: char) {
+fn dump_traditional(
+    df: &LazyFrame,
+    output_path_str: &str,
+    separator: Option<char>,
+    format: DumpFormat,
+) -> Result<(), QsvError> {
     LogController::debug("Using traditional dump method");
 
-    let mut df_collected = match df.clone().collect() {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!("Error: Failed to collect DataFrame for dumping: {e}");
-            eprintln!("Tip: For very large files, the streaming approach should have worked.");
-            eprintln!("      Try reducing data size with 'head', 'select', or other filters.");
-            return;
-        }
-    };
+    let mut df_collected = df.clone().collect()?;
 
     let output_path = PathBuf::from(output_path_str);
-    let result = match File::create(&output_path) {
-        Ok(file) => CsvWriter::new(file)
-            .include_header(true)
-            .with_separator(separator as u8)
-            .finish(&mut df_collected),
-        Err(e) => {
-            eprintln!(
-                "Error: Failed to create file '{}': {}",
-                output_path.display(),
-                e
-            );
-            return;
+    let writer = create_output(&output_path)?;
+    let schema = df_collected.schema().clone();
+    DumpSink::new(writer, format, separator, &schema)
+        .and_then(|mut sink| {
+            sink.write_batch(&mut df_collected)?;
+            if let Some(writer) = sink.finish()? {
+                writer.finish()?;
+            }
+            Ok(())
+        })
+        .map_err(|e| {
+            QsvError::Io(format!(
+                "writing {} to '{output_path_str}': {e}",
+                format.name()
+            ))
+        })?;
+    LogController::info(&format!("Successfully dumped to: {output_path_str}"));
+    Ok(())
+}
+
+// Incremental writer for one output format; batches are appended in order and
+// `finish` writes any trailer (closing brackets, parquet footer, ...)
+enum DumpSink<W: Write> {
+    Csv {
+        writer: W,
+        separator: u8,
+        header_written: bool,
+    },
+    Ndjson {
+        writer: W,
+    },
+    Json {
+        writer: W,
+        first_row: bool,
+    },
+    Parquet(Box<polars::io::parquet::write::BatchedWriter<W>>),
+    Ipc(Box<polars::io::ipc::BatchedWriter<W>>),
+    Markdown {
+        writer: W,
+    },
+    Html {
+        writer: W,
+    },
+}
+
+impl<W: Write> DumpSink<W> {
+    fn new(
+        mut writer: W,
+        format: DumpFormat,
+        separator: Option<char>,
+        schema: &Schema,
+    ) -> PolarsResult<Self> {
+        let sink = match format {
+            DumpFormat::Csv | DumpFormat::Tsv => {
+                let default_separator = if format == DumpFormat::Tsv { '\t' } else { ',' };
+                Self::Csv {
+                    writer,
+                    separator: separator.unwrap_or(default_separator) as u8,
+                    header_written: false,
+                }
+            }
+            DumpFormat::Ndjson => Self::Ndjson { writer },
+            DumpFormat::Json => {
+                writer.write_all(b"[")?;
+                Self::Json {
+                    writer,
+                    first_row: true,
+                }
+            }
+            DumpFormat::Parquet => Self::Parquet(Box::new(
+                ParquetWriter::new(writer)
+                    .with_compression(ParquetCompression::Snappy)
+                    .batched(schema)?,
+            )),
+            DumpFormat::Ipc => Self::Ipc(Box::new(IpcWriter::new(writer).batched(schema)?)),
+            DumpFormat::Markdown => {
+                let names: Vec<String> = schema
+                    .iter_names()
+                    .map(|name| escape_markdown(name))
+                    .collect();
+                writeln!(writer, "| {} |", names.join(" | "))?;
+                writeln!(writer, "|{}", " --- |".repeat(names.len()))?;
+                Self::Markdown { writer }
+            }
+            DumpFormat::Html => {
+                writeln!(writer, "<table>")?;
+                writeln!(writer, "  <thead>")?;
+                writeln!(writer, "    <tr>")?;
+                for name in schema.iter_names() {
+                    writeln!(writer, "      <th>{}</th>", escape_html(name))?;
+                }
+                writeln!(writer, "    </tr>")?;
+                writeln!(writer, "  </thead>")?;
+                writeln!(writer, "  <tbody>")?;
+                Self::Html { writer }
+            }
+        };
+        Ok(sink)
+    }
+
+    fn write_batch(&mut self, batch: &mut DataFrame) -> PolarsResult<()> {
+        match self {
+            Self::Csv {
+                writer,
+                separator,
+                header_written,
+            } => {
+                // Use a temporary buffer to write each batch, then write buffer to the writer
+                // Estimate buffer size: ~100 bytes per row on average for CSV output
+                let mut buf = Vec::with_capacity(batch.height() * 100);
+                CsvWriter::new(&mut buf)
+                    .include_header(!*header_written) // Write header only for the first batch
+                    .with_separator(*separator)
+                    .finish(batch)?;
+                writer.write_all(&buf)?;
+                *header_written = true;
+            }
+            Self::Ndjson { writer } => {
+                for record in json_records(batch)? {
+                    writeln!(writer, "{record}")?;
+                }
+            }
+            Self::Json { writer, first_row } => {
+                for record in json_records(batch)? {
+                    if !*first_row {
+                        writer.write_all(b",")?;
+                    }
+                    write!(writer, "\n  {record}")?;
+                    *first_row = false;
+                }
+            }
+            // Batched writers read the batch chunk by chunk across all columns; columns
+            // computed by `map` (datetime parsing, for one) can be chunked differently
+            Self::Parquet(batched) => batched.write_batch(batch.align_chunks_par())?,
+            Self::Ipc(batched) => batched.write_batch(batch.align_chunks_par())?,
+            Self::Markdown { writer } => {
+                for row in text_rows(batch)? {
+                    let cells: Vec<String> = row.iter().map(|cell| escape_markdown(cell)).collect();
+                    writeln!(writer, "| {} |", cells.join(" | "))?;
+                }
+            }
+            Self::Html { writer } => {
+                for row in text_rows(batch)? {
+                    writeln!(writer, "    <tr>")?;
+                    for cell in row {
+                        writeln!(writer, "      <td>{}</td>", escape_html(&cell))?;
+                    }
+                    writeln!(writer, "    </tr>")?;
+                }
+            }
         }
-    };
+        Ok(())
+    }
 
-    if let Err(e) = result {
-        eprintln!("Error writing CSV to '{output_path_str}': {e}");
-    } else {
-        LogController::info(&format!("Successfully dumped to: {output_path_str}"));
+    // Returns the writer of text formats, so a compressed stream can be completed
+    fn finish(self) -> PolarsResult<Option<W>> {
+        match self {
+            Self::Csv { mut writer, .. }
+            | Self::Ndjson { mut writer }
+            | Self::Markdown { mut writer } => {
+                writer.flush()?;
+                Ok(Some(writer))
+            }
+            Self::Json {
+                mut writer,
+                first_row,
+            } => {
+                if first_row {
+                    writer.write_all(b"]\n")?;
+                } else {
+                    writer.write_all(b"\n]\n")?;
+                }
+                writer.flush()?;
+                Ok(Some(writer))
+            }
+            Self::Parquet(batched) => {
+                batched.finish()?;
+                Ok(None)
+            }
+            Self::Ipc(mut batched) => {
+                batched.finish()?;
+                Ok(None)
+            }
+            Self::Html { mut writer } => {
+                writeln!(writer, "  </tbody>")?;
+                writeln!(writer, "</table>")?;
+                writer.flush()?;
+                Ok(Some(writer))
+            }
+        }
+    }
+}
+
+// Materialized columns of a batch, so rows can be read by index
+fn batch_columns(batch: &DataFrame) -> Vec<Series> {
+    batch
+        .get_columns()
+        .iter()
+        .map(|column| column.as_materialized_series().rechunk())
+        .collect()
+}
+
+// Convert each row of a batch into a JSON object text; keys are written in column
+// order (serde_json::Map would sort them)
+fn json_records(batch: &DataFrame) -> PolarsResult<Vec<String>> {
+    let columns = batch_columns(batch);
+    let keys: Vec<String> = columns
+        .iter()
+        .map(|column| JsonValue::String(column.name().to_string()).to_string())
+        .collect();
+    let mut records = Vec::with_capacity(batch.height());
+    for row in 0..batch.height() {
+        let mut fields = Vec::with_capacity(columns.len());
+        for (key, column) in keys.iter().zip(&columns) {
+            fields.push(format!("{key}:{}", any_value_to_json(column.get(row)?)));
+        }
+        records.push(format!("{{{}}}", fields.join(",")));
+    }
+    Ok(records)
+}
+
+// Convert each row of a batch into display strings (nulls become empty cells)
+fn text_rows(batch: &DataFrame) -> PolarsResult<Vec<Vec<String>>> {
+    let columns = batch_columns(batch);
+    let mut rows = Vec::with_capacity(batch.height());
+    for row in 0..batch.height() {
+        let mut cells = Vec::with_capacity(columns.len());
+        for column in &columns {
+            cells.push(any_value_to_text(column.get(row)?));
+        }
+        rows.push(cells);
+    }
+    Ok(rows)
+}
+
+fn any_value_to_json(value: AnyValue) -> JsonValue {
+    match value {
+        AnyValue::Null => JsonValue::Null,
+        AnyValue::Boolean(b) => JsonValue::Bool(b),
+        AnyValue::Int8(v) => JsonValue::from(v),
+        AnyValue::Int16(v) => JsonValue::from(v),
+        AnyValue::Int32(v) => JsonValue::from(v),
+        AnyValue::Int64(v) => JsonValue::from(v),
+        AnyValue::UInt8(v) => JsonValue::from(v),
+        AnyValue::UInt16(v) => JsonValue::from(v),
+        AnyValue::UInt32(v) => JsonValue::from(v),
+        AnyValue::UInt64(v) => JsonValue::from(v),
+        AnyValue::Float32(v) => Number::from_f64(v as f64)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        AnyValue::Float64(v) => Number::from_f64(v)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        other => JsonValue::String(any_value_to_text(other)),
+    }
+}
+
+fn any_value_to_text(value: AnyValue) -> String {
+    match value {
+        AnyValue::Null => String::new(),
+        other => other.str_value().to_string(),
     }
 }
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
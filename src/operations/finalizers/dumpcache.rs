@@ -1,10 +1,11 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use chrono;
 use polars::prelude::*;
 use std::fs::File;
 use std::path::PathBuf;
 
-pub fn dumpcache(df: &LazyFrame, output_path_opt: Option<&str>) {
+pub fn dumpcache(df: &LazyFrame, output_path_opt: Option<&str>) -> Result<(), QsvError> {
     LogController::debug("Applying dumpcache (save DataFrame as parquet cache)");
 
     let output_path = if let Some(path_str) = output_path_opt {
@@ -31,42 +32,27 @@ pub fn dumpcache(df: &LazyFrame, output_path_opt: Option<&str>) {
     ));
 
     // Collect the LazyFrame first to ensure data is available
-    let mut df_collected = match df.clone().collect() {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!("Error: Failed to collect DataFrame for caching: {e}");
-            return;
-        }
-    };
+    let mut df_collected = df.clone().collect()?;
 
     // Write to Parquet file
-    match File::create(&final_path) {
-        Ok(file) => {
-            match ParquetWriter::new(file)
-                .with_compression(ParquetCompression::Snappy)
-                .finish(&mut df_collected)
-            {
-                Ok(_) => {
-                    LogController::info(&format!(
-                        "DataFrame cache saved successfully to: {}",
-                        final_path.display()
-                    ));
-                }
-                Err(e) => {
-                    eprintln!(
-                        "Error writing parquet cache to file '{}': {}",
-                        final_path.display(),
-                        e
-                    );
-                }
-            }
-        }
-        Err(e) => {
-            eprintln!(
-                "Error: Failed to create cache file '{}': {}",
-                final_path.display(),
-                e
-            );
-        }
-    }
+    let file = File::create(&final_path).map_err(|e| {
+        QsvError::Io(format!(
+            "creating cache file '{}': {e}",
+            final_path.display()
+        ))
+    })?;
+    ParquetWriter::new(file)
+        .with_compression(ParquetCompression::Snappy)
+        .finish(&mut df_collected)
+        .map_err(|e| {
+            QsvError::Io(format!(
+                "writing parquet cache to file '{}': {e}",
+                final_path.display()
+            ))
+        })?;
+    LogController::info(&format!(
+        "DataFrame cache saved successfully to: {}",
+        final_path.display()
+    ));
+    Ok(())
 }
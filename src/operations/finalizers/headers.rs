@@ -1,17 +1,12 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::{Cell, Table};
 use polars::prelude::*;
 
-pub fn headers(df: &LazyFrame, plain: bool) {
+pub fn headers(df: &LazyFrame, plain: bool) -> Result<(), QsvError> {
     // Get schema from LazyFrame without collecting
-    let schema = match df.clone().collect_schema() {
-        Ok(schema) => schema,
-        Err(e) => {
-            eprintln!("Error getting schema: {e}");
-            return;
-        }
-    };
+    let schema = df.clone().collect_schema()?;
 
     let column_names: Vec<String> = schema.iter_names().map(|s| s.to_string()).collect();
     LogController::debug(&format!("Showing headers: {} columns", column_names.len()));
@@ -29,4 +24,5 @@ pub fn headers(df: &LazyFrame, plain: bool) {
         }
         println!("{table}");
     }
+    Ok(())
 }
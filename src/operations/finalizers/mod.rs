@@ -1,3 +1,4 @@
+pub mod chart;
 pub mod dump;
 pub mod dumpcache;
 pub mod headers;
@@ -1,13 +1,21 @@
+use crate::controllers::compression::{create_output, Compression};
+use crate::controllers::datetime::format_datetimes;
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::Path;
 
-pub fn partition(df: &LazyFrame, colname: &str, output_dir: &str) {
+/// Write one CSV file per value of `colname`, compressed with `compression` when given
+pub fn partition(
+    df: &LazyFrame,
+    colname: &str,
+    output_dir: &str,
+    compression: Option<Compression>,
+) -> Result<(), QsvError> {
     // First, check if the column exists in the schema without collecting the DataFrame
-    if df.clone().collect_schema().unwrap().get(colname).is_none() {
-        eprintln!("Error: Column '{colname}' not found in DataFrame for partition operation");
-        std::process::exit(1);
+    if df.clone().collect_schema()?.get(colname).is_none() {
+        return Err(QsvError::column_not_found(colname, "partition"));
     }
 
     LogController::debug(&format!(
@@ -16,69 +24,47 @@ pub fn partition(df: &LazyFrame, colname: &str, output_dir: &str) {
 
     // Create output directory if it doesn't exist
     let output_path = Path::new(output_dir);
-    if let Err(e) = fs::create_dir_all(output_path) {
-        eprintln!("Error creating output directory '{output_dir}': {e}");
-        std::process::exit(1);
-    }
+    fs::create_dir_all(output_path)
+        .map_err(|e| QsvError::Io(format!("creating output directory '{output_dir}': {e}")))?;
 
-    // Collect the DataFrame once
-    let collected_df = match df.clone().collect() {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!("Error collecting DataFrame for partition: {e}");
-            std::process::exit(1);
-        }
-    };
+    // Collect the DataFrame once, with datetimes written like `show` writes them
+    let collected_df = format_datetimes(df)?.collect()?;
 
     // Use partition_by for efficient grouping
-    match collected_df.partition_by([colname], true) {
-        Ok(groups) => {
-            let num_groups = groups.len();
-            LogController::info(&format!("Found {num_groups} unique groups to partition."));
+    let groups = collected_df.partition_by([colname], true)?;
+    let num_groups = groups.len();
+    LogController::info(&format!("Found {num_groups} unique groups to partition."));
 
-            let mut files_created = 0;
-            for mut group_df in groups {
-                // The first value in the partition column determines the file name
-                let value_any = match group_df.column(colname).and_then(|c| c.get(0)) {
-                    Ok(val) => val,
-                    Err(e) => {
-                        eprintln!("Error: Could not get partition value: {e}");
-                        continue;
-                    }
-                };
+    let mut files_created = 0;
+    for mut group_df in groups {
+        // The first value in the partition column determines the file name
+        let value_any = group_df.column(colname)?.get(0)?;
 
-                let value_str = anyvalue_to_string(value_any);
-                let safe_filename = sanitize_filename(&value_str);
-                let output_file = output_path.join(format!("{safe_filename}.csv"));
+        let value_str = anyvalue_to_string(value_any);
+        let safe_filename = sanitize_filename(&value_str);
+        let output_file = match compression {
+            Some(codec) => output_path.join(format!("{safe_filename}.csv.{}", codec.extension())),
+            None => output_path.join(format!("{safe_filename}.csv")),
+        };
 
-                // Write the group DataFrame to a CSV file
-                match write_csv_file(&mut group_df, &output_file) {
-                    Ok(_) => {
-                        files_created += 1;
-                        LogController::info(&format!(
-                            "Created partition file: {} ({} rows)",
-                            output_file.display(),
-                            group_df.height()
-                        ));
-                    }
-                    Err(e) => {
-                        LogController::error(&format!(
-                            "Error writing partition file '{}': {}",
-                            output_file.display(),
-                            e
-                        ));
-                    }
-                }
-            }
-            LogController::info(&format!(
-                "Partition complete: {files_created} files created in '{output_dir}'"
-            ));
-        }
-        Err(e) => {
-            eprintln!("Error partitioning DataFrame: {e}");
-            std::process::exit(1);
-        }
+        // Write the group DataFrame to a CSV file
+        write_csv_file(&mut group_df, &output_file).map_err(|e| {
+            QsvError::Io(format!(
+                "writing partition file '{}': {e}",
+                output_file.display()
+            ))
+        })?;
+        files_created += 1;
+        LogController::info(&format!(
+            "Created partition file: {} ({} rows)",
+            output_file.display(),
+            group_df.height()
+        ));
     }
+    LogController::info(&format!(
+        "Partition complete: {files_created} files created in '{output_dir}'"
+    ));
+    Ok(())
 }
 
 fn anyvalue_to_string(val: AnyValue) -> String {
@@ -120,12 +106,13 @@ fn sanitize_filename(filename: &str) -> String {
 
 fn write_csv_file(
     df: &mut DataFrame,
-    output_path: &PathBuf,
+    output_path: &Path,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let file = fs::File::create(output_path)?;
-    CsvWriter::new(file)
+    let mut writer = create_output(output_path)?;
+    CsvWriter::new(&mut writer)
         .include_header(true)
         .finish(df)
         .map_err(|e| Box::new(e) as Box<dyn std::error::Error>)?;
+    writer.finish()?;
     Ok(())
 }
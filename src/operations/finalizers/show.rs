@@ -1,27 +1,31 @@
 use crate::controllers::batch::calculate_batch_size;
+use crate::controllers::datetime::format_datetimes;
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 use std::io::{BufWriter, Write};
 
-pub fn show(df: &LazyFrame) {
+pub fn show(df: &LazyFrame) -> Result<(), QsvError> {
     LogController::debug("Showing DataFrame with traditional method");
-    show_traditional(df);
+    show_traditional(&format_datetimes(df)?)
 }
 
-pub fn show_with_batch_size(df: &LazyFrame, batch_size_bytes: usize) {
+pub fn show_with_batch_size(df: &LazyFrame, batch_size_bytes: usize) -> Result<(), QsvError> {
     LogController::debug(&format!(
         "Showing DataFrame with streaming support (batch size: {}MB)",
         batch_size_bytes / 1_048_576
     ));
 
+    let df = &format_datetimes(df)?;
     let stdout = std::io::stdout();
     let writer = BufWriter::new(stdout);
 
     if let Err(e) = show_streaming_internal(df, writer, batch_size_bytes) {
         LogController::debug(&format!("Streaming show failed: {e}"));
         LogController::debug("Falling back to traditional show method");
-        show_traditional(df);
+        return show_traditional(df);
     }
+    Ok(())
 }
 
 /// Memory-efficient streaming show for large datasets
@@ -86,38 +90,24 @@ fn show_streaming_internal<W: Write>(
 }
 
 /// Traditional show method (fallback)
-fn show_traditional(df: &LazyFrame) {
+fn show_traditional(df: &LazyFrame) -> Result<(), QsvError> {
     LogController::debug("Using traditional show method");
 
-    match df.clone().collect() {
-        Ok(mut df_collected) => {
-            // By default, Polars prints a table to stdout
-            // To emulate the previous CSV output, we use CsvWriter
-            // Estimate buffer size based on data size
-            let estimated_size = df_collected.height() * 100; // ~100 bytes per row estimate
-            let mut buf = Vec::with_capacity(estimated_size);
-            if CsvWriter::new(&mut buf)
-                .include_header(true)
-                .with_separator(b',')
-                .finish(&mut df_collected)
-                .is_ok()
-            {
-                // The `show` command in many tools prints to stdout.
-                // We will write the buffer to stdout.
-                if let Ok(s) = String::from_utf8(buf) {
-                    println!("{s}");
-                    LogController::debug("Successfully showed DataFrame as CSV to stdout");
-                } else {
-                    eprintln!("Error: Could not convert buffer to UTF-8 string");
-                }
-            } else {
-                eprintln!("Error writing to buffer");
-            }
-        }
-        Err(e) => {
-            eprintln!("Error: Failed to collect DataFrame: {e}");
-            eprintln!("Tip: For very large files, the streaming approach should have worked.");
-            eprintln!("      Try using 'head <n>' to limit the number of rows.");
-        }
-    }
+    let mut df_collected = df.clone().collect()?;
+    // By default, Polars prints a table to stdout
+    // To emulate the previous CSV output, we use CsvWriter
+    // Estimate buffer size based on data size
+    let estimated_size = df_collected.height() * 100; // ~100 bytes per row estimate
+    let mut buf = Vec::with_capacity(estimated_size);
+    CsvWriter::new(&mut buf)
+        .include_header(true)
+        .with_separator(b',')
+        .finish(&mut df_collected)?;
+    // The `show` command in many tools prints to stdout.
+    // We will write the buffer to stdout.
+    let s = String::from_utf8(buf)
+        .map_err(|e| QsvError::Io(format!("converting output to UTF-8: {e}")))?;
+    println!("{s}");
+    LogController::debug("Successfully showed DataFrame as CSV to stdout");
+    Ok(())
 }
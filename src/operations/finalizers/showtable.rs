@@ -1,21 +1,19 @@
+use crate::controllers::datetime::format_datetimes;
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use comfy_table::presets::UTF8_FULL;
 use comfy_table::{Cell, ContentArrangement, Table};
 use polars::prelude::*;
 
 const MAX_DISPLAY_ROWS: usize = 8;
 
-pub fn showtable(df: &LazyFrame) {
+pub fn showtable(df: &LazyFrame) -> Result<(), QsvError> {
     LogController::debug("Applying showtable (display DataFrame as a formatted table)");
 
     // Try to estimate the size using limit + head approach to avoid full collection
-    let head_df = match df.clone().limit((MAX_DISPLAY_ROWS + 1) as u32).collect() {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!("Error: Failed to collect DataFrame for showtable: {e}");
-            return;
-        }
-    };
+    let head_df = format_datetimes(df)?
+        .limit((MAX_DISPLAY_ROWS + 1) as u32)
+        .collect()?;
 
     let is_truncated = head_df.height() > MAX_DISPLAY_ROWS;
     let display_df = if is_truncated {
@@ -52,7 +50,7 @@ pub fn showtable(df: &LazyFrame) {
     for row_idx in 0..shape.0 {
         let mut row_cells = Vec::new();
         for col_name in &colnames {
-            let s = display_df.column(col_name).unwrap();
+            let s = display_df.column(col_name)?;
             let val_result = s.get(row_idx);
             let cell_content = match val_result {
                 Ok(val) => format_anyvalue(&val),
@@ -73,6 +71,7 @@ pub fn showtable(df: &LazyFrame) {
     }
 
     println!("{table}");
+    Ok(())
 }
 
 fn format_anyvalue(val: &AnyValue) -> String {
@@ -1,18 +1,13 @@
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use comfy_table::{presets::UTF8_FULL, Cell, Color, Table};
 use polars::prelude::*;
 
-pub fn stats(df: &LazyFrame) {
+pub fn stats(df: &LazyFrame) -> Result<(), QsvError> {
     LogController::debug("Calculating statistics for DataFrame using lazy evaluation");
 
     // Get schema to understand the columns and their types
-    let schema = match df.clone().collect_schema() {
-        Ok(schema) => schema,
-        Err(e) => {
-            eprintln!("Error: Failed to get DataFrame schema: {e}");
-            return;
-        }
-    };
+    let schema = df.clone().collect_schema()?;
 
     let mut table = Table::new();
     table.load_preset(UTF8_FULL);
@@ -25,30 +20,22 @@ pub fn stats(df: &LazyFrame) {
     table.set_header(header_cells);
 
     // Calculate statistics using lazy evaluation
-    let stats_result = calculate_stats_lazy(df, &column_names, &schema);
-
-    match stats_result {
-        Ok(stats_data) => {
-            // Build table rows from calculated statistics
-            table.add_row(build_stat_row("count", &stats_data.counts));
-            table.add_row(build_stat_row("null_count", &stats_data.null_counts));
-            table.add_row(build_stat_row("datatype", &stats_data.dtypes));
-            table.add_row(build_stat_row("mean", &stats_data.means));
-            table.add_row(build_stat_row("std", &stats_data.stds));
-            table.add_row(build_stat_row("min", &stats_data.mins));
-            table.add_row(build_stat_row("25%", &stats_data.p25s));
-            table.add_row(build_stat_row("50% (median)", &stats_data.p50s));
-            table.add_row(build_stat_row("75%", &stats_data.p75s));
-            table.add_row(build_stat_row("max", &stats_data.maxs));
-
-            println!("{table}");
-        }
-        Err(e) => {
-            eprintln!("Error calculating statistics: {e}");
-            LogController::debug("Falling back to traditional stats calculation");
-            stats_fallback(df);
-        }
-    }
+    let stats_data = calculate_stats_lazy(df, &column_names, &schema)?;
+
+    // Build table rows from calculated statistics
+    table.add_row(build_stat_row("count", &stats_data.counts));
+    table.add_row(build_stat_row("null_count", &stats_data.null_counts));
+    table.add_row(build_stat_row("datatype", &stats_data.dtypes));
+    table.add_row(build_stat_row("mean", &stats_data.means));
+    table.add_row(build_stat_row("std", &stats_data.stds));
+    table.add_row(build_stat_row("min", &stats_data.mins));
+    table.add_row(build_stat_row("25%", &stats_data.p25s));
+    table.add_row(build_stat_row("50% (median)", &stats_data.p50s));
+    table.add_row(build_stat_row("75%", &stats_data.p75s));
+    table.add_row(build_stat_row("max", &stats_data.maxs));
+
+    println!("{table}");
+    Ok(())
 }
 
 struct StatsData {
@@ -68,7 +55,7 @@ fn calculate_stats_lazy(
     df: &LazyFrame,
     column_names: &[String],
     schema: &Schema,
-) -> Result<StatsData, Box<dyn std::error::Error>> {
+) -> PolarsResult<StatsData> {
     let mut stats_data = StatsData {
         counts: Vec::new(),
         null_counts: Vec::new(),
@@ -254,18 +241,3 @@ fn build_stat_row(stat_name: &str, values: &[String]) -> Vec<Cell> {
     }
     row
 }
-
-// Fallback to original implementation if lazy evaluation fails
-fn stats_fallback(df: &LazyFrame) {
-    LogController::debug("Using fallback stats calculation");
-    let _df_collected = match df.clone().collect() {
-        Ok(df) => df,
-        Err(e) => {
-            eprintln!("Error: Failed to collect DataFrame: {e}");
-            return;
-        }
-    };
-
-    // ... existing fallback implementation would go here ...
-    eprintln!("Fallback stats calculation not yet implemented");
-}
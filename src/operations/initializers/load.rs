@@ -1,5 +1,8 @@
+use crate::controllers::compression::{data_extension, is_zip_path};
 use crate::controllers::csv::{exists_path, CsvController};
+use crate::controllers::json::{is_json_path, JsonController};
 use crate::controllers::log::LogController;
+use crate::error::QsvError;
 use polars::prelude::*;
 use std::path::PathBuf;
 pub fn load(
@@ -8,11 +11,8 @@ pub fn load(
     low_memory: bool,
     no_headers: bool,
     chunk_size: Option<usize>,
-) -> LazyFrame {
-    if !exists_path(paths) {
-        eprintln!("One or more files do not exist");
-        std::process::exit(1);
-    }
+) -> Result<LazyFrame, QsvError> {
+    exists_path(paths)?;
     LogController::debug(&format!(
         "{} files are loaded. [{}]",
         paths.len(),
@@ -29,62 +29,65 @@ pub fn load(
             .map(|ext| ext.to_lowercase() == "parquet")
             .unwrap_or(false)
     });
+    let has_json = paths.iter().any(|path| is_json_path(path));
     let has_csv = paths.iter().any(|path| {
-        let ext = path
-            .extension()
-            .and_then(|ext| ext.to_str())
-            .map(|ext| ext.to_lowercase());
-        matches!(ext, Some(ref e) if e == "csv" || e == "tsv" || e == "gz" || e == "txt")
+        if is_json_path(path) {
+            return false;
+        }
+        // Compressed files are classified by the extension inside, e.g. `logs.csv.zst`
+        let ext = data_extension(path);
+        is_zip_path(path)
+            || matches!(ext, Some(ref e) if e == "csv" || e == "tsv" || e == "txt")
             || ext.is_none() // Files without extension are assumed to be CSV
     });
-    // Cannot mix parquet and CSV files
+    // Cannot mix parquet, JSON and CSV files
     if has_parquet && has_csv {
-        eprintln!("Error: Cannot mix parquet and CSV files in the same load command");
-        std::process::exit(1);
+        return Err(QsvError::InvalidArgument(
+            "Cannot mix parquet and CSV files in the same load command".to_string(),
+        ));
+    }
+    if has_json && (has_parquet || has_csv) {
+        return Err(QsvError::InvalidArgument(
+            "Cannot mix JSON and CSV/parquet files in the same load command".to_string(),
+        ));
     }
     if has_parquet {
         load_parquet_files(paths)
+    } else if has_json {
+        load_json_files(paths)
     } else {
         load_csv_files(paths, separator, low_memory, no_headers, chunk_size)
     }
 }
-fn load_parquet_files(paths: &[PathBuf]) -> LazyFrame {
+fn load_parquet_files(paths: &[PathBuf]) -> Result<LazyFrame, QsvError> {
+    let scan = |path: &PathBuf| {
+        LazyFrame::scan_parquet(path, ScanArgsParquet::default())
+            .map_err(|e| QsvError::Io(format!("reading parquet file {}: {e}", path.display())))
+    };
     if paths.len() == 1 {
-        LazyFrame::scan_parquet(&paths[0], ScanArgsParquet::default()).unwrap_or_else(|e| {
-            eprintln!("Error reading parquet file {}: {}", paths[0].display(), e);
-            std::process::exit(1);
-        })
+        scan(&paths[0])
     } else {
         // Concatenate multiple parquet files
-        let mut dataframes = Vec::new();
-        for path in paths {
-            let df =
-                LazyFrame::scan_parquet(path, ScanArgsParquet::default()).unwrap_or_else(|e| {
-                    eprintln!("Error reading parquet file {}: {}", path.display(), e);
-                    std::process::exit(1);
-                });
-            dataframes.push(df);
-        }
-        concat(
+        let dataframes = paths.iter().map(scan).collect::<Result<Vec<_>, _>>()?;
+        Ok(concat(
             dataframes,
             UnionArgs {
                 parallel: true,
                 rechunk: true,
                 ..Default::default()
             },
-        )
-        .unwrap_or_else(|e| {
-            eprintln!("Error concatenating parquet files: {e}");
-            std::process::exit(1);
-        })
+        )?)
     }
 }
+fn load_json_files(paths: &[PathBuf]) -> Result<LazyFrame, QsvError> {
+    JsonController::new(paths).get_dataframe()
+}
 fn load_csv_files(
     paths: &[PathBuf],
     separator: &str,
     low_memory: bool,
     no_headers: bool,
     chunk_size: Option<usize>,
-) -> LazyFrame {
+) -> Result<LazyFrame, QsvError> {
     CsvController::new(paths).get_dataframe(separator, low_memory, no_headers, chunk_size)
 }
@@ -0,0 +1,439 @@
+use crate::controllers::command::{
+    command_line, parse_column_names, sql_tables, validate_command_options, Command,
+    FINALIZER_COMMANDS,
+};
+use crate::controllers::log::LogController;
+use crate::error::QsvError;
+use crate::operations::finalizers::dump::{default_output_path, parse_dump_format};
+use crate::operations::quilters::quilt::QuiltConfig;
+use serde_yml::{Mapping, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Name of the process stage holding an exported command-line pipeline
+pub const EXPORT_STAGE_NAME: &str = "pipeline";
+
+/// Title of exported quilts when `--title` is not given
+pub const DEFAULT_EXPORT_TITLE: &str = "Exported qsv pipeline";
+
+/// Convert a parsed command-line pipeline into a quilt with a single process stage.
+///
+/// Every command becomes a step with the argument keys the quilt runner reads, so running
+/// the quilt gives the same result as the command line. Relative input paths are rewritten
+/// for `yaml_dir`, the directory the quilt is saved in, since quilts read inputs relative
+/// to their own location. Tables given to `sql --table` become stages of their own.
+pub fn commands_to_quilt(
+    commands: &[Command],
+    title: &str,
+    yaml_dir: &Path,
+) -> Result<QuiltConfig, QsvError> {
+    let paths = PathRewriter::new(yaml_dir);
+    let mut stages = Mapping::new();
+    let mut steps = Mapping::new();
+    for cmd in commands {
+        validate_command_options(cmd)?;
+        let args = match cmd.name.as_str() {
+            "sql" => {
+                for (name, path) in sql_tables(cmd)? {
+                    if name == EXPORT_STAGE_NAME || stages.contains_key(name.as_str()) {
+                        return Err(QsvError::InvalidArgument(format!(
+                            "Cannot export sql table '{name}': the name is already used by another stage"
+                        )));
+                    }
+                    let mut load = Mapping::new();
+                    load.insert("path".into(), paths.rewrite(&path).into());
+                    stages.insert(
+                        name.into(),
+                        process_stage(Mapping::from_iter([("load".into(), Value::Mapping(load))])),
+                    );
+                }
+                step_args(cmd, &paths)?
+            }
+            _ => step_args(cmd, &paths)?,
+        };
+        // Steps are mapping keys, so repeated commands get a trailing `_` per repetition
+        let mut step_name = cmd.name.clone();
+        while steps.contains_key(step_name.as_str()) {
+            step_name.push('_');
+        }
+        steps.insert(step_name.into(), args);
+    }
+    if commands
+        .last()
+        .is_some_and(|cmd| !FINALIZER_COMMANDS.contains(&cmd.name.as_str()))
+    {
+        steps.insert("showtable".into(), Value::Null);
+    }
+    stages.insert(EXPORT_STAGE_NAME.into(), process_stage(steps));
+
+    Ok(QuiltConfig {
+        title: title.to_string(),
+        description: Some(format!(
+            "Exported from: qsv {}",
+            commands
+                .iter()
+                .map(command_line)
+                .collect::<Vec<_>>()
+                .join(" - ")
+        )),
+        version: None,
+        author: None,
+        params: None,
+        max_parallel: None,
+        stages,
+    })
+}
+
+/// Write a command-line pipeline to `output_path` as a quilt YAML file
+pub fn save_quilt(
+    commands: &[Command],
+    output_path: &str,
+    title: Option<&str>,
+) -> Result<(), QsvError> {
+    if commands.is_empty() {
+        return Err(QsvError::InvalidArgument(
+            "'toquilt' needs a pipeline to export, e.g. qsv load data.csv - head 5 - toquilt pipeline.yaml".to_string(),
+        ));
+    }
+    let output = Path::new(output_path);
+    let yaml_dir = output
+        .parent()
+        .filter(|p| !p.as_os_str().is_empty())
+        .unwrap_or_else(|| Path::new("."));
+    let config = commands_to_quilt(commands, title.unwrap_or(DEFAULT_EXPORT_TITLE), yaml_dir)?;
+    let yaml = serde_yml::to_string(&config)
+        .map_err(|e| QsvError::Io(format!("serializing quilt: {e}")))?;
+    if !yaml_dir.exists() {
+        fs::create_dir_all(yaml_dir)
+            .map_err(|e| QsvError::Io(format!("creating directory {}: {e}", yaml_dir.display())))?;
+    }
+    fs::write(output, yaml)
+        .map_err(|e| QsvError::Io(format!("writing quilt file {output_path}: {e}")))?;
+    LogController::info(&format!(
+        "Saved {} command(s) as quilt '{}' to {output_path}",
+        commands.len(),
+        config.title
+    ));
+    Ok(())
+}
+
+fn process_stage(steps: Mapping) -> Value {
+    let mut stage = Mapping::new();
+    stage.insert("type".into(), "process".into());
+    stage.insert("steps".into(), Value::Mapping(steps));
+    Value::Mapping(stage)
+}
+
+// Arguments of the quilt step equivalent to one command
+fn step_args(cmd: &Command, paths: &PathRewriter) -> Result<Value, QsvError> {
+    let mut args = StepArgs::default();
+    let arg = |i: usize| -> Result<&str, QsvError> {
+        cmd.args.get(i).map(|s| s.as_str()).ok_or_else(|| {
+            QsvError::InvalidArgument(format!(
+                "'{}' is missing a required argument; see 'qsv {} --help'",
+                cmd.name, cmd.name
+            ))
+        })
+    };
+    // Column lists accept ranges on the command line, so expand them the same way
+    let columns = |i: usize| -> Result<Vec<String>, QsvError> {
+        if cmd.args.len() == 1 {
+            parse_column_names(arg(i)?)
+        } else {
+            arg(i)?;
+            Ok(cmd.args.clone())
+        }
+    };
+    match cmd.name.as_str() {
+        "load" => {
+            arg(0)?;
+            let files: Vec<String> = cmd.args.iter().map(|p| paths.rewrite(p)).collect();
+            if let [file] = files.as_slice() {
+                args.set("path", file.as_str());
+            } else {
+                args.set("path", files);
+            }
+            args.option(cmd, "separator", "separator");
+            args.flag(cmd, "low_memory", "low_memory");
+            args.flag(cmd, "no_headers", "no_headers");
+            if let Some(size) = option(cmd, "chunk_size") {
+                let size: u64 = size.parse().map_err(|_| {
+                    QsvError::InvalidArgument(format!("Invalid --chunk-size '{size}'"))
+                })?;
+                args.set("chunk_size", size);
+            }
+        }
+        "select" => args.set("colnames", columns(0)?),
+        "isin" => {
+            args.set("colname", arg(0)?);
+            args.set("values", split_list(arg(1)?));
+        }
+        "contains" => {
+            args.set("colname", arg(0)?);
+            args.set("pattern", arg(1)?);
+            args.flag(cmd, "ignore_case", "ignorecase");
+        }
+        "filter" => {
+            arg(0)?;
+            args.set("expr", cmd.args.join(" "));
+        }
+        "join" => {
+            arg(0)?;
+            let files: Vec<String> = cmd.args.iter().map(|p| paths.rewrite(p)).collect();
+            if let [file] = files.as_slice() {
+                args.set("path", file.as_str());
+            } else {
+                args.set("path", files);
+            }
+            for key in ["on", "left_on", "right_on", "how", "suffix", "separator"] {
+                args.option(cmd, key, key);
+            }
+        }
+        "sed" => {
+            args.set("pattern", arg(0)?);
+            args.set("replacement", arg(1)?);
+            args.option(cmd, "column", "colname");
+            args.flag(cmd, "ignore_case", "ignorecase");
+        }
+        "grep" => {
+            args.set("pattern", arg(0)?);
+            args.flag(cmd, "ignore_case", "ignorecase");
+            args.flag(cmd, "invert_match", "invert_match");
+        }
+        "head" | "tail" => {
+            let number = cmd
+                .args
+                .first()
+                .map(|s| s.as_str())
+                .or_else(|| option(cmd, "number"));
+            if let Some(number) = number {
+                let number: u64 = number.parse().map_err(|_| {
+                    QsvError::InvalidArgument(format!(
+                        "'{}' command requires a valid number",
+                        cmd.name
+                    ))
+                })?;
+                args.set("number", number);
+            }
+        }
+        "sort" => {
+            args.set("colnames", columns(0)?);
+            args.flag(cmd, "desc", "desc");
+        }
+        "groupby" => {
+            args.set("by", columns(0)?);
+            args.option(cmd, "agg", "agg");
+        }
+        "sql" => {
+            arg(0)?;
+            args.set("query", cmd.args.join(" "));
+        }
+        "changetz" => {
+            args.set("colname", arg(0)?);
+            args.option(cmd, "from_tz", "from-tz");
+            args.option(cmd, "to_tz", "to-tz");
+            args.option(cmd, "input_format", "input_format");
+            args.option(cmd, "output_format", "output_format");
+            args.option(cmd, "ambiguous", "ambiguous");
+            args.option(cmd, "epoch", "epoch");
+            args.flag(cmd, "strict", "strict");
+        }
+        "renamecol" => {
+            args.set("old_name", arg(0)?);
+            args.set("new_name", arg(1)?);
+        }
+        "convert" => {
+            args.set("colname", arg(0)?);
+            args.option(cmd, "from", "from");
+            args.option(cmd, "to", "to");
+        }
+        "timeline" => {
+            args.set("time_column", arg(0)?);
+            args.option(cmd, "interval", "interval");
+            for agg_type in ["sum", "avg", "min", "max", "std", "agg", "by"] {
+                args.option(cmd, agg_type, agg_type);
+            }
+            args.flag(cmd, "fill", "fill");
+            args.option(cmd, "start", "start");
+            args.option(cmd, "end", "end");
+            args.option(cmd, "week_start", "week_start");
+            args.option(cmd, "tz", "tz");
+            args.option(cmd, "input_format", "input_format");
+            args.option(cmd, "epoch", "epoch");
+            args.flag(cmd, "strict", "strict");
+        }
+        "timeslice" => {
+            args.set("time_column", arg(0)?);
+            args.option(cmd, "start", "start");
+            args.option(cmd, "end", "end");
+            args.option(cmd, "input_format", "input_format");
+            args.option(cmd, "epoch", "epoch");
+            args.flag(cmd, "strict", "strict");
+        }
+        "timeround" => {
+            args.set("colname", arg(0)?);
+            args.option(cmd, "unit", "unit");
+            args.option(cmd, "output", "output");
+            args.option(cmd, "input_format", "input_format");
+            args.option(cmd, "epoch", "epoch");
+            args.flag(cmd, "strict", "strict");
+        }
+        "todatetime" => {
+            args.set("colname", arg(0)?);
+            args.option(cmd, "tz", "tz");
+            args.option(cmd, "input_format", "input_format");
+            args.option(cmd, "epoch", "epoch");
+            args.flag(cmd, "strict", "strict");
+        }
+        "pivot" => {
+            for key in ["rows", "cols", "values", "agg", "fill"] {
+                args.option(cmd, key, key);
+            }
+            args.flag(cmd, "row_totals", "row_totals");
+            args.flag(cmd, "col_totals", "col_totals");
+        }
+        "melt" => {
+            for key in ["id", "value_cols"] {
+                if let Some(list) = option(cmd, key) {
+                    args.set(key, parse_column_names(list)?);
+                }
+            }
+            args.option(cmd, "variable_name", "variable_name");
+            args.option(cmd, "value_name", "value_name");
+        }
+        "partition" => {
+            args.set("colname", arg(0)?);
+            if let Some(dir) = cmd.args.get(1) {
+                args.set("output_dir", dir.as_str());
+            }
+            args.option(cmd, "compression", "compression");
+        }
+        "show" => args.option(cmd, "batch_size", "batch-size"),
+        "headers" => args.flag(cmd, "plain", "plain"),
+        "chart" => {
+            args.set("column", arg(0)?);
+            args.option(cmd, "type", "type");
+            args.option(cmd, "x", "x");
+            args.option(cmd, "by", "by");
+            for key in ["width", "height", "bins"] {
+                if let Some(value) = option(cmd, key) {
+                    let value: u64 = value.parse().map_err(|_| {
+                        QsvError::InvalidArgument(format!(
+                            "Invalid value '{value}' for --{key}: expected a positive integer"
+                        ))
+                    })?;
+                    args.set(key, value);
+                }
+            }
+            args.flag(cmd, "log", "log");
+            args.flag(cmd, "ascii", "ascii");
+        }
+        "dump" => {
+            // Without -o the command line names the file after the time it ran, which a
+            // quilt cannot repeat, so the export fixes the name it would have used now
+            match option(cmd, "output") {
+                Some(path) => args.set("path", path),
+                None => {
+                    let format = option(cmd, "format").map(parse_dump_format).transpose()?;
+                    args.set("path", default_output_path(format));
+                }
+            }
+            args.option(cmd, "separator", "separator");
+            args.option(cmd, "format", "format");
+            args.option(cmd, "batch_size", "batch-size");
+        }
+        "dumpcache" => args.option(cmd, "output", "output"),
+        "count" | "uniq" | "showtable" | "stats" | "showquery" => {}
+        "quilt" | "toquilt" => {
+            return Err(QsvError::InvalidArgument(format!(
+                "'{}' cannot be part of an exported quilt",
+                cmd.name
+            )))
+        }
+        _ => {
+            return Err(QsvError::InvalidArgument(format!(
+                "Unknown command '{}'",
+                cmd.name
+            )))
+        }
+    }
+    Ok(args.into_value())
+}
+
+// The value of a command option, if given
+fn option<'a>(cmd: &'a Command, key: &str) -> Option<&'a str> {
+    cmd.options.get(key).and_then(|v| v.as_deref())
+}
+
+fn split_list(list: &str) -> Vec<String> {
+    list.split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect()
+}
+
+// Arguments of one quilt step, in insertion order
+#[derive(Default)]
+struct StepArgs(Mapping);
+
+impl StepArgs {
+    fn set(&mut self, key: &str, value: impl Into<Value>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    // Copy a command option's value to a step argument
+    fn option(&mut self, cmd: &Command, option_key: &str, arg_key: &str) {
+        if let Some(value) = option(cmd, option_key) {
+            self.set(arg_key, value);
+        }
+    }
+
+    // Copy a command flag as `true`
+    fn flag(&mut self, cmd: &Command, option_key: &str, arg_key: &str) {
+        if cmd.options.contains_key(option_key) {
+            self.set(arg_key, true);
+        }
+    }
+
+    // Steps without arguments are written as `step:` rather than `step: {}`
+    fn into_value(self) -> Value {
+        if self.0.is_empty() {
+            Value::Null
+        } else {
+            Value::Mapping(self.0)
+        }
+    }
+}
+
+// Quilts read relative input paths from their own directory, the command line from the
+// working directory
+struct PathRewriter {
+    absolute: bool,
+}
+
+impl PathRewriter {
+    fn new(yaml_dir: &Path) -> Self {
+        let same_dir = match (
+            fs::canonicalize(yaml_dir),
+            std::env::current_dir().and_then(fs::canonicalize),
+        ) {
+            (Ok(dir), Ok(cwd)) => dir == cwd,
+            // The directory does not exist yet, so it cannot be the working directory
+            _ => false,
+        };
+        PathRewriter {
+            absolute: !same_dir,
+        }
+    }
+
+    fn rewrite(&self, path: &str) -> String {
+        let p = Path::new(path);
+        if !self.absolute || p.is_absolute() {
+            return path.to_string();
+        }
+        std::path::absolute(p)
+            .unwrap_or_else(|_| PathBuf::from(path))
+            .display()
+            .to_string()
+    }
+}
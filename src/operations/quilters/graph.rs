@@ -0,0 +1,170 @@
+use crate::error::QsvError;
+use crate::operations::quilters::quilt::StageConfig;
+use std::collections::{BTreeSet, HashMap};
+
+/// Stage types understood by the quilt runner
+pub const STAGE_TYPES: &[&str] = &["process", "concat", "join"];
+
+/// A named quilt stage with its parsed configuration
+#[derive(Debug, Clone)]
+pub struct Stage {
+    pub name: String,
+    pub config: StageConfig,
+}
+
+impl Stage {
+    /// Stages whose results this stage reads, in declaration order and without duplicates
+    pub fn dependencies(&self) -> Vec<&str> {
+        let mut deps: Vec<&str> = Vec::new();
+        let named = self
+            .config
+            .source
+            .iter()
+            .chain(self.config.sources.iter().flatten())
+            .chain(self.config.depends_on.iter().flatten());
+        for name in named {
+            if !deps.contains(&name.as_str()) {
+                deps.push(name.as_str());
+            }
+        }
+        deps
+    }
+}
+
+/// Parse every entry of the quilt's `stages:` mapping, keeping YAML order
+pub fn parse_stages(stages: &serde_yml::Mapping) -> Result<Vec<Stage>, QsvError> {
+    stages
+        .iter()
+        .map(|(name, config)| parse_stage(name, config))
+        .collect()
+}
+
+/// Parse one entry of the quilt's `stages:` mapping
+pub fn parse_stage(name: &serde_yml::Value, config: &serde_yml::Value) -> Result<Stage, QsvError> {
+    let name = name
+        .as_str()
+        .ok_or_else(|| QsvError::Config(format!("Stage name {name:?} must be a string")))?
+        .to_string();
+    let config: StageConfig = serde_yml::from_value(config.clone())
+        .map_err(|e| QsvError::Parse(format!("config for stage '{name}': {e}")))?;
+    Ok(Stage { name, config })
+}
+
+/// Problems in the stage graph itself: unknown stage types, missing `sources` and
+/// references to undefined stages
+pub fn stage_problems(stages: &[Stage]) -> Vec<String> {
+    let names: BTreeSet<&str> = stages.iter().map(|s| s.name.as_str()).collect();
+    let mut problems = Vec::new();
+    for stage in stages {
+        if !STAGE_TYPES.contains(&stage.config.stage_type.as_str()) {
+            problems.push(format!(
+                "stage '{}': unknown stage type '{}' (expected one of: {})",
+                stage.name,
+                stage.config.stage_type,
+                STAGE_TYPES.join(", ")
+            ));
+        }
+        if matches!(stage.config.stage_type.as_str(), "concat" | "join")
+            && stage.config.sources.as_ref().is_none_or(|s| s.is_empty())
+        {
+            problems.push(format!(
+                "stage '{}': {} stage needs a 'sources' list",
+                stage.name, stage.config.stage_type
+            ));
+        }
+        for dep in stage.dependencies() {
+            if dep == stage.name {
+                problems.push(format!("stage '{}': depends on itself", stage.name));
+            } else if !names.contains(dep) {
+                problems.push(format!(
+                    "stage '{}': references undefined stage '{dep}'",
+                    stage.name
+                ));
+            }
+        }
+    }
+    problems
+}
+
+/// Indices of the stages each stage depends on; references must already be validated
+pub fn dependency_indices(stages: &[Stage]) -> Vec<Vec<usize>> {
+    let index: HashMap<&str, usize> = stages
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.name.as_str(), i))
+        .collect();
+    stages
+        .iter()
+        .map(|s| s.dependencies().iter().map(|d| index[d]).collect())
+        .collect()
+}
+
+/// Order stages so every stage runs after the stages it depends on.
+///
+/// Stages that are already in a valid order keep their YAML order. Unknown stage types,
+/// dangling references and cycles are all reported as one configuration error.
+pub fn execution_order(stages: &[Stage]) -> Result<Vec<usize>, QsvError> {
+    let problems = stage_problems(stages);
+    if !problems.is_empty() {
+        return Err(QsvError::Config(format!(
+            "Invalid quilt stages:\n  {}",
+            problems.join("\n  ")
+        )));
+    }
+
+    let deps = dependency_indices(stages);
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); stages.len()];
+    for (i, stage_deps) in deps.iter().enumerate() {
+        for &d in stage_deps {
+            dependents[d].push(i);
+        }
+    }
+
+    // Kahn's algorithm, always taking the earliest ready stage in YAML order
+    let mut remaining: Vec<usize> = deps.iter().map(|d| d.len()).collect();
+    let mut ready: BTreeSet<usize> = (0..stages.len()).filter(|&i| remaining[i] == 0).collect();
+    let mut order = Vec::with_capacity(stages.len());
+    while let Some(i) = ready.pop_first() {
+        order.push(i);
+        for &next in &dependents[i] {
+            remaining[next] -= 1;
+            if remaining[next] == 0 {
+                ready.insert(next);
+            }
+        }
+    }
+
+    if order.len() < stages.len() {
+        let cycle = find_cycle(&deps, &remaining)
+            .iter()
+            .map(|&i| stages[i].name.as_str())
+            .collect::<Vec<_>>()
+            .join(" -> ");
+        return Err(QsvError::Config(format!(
+            "Invalid quilt stages: dependency cycle {cycle}"
+        )));
+    }
+    Ok(order)
+}
+
+// Follow unresolved dependencies from an unscheduled stage until a stage repeats
+fn find_cycle(deps: &[Vec<usize>], remaining: &[usize]) -> Vec<usize> {
+    let Some(start) = (0..deps.len()).find(|&i| remaining[i] > 0) else {
+        return Vec::new();
+    };
+    let mut path = vec![start];
+    let mut current = start;
+    loop {
+        let Some(&next) = deps[current].iter().find(|&&d| remaining[d] > 0) else {
+            return path;
+        };
+        if let Some(pos) = path.iter().position(|&p| p == next) {
+            let mut cycle = path.split_off(pos);
+            cycle.push(next);
+            cycle.reverse();
+            return cycle;
+        }
+        path.push(next);
+        current = next;
+    }
+}
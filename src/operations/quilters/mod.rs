@@ -1 +1,6 @@
+pub mod export;
+pub mod graph;
+pub mod params;
 pub mod quilt;
+pub mod schema;
+pub mod validate;
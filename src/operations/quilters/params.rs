@@ -0,0 +1,149 @@
+use crate::error::QsvError;
+use serde_yml::Value;
+use std::collections::HashMap;
+
+/// Prefix for variables read from the environment, e.g. `${env:HOME}`
+pub const ENV_VAR_PREFIX: &str = "env:";
+
+/// Merge the quilt's `params:` defaults with command-line overrides.
+///
+/// A default of `~` (null) declares a parameter without a value, which must then be
+/// given on the command line if the quilt references it. String defaults may read
+/// environment variables with `${env:NAME}`.
+pub fn resolve_params(
+    defaults: Option<&serde_yml::Mapping>,
+    overrides: &HashMap<String, String>,
+) -> Result<HashMap<String, String>, QsvError> {
+    let mut params = HashMap::new();
+    for (key, value) in defaults.into_iter().flatten() {
+        let name = key.as_str().ok_or_else(|| {
+            QsvError::Config(format!("Quilt param name {key:?} must be a string"))
+        })?;
+        let value = match value {
+            Value::Null => continue,
+            Value::String(s) => {
+                // Defaults may read the environment, but not other params
+                let mut undefined = Vec::new();
+                let resolved = interpolate_str(s, &HashMap::new(), &mut undefined)?;
+                if !undefined.is_empty() {
+                    return Err(QsvError::Config(format!(
+                        "Default for quilt param '{name}' references undefined variable(s): {}",
+                        undefined.join(", ")
+                    )));
+                }
+                scalar_to_string(&resolved).unwrap_or_default()
+            }
+            Value::Bool(_) | Value::Number(_) => scalar_to_string(value).unwrap_or_default(),
+            _ => {
+                return Err(QsvError::Config(format!(
+                    "Default for quilt param '{name}' must be a scalar value"
+                )))
+            }
+        };
+        params.insert(name.to_string(), value);
+    }
+    params.extend(overrides.iter().map(|(k, v)| (k.clone(), v.clone())));
+    Ok(params)
+}
+
+fn scalar_to_string(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
+
+/// Parse a `name=value` command-line parameter
+pub fn parse_param(spec: &str) -> Result<(String, String), QsvError> {
+    match spec.split_once('=') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(QsvError::InvalidArgument(format!(
+            "Invalid quilt param '{spec}': expected <name>=<value>"
+        ))),
+    }
+}
+
+/// Substitute `${name}` and `${env:NAME}` in every string scalar of `value`.
+///
+/// Undefined variables are appended to `undefined` instead of failing immediately, so
+/// all of them can be reported at once. A string consisting of a single variable takes
+/// the type of its value, which lets `number: ${rows}` stay numeric.
+pub fn interpolate(
+    value: &Value,
+    params: &HashMap<String, String>,
+    undefined: &mut Vec<String>,
+) -> Result<Value, QsvError> {
+    Ok(match value {
+        Value::String(s) => interpolate_str(s, params, undefined)?,
+        Value::Sequence(seq) => Value::Sequence(
+            seq.iter()
+                .map(|v| interpolate(v, params, undefined))
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Mapping(map) => {
+            let mut out = serde_yml::Mapping::new();
+            for (k, v) in map {
+                out.insert(k.clone(), interpolate(v, params, undefined)?);
+            }
+            Value::Mapping(out)
+        }
+        other => other.clone(),
+    })
+}
+
+fn interpolate_str(
+    s: &str,
+    params: &HashMap<String, String>,
+    undefined: &mut Vec<String>,
+) -> Result<Value, QsvError> {
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    let mut whole_value = None;
+    while let Some(pos) = rest.find('$') {
+        out.push_str(&rest[..pos]);
+        let tail = &rest[pos..];
+        if let Some(after) = tail.strip_prefix("$$") {
+            // `$$` escapes a literal dollar sign
+            out.push('$');
+            rest = after;
+        } else if let Some(after) = tail.strip_prefix("${") {
+            let end = after.find('}').ok_or_else(|| {
+                QsvError::Config(format!("Unterminated variable reference in '{s}'"))
+            })?;
+            let name = after[..end].trim();
+            let resolved = match name.strip_prefix(ENV_VAR_PREFIX) {
+                Some(env_name) => std::env::var(env_name).ok(),
+                None => params.get(name).cloned(),
+            };
+            match resolved {
+                Some(v) => {
+                    if pos == 0 && end + 3 == s.len() && rest.len() == s.len() {
+                        whole_value = Some(v.clone());
+                    }
+                    out.push_str(&v);
+                }
+                None => {
+                    if !undefined.iter().any(|u| u == name) {
+                        undefined.push(name.to_string());
+                    }
+                }
+            }
+            rest = &after[end + 1..];
+        } else {
+            out.push('$');
+            rest = &tail[1..];
+        }
+    }
+    out.push_str(rest);
+
+    if let Some(v) = whole_value {
+        if let Ok(typed @ (Value::Number(_) | Value::Bool(_))) = serde_yml::from_str::<Value>(&v) {
+            return Ok(typed);
+        }
+    }
+    Ok(Value::String(out))
+}
@@ -1,25 +1,50 @@
 use crate::controllers::command::parse_batch_size;
+use crate::controllers::compression::Compression;
 use crate::controllers::dataframe::DataFrameController;
+use crate::controllers::datetime::DatetimeParser;
 use crate::controllers::log::LogController;
-use polars::prelude::{col, JoinType, LazyFrame};
+use crate::error::QsvError;
+use polars::prelude::{DataFrame, IntoLazy, LazyFrame};
 use serde::{Deserialize, Serialize};
 use serde_yml::Value;
-use std::collections::HashMap;
-use std::fs;
+use std::collections::{BTreeSet, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::mpsc;
 // Re-import operations to call them directly with LazyFrame
+use crate::operations::chainables::join::{self as join_op, AsofJoinOptions, DEFAULT_JOIN_SUFFIX};
 use crate::operations::chainables::{
-    changetz, contains, count, grep, head, isin, pivot, renamecol, sed, select, sort, tail,
-    timeline, timeslice, uniq,
+    changetz, contains, convert, count, filter, grep, groupby, head, isin, melt, pivot, renamecol,
+    sed, select, sort, sql, tail, timeline, timeround, timeslice, todatetime, uniq,
 };
 use crate::operations::finalizers::{
-    dump as dump_op, dumpcache as dumpcache_op, headers as headers_op, partition as partition_op,
-    show as show_op, showquery as showquery_op, showtable as showtable_op, stats as stats_op,
+    chart as chart_op, dump as dump_op, dumpcache as dumpcache_op, headers as headers_op,
+    partition as partition_op, show as show_op, showquery as showquery_op,
+    showtable as showtable_op, stats as stats_op,
 };
 use crate::operations::initializers::load as load_op;
+use crate::operations::quilters::graph::{
+    dependency_indices, execution_order, parse_stages, Stage,
+};
+use crate::operations::quilters::params::{interpolate, resolve_params};
+use crate::operations::quilters::validate::{check_quilt, invalid_quilt, read_quilt};
 // Type alias for chainable operation functions
-type ChainableOperation = fn(&LazyFrame, &Value) -> LazyFrame;
-type FinalizerOperation = fn(&LazyFrame, &Value);
+type ChainableOperation = fn(&LazyFrame, &Value) -> Result<LazyFrame, QsvError>;
+type FinalizerOperation = fn(&LazyFrame, &Value) -> Result<(), QsvError>;
+/// Values accepted by the `how` parameter of concat stages
+pub const CONCAT_STAGE_TYPES: &[&str] = &["vertical", "v", "horizontal", "h", "diagonal", "d"];
+/// Values accepted by the `how` parameter of join stages
+pub const JOIN_STAGE_TYPES: &[&str] = &[
+    "inner", "left", "right", "full", "outer", "semi", "anti", "cross", "asof",
+];
+// Finalizers that print to the console; they run in stage order so output never interleaves
+const CONSOLE_FINALIZERS: &[&str] = &[
+    "show",
+    "showtable",
+    "chart",
+    "headers",
+    "stats",
+    "showquery",
+];
 // Create a dispatch table for chainable operations
 fn create_chainable_dispatch_table() -> HashMap<&'static str, ChainableOperation> {
     let mut table: HashMap<&'static str, ChainableOperation> = HashMap::new();
@@ -47,6 +72,10 @@ fn create_chainable_dispatch_table() -> HashMap<&'static str, ChainableOperation
         let ignorecase = get_bool_from_value(args, "ignorecase");
         contains::contains(df, &colname, &pattern, ignorecase)
     });
+    table.insert("filter", |df, args| {
+        let expression = get_string_from_value(args, "expr").unwrap_or_default();
+        filter::filter(df, &expression)
+    });
     table.insert("sed", |df, args| {
         let colname = get_string_from_value(args, "colname");
         let pattern = get_string_from_value(args, "pattern").unwrap_or_default();
@@ -87,26 +116,49 @@ fn create_chainable_dispatch_table() -> HashMap<&'static str, ChainableOperation
         sort::sort(df, &colnames, desc)
     });
     table.insert("count", |df, _args| count::count(df));
+    table.insert("groupby", |df, args| {
+        let keys = get_list_from_value(args, "by")
+            .or_else(|| get_list_from_value(args, "keys"))
+            .unwrap_or_default();
+        // Aggregations are a single comma-separated string or a list of them
+        let aggregations = get_string_vec_from_value(args, "agg")
+            .or_else(|| get_string_from_value(args, "agg").map(|a| vec![a]))
+            .unwrap_or_default();
+        groupby::groupby(df, &keys, &aggregations)
+    });
     table.insert("uniq", |df, _args| uniq::uniq(df));
     table.insert("changetz", |df, args| {
         let colname = get_string_from_value(args, "colname").unwrap_or_default();
         let from_tz = get_string_from_value(args, "from-tz").unwrap_or_default();
         let to_tz = get_string_from_value(args, "to-tz").unwrap_or_default();
-        let input_format = get_string_from_value(args, "input_format")
-            .or_else(|| get_string_from_value(args, "input-format"))
-            .or_else(|| get_string_from_value(args, "format"));
         let output_format = get_string_from_value(args, "output_format")
             .or_else(|| get_string_from_value(args, "output-format"));
-        let ambiguous = get_string_from_value(args, "ambiguous");
-        changetz::changetz(
-            df,
-            &colname,
-            &from_tz,
-            &to_tz,
-            input_format.as_deref().unwrap_or("auto"),
-            output_format.as_deref().unwrap_or("auto"),
-            ambiguous.as_deref().unwrap_or("earliest"),
-        )
+        let defaults = changetz::ChangetzOptions::default();
+        let options = changetz::ChangetzOptions {
+            parser: get_datetime_parser(args)?,
+            output_format: output_format.unwrap_or(defaults.output_format),
+            ambiguous: get_string_from_value(args, "ambiguous").unwrap_or(defaults.ambiguous),
+        };
+        changetz::changetz(df, &colname, &from_tz, &to_tz, &options)
+    });
+    table.insert("convert", |df, args| {
+        let colname = get_string_from_value(args, "colname").unwrap_or_default();
+        let from = get_string_from_value(args, "from").unwrap_or_default();
+        let to = get_string_from_value(args, "to").unwrap_or_default();
+        convert::convert(df, &colname, &from, &to)
+    });
+    table.insert("timeround", |df, args| {
+        let colname = get_string_from_value(args, "colname").unwrap_or_default();
+        let unit = get_string_from_value(args, "unit").unwrap_or_default();
+        let output = get_string_from_value(args, "output");
+        let parser = get_datetime_parser(args)?;
+        timeround::timeround(df, &colname, &unit, output.as_deref(), &parser)
+    });
+    table.insert("todatetime", |df, args| {
+        let colname = get_string_from_value(args, "colname").unwrap_or_default();
+        let tz = get_string_from_value(args, "tz");
+        let parser = get_datetime_parser(args)?;
+        todatetime::todatetime(df, &colname, tz.as_deref(), &parser)
     });
     table.insert("renamecol", |df, args| {
         let old_name = get_string_from_value(args, "old_name")
@@ -120,34 +172,61 @@ fn create_chainable_dispatch_table() -> HashMap<&'static str, ChainableOperation
     table.insert("timeline", |df, args| {
         let time_column = get_string_from_value(args, "time_column").unwrap_or_default();
         let interval = get_string_from_value(args, "interval").unwrap_or_default();
-        let agg_type =
-            get_string_from_value(args, "agg_type").unwrap_or_else(|| "count".to_string());
-        let agg_column = get_string_from_value(args, "agg_column");
-        timeline::timeline(
-            df,
-            &time_column,
-            &interval,
-            &agg_type,
-            agg_column.as_deref(),
-        )
+        // agg_type/agg_column name a single aggregation, as written by older quilts
+        let mut aggregations: Vec<(String, String)> = get_string_from_value(args, "agg_type")
+            .zip(get_string_from_value(args, "agg_column"))
+            .into_iter()
+            .collect();
+        for agg_type in ["sum", "avg", "min", "max", "std"] {
+            for column in get_list_from_value(args, agg_type).unwrap_or_default() {
+                aggregations.push((agg_type.to_string(), column));
+            }
+        }
+        let options = timeline::TimelineOptions {
+            aggregations,
+            agg: get_string_vec_from_value(args, "agg")
+                .or_else(|| get_string_from_value(args, "agg").map(|a| vec![a]))
+                .unwrap_or_default(),
+            by: get_string_from_value(args, "by"),
+            fill: get_bool_from_value(args, "fill"),
+            start: get_string_from_value(args, "start"),
+            end: get_string_from_value(args, "end"),
+            week_start: get_string_from_value(args, "week_start")
+                .or_else(|| get_string_from_value(args, "week-start")),
+            tz: get_string_from_value(args, "tz"),
+        };
+        let parser = get_datetime_parser(args)?;
+        timeline::timeline(df, &time_column, &interval, &options, &parser)
     });
     table.insert("timeslice", |df, args| {
         let time_column = get_string_from_value(args, "time_column").unwrap_or_default();
         let start_time = get_string_from_value(args, "start");
         let end_time = get_string_from_value(args, "end");
-        timeslice::timeslice(df, &time_column, start_time.as_deref(), end_time.as_deref())
+        let parser = get_datetime_parser(args)?;
+        timeslice::timeslice(
+            df,
+            &time_column,
+            start_time.as_deref(),
+            end_time.as_deref(),
+            &parser,
+        )
     });
     table.insert("pivot", |df, args| {
         let rows_str = get_string_from_value(args, "rows").unwrap_or_default();
         let cols_str = get_string_from_value(args, "cols")
             .or_else(|| get_string_from_value(args, "columns"))
             .unwrap_or_default();
-        let values = get_string_from_value(args, "values")
-            .or_else(|| get_string_from_value(args, "value"))
+        let values = get_list_from_value(args, "values")
+            .or_else(|| get_list_from_value(args, "value"))
             .unwrap_or_default();
-        let agg_func = get_string_from_value(args, "agg")
-            .or_else(|| get_string_from_value(args, "aggregation"))
-            .unwrap_or_else(|| "sum".to_string());
+        let agg_funcs = get_list_from_value(args, "agg")
+            .or_else(|| get_list_from_value(args, "aggregation"))
+            .unwrap_or_else(|| vec!["sum".to_string()]);
+        let options = pivot::PivotOptions {
+            fill: get_string_from_value(args, "fill"),
+            row_totals: get_bool_from_value(args, "row_totals"),
+            col_totals: get_bool_from_value(args, "col_totals"),
+        };
         let rows: Vec<String> = if rows_str.is_empty() {
             Vec::new()
         } else {
@@ -158,7 +237,16 @@ fn create_chainable_dispatch_table() -> HashMap<&'static str, ChainableOperation
         } else {
             cols_str.split(',').map(|s| s.trim().to_string()).collect()
         };
-        pivot::pivot(df, &rows, &columns, &values, &agg_func)
+        pivot::pivot(df, &rows, &columns, &values, &agg_funcs, &options)
+    });
+    table.insert("melt", |df, args| {
+        let id_cols = get_list_from_value(args, "id").unwrap_or_default();
+        let value_cols = get_list_from_value(args, "value_cols").unwrap_or_default();
+        let variable_name = get_string_from_value(args, "variable_name")
+            .unwrap_or_else(|| melt::MELT_VARIABLE_NAME.to_string());
+        let value_name = get_string_from_value(args, "value_name")
+            .unwrap_or_else(|| melt::MELT_VALUE_NAME.to_string());
+        melt::melt(df, &id_cols, &value_cols, &variable_name, &value_name)
     });
     table
 }
@@ -167,67 +255,102 @@ fn create_finalizer_dispatch_table() -> HashMap<&'static str, FinalizerOperation
     let mut table: HashMap<&'static str, FinalizerOperation> = HashMap::new();
     table.insert("show", |df, args| {
         if let Some(batch_size_str) = get_string_from_value(args, "batch-size") {
-            match parse_batch_size(&batch_size_str) {
-                Ok(batch_size) => show_op::show_with_batch_size(df, batch_size),
-                Err(e) => eprintln!("Error parsing batch-size for show: {e}"),
-            }
+            let batch_size = parse_batch_size(&batch_size_str)
+                .map_err(|e| QsvError::Parse(format!("batch-size for show: {e}")))?;
+            show_op::show_with_batch_size(df, batch_size)
         } else {
-            show_op::show(df);
+            show_op::show(df)
         }
     });
-    table.insert("showtable", |df, _args| {
-        showtable_op::showtable(df);
+    table.insert("showtable", |df, _args| showtable_op::showtable(df));
+    table.insert("chart", |df, args| {
+        let column = get_string_from_value(args, "column").unwrap_or_default();
+        let defaults = chart_op::ChartOptions::default();
+        let options = chart_op::ChartOptions {
+            chart_type: get_string_from_value(args, "type")
+                .map(|name| chart_op::ChartType::from_name(&name))
+                .transpose()?
+                .unwrap_or_default(),
+            x: get_string_from_value(args, "x"),
+            by: get_string_from_value(args, "by"),
+            width: get_usize_from_value(args, "width").unwrap_or(defaults.width),
+            height: get_usize_from_value(args, "height"),
+            bins: get_usize_from_value(args, "bins").unwrap_or(defaults.bins),
+            log: get_bool_from_value(args, "log"),
+            ascii: get_bool_from_value(args, "ascii"),
+        };
+        chart_op::chart(df, &column, &options)
     });
     table.insert("headers", |df, args| {
         let plain = get_bool_from_value(args, "plain");
-        headers_op::headers(df, plain);
-    });
-    table.insert("stats", |df, _args| {
-        stats_op::stats(df);
-    });
-    table.insert("showquery", |df, _args| {
-        showquery_op::showquery(df);
+        headers_op::headers(df, plain)
     });
+    table.insert("stats", |df, _args| stats_op::stats(df));
+    table.insert("showquery", |df, _args| showquery_op::showquery(df));
     table.insert("dump", |df, args| {
         let path_from_yaml = get_string_from_value(args, "path")
             .or_else(|| get_string_from_value(args, "output"))
             .unwrap_or_else(|| "output.csv".to_string());
-        let separator = get_string_from_value(args, "separator")
-            .and_then(|s| s.chars().next())
-            .unwrap_or(',');
+        let separator = get_string_from_value(args, "separator").and_then(|s| s.chars().next());
+        let format = get_string_from_value(args, "format")
+            .map(|f| dump_op::parse_dump_format(&f))
+            .transpose()?;
 
         if let Some(batch_size_str) = get_string_from_value(args, "batch-size") {
-            match parse_batch_size(&batch_size_str) {
-                Ok(batch_size) => {
-                    dump_op::dump_with_batch_size(df, Some(&path_from_yaml), separator, batch_size)
-                }
-                Err(e) => eprintln!("Error parsing batch-size for dump: {e}"),
-            }
+            let batch_size = parse_batch_size(&batch_size_str)
+                .map_err(|e| QsvError::Parse(format!("batch-size for dump: {e}")))?;
+            dump_op::dump_with_batch_size(df, Some(&path_from_yaml), separator, format, batch_size)
         } else {
-            dump_op::dump(df, Some(&path_from_yaml), separator);
+            dump_op::dump(df, Some(&path_from_yaml), separator, format)
         }
     });
     table.insert("dumpcache", |df, args| {
         let output_path = get_string_from_value(args, "output");
-        dumpcache_op::dumpcache(df, output_path.as_deref());
+        dumpcache_op::dumpcache(df, output_path.as_deref())
     });
     table.insert("partition", |df, args| {
         let colname = get_string_from_value(args, "colname").unwrap_or_default();
         let output_dir = get_string_from_value(args, "output_dir")
             .or_else(|| get_string_from_value(args, "output_directory"))
             .unwrap_or_else(|| "./partitions".to_string());
-        partition_op::partition(df, &colname, &output_dir);
+        let compression = get_string_from_value(args, "compression")
+            .map(|name| Compression::from_name(&name))
+            .transpose()?;
+        partition_op::partition(df, &colname, &output_dir, compression)
     });
     table
 }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct QuiltConfig {
     pub title: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub author: Option<String>,
+    /// Default values for `${name}` variables, overridable from the command line
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub params: Option<serde_yml::Mapping>,
+    /// Maximum number of stages run at the same time (default: number of CPUs)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_parallel: Option<usize>,
     pub stages: serde_yml::Mapping,
 }
+/// Run-time settings for a quilt beyond the YAML file itself
+#[derive(Debug, Clone, Default)]
+pub struct QuiltOptions {
+    /// Variable values that override the quilt's `params:` defaults
+    pub params: HashMap<String, String>,
+    /// Overrides the quilt's `max_parallel` setting
+    pub max_parallel: Option<usize>,
+}
+// Result of one stage: its data plus console finalizers still to run
+#[derive(Default)]
+struct StageOutput {
+    df: Option<LazyFrame>,
+    deferred: Vec<(FinalizerOperation, LazyFrame, Value)>,
+}
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct StageConfig {
     #[serde(rename = "type")]
@@ -235,383 +358,436 @@ pub struct StageConfig {
     pub source: Option<String>,
     pub sources: Option<Vec<String>>,
     pub params: Option<Value>,
+    /// Extra stages that must run first, e.g. ones only referenced from a `sql` query
+    pub depends_on: Option<Vec<String>>,
     pub steps: Option<serde_yml::Mapping>,
 }
+// Numbers are read as text, so `colname: 2024` or `values: [1, 4]` work as expected
+fn scalar_string(v: &Value) -> Option<String> {
+    match v {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        _ => None,
+    }
+}
 fn get_string_from_value(val: &Value, key: &str) -> Option<String> {
-    val.get(key).and_then(|v| v.as_str().map(String::from))
+    val.get(key).and_then(scalar_string)
 }
 fn get_string_vec_from_value(val: &Value, key: &str) -> Option<Vec<String>> {
-    val.get(key).and_then(|v| v.as_sequence()).map(|seq| {
-        seq.iter()
-            .filter_map(|item| item.as_str().map(String::from))
-            .collect()
-    })
+    val.get(key)
+        .and_then(|v| v.as_sequence())
+        .map(|seq| seq.iter().filter_map(scalar_string).collect())
+}
+// Accept either a comma-separated string or a YAML sequence
+fn get_list_from_value(val: &Value, key: &str) -> Option<Vec<String>> {
+    get_string_from_value(val, key)
+        .map(|s| s.split(',').map(|item| item.trim().to_string()).collect())
+        .or_else(|| get_string_vec_from_value(val, key))
+}
+// A `path` given as one file or a list of files, relative to the quilt file's directory
+fn get_path_list(val: &Value, config_path: &Path) -> Option<Vec<PathBuf>> {
+    let files = get_string_from_value(val, "path")
+        .map(|p| vec![p])
+        .or_else(|| get_string_vec_from_value(val, "path"))?;
+    let base_dir = config_path.parent().unwrap_or_else(|| Path::new("."));
+    Some(files.iter().map(|f| base_dir.join(f)).collect())
 }
 fn get_bool_from_value(val: &Value, key: &str) -> bool {
     val.get(key).and_then(|v| v.as_bool()).unwrap_or(false)
 }
+// The input_format, epoch and strict arguments of a time-aware step
+fn get_datetime_parser(args: &Value) -> Result<DatetimeParser, QsvError> {
+    let input_format = get_string_from_value(args, "input_format")
+        .or_else(|| get_string_from_value(args, "input-format"))
+        .or_else(|| get_string_from_value(args, "format"));
+    DatetimeParser::new(
+        input_format.as_deref(),
+        get_string_from_value(args, "epoch").as_deref(),
+        get_bool_from_value(args, "strict"),
+    )
+}
 fn get_usize_from_value(val: &Value, key: &str) -> Option<usize> {
     val.get(key)
         .and_then(|v| v.as_u64().and_then(|u| usize::try_from(u).ok()))
 }
-pub fn quilt(
-    controller: &mut DataFrameController,
-    config_path_str: &str,
-    cli_input_files: Option<Vec<PathBuf>>,
-    output_path_str: Option<&str>,
-) {
-    let config_path = Path::new(config_path_str);
-    let config_content = match fs::read_to_string(config_path) {
-        Ok(content) => content,
-        Err(e) => {
-            eprintln!("Error reading config file {}: {}", config_path.display(), e);
-            std::process::exit(1);
-        }
+// Results of the source stages of a concat or join stage, in `sources` order
+fn source_frames(
+    stage_name: &str,
+    stage_type: &str,
+    sources: &[String],
+    stage_results: &HashMap<String, LazyFrame>,
+) -> Result<Vec<LazyFrame>, QsvError> {
+    sources
+        .iter()
+        .map(|name| {
+            stage_results.get(name).cloned().ok_or_else(|| {
+                QsvError::Config(format!(
+                    "Source stage '{name}' for {stage_type} stage '{stage_name}' has no result"
+                ))
+            })
+        })
+        .collect()
+}
+// Concatenate the source frames of a concat stage:
+// - vertical: append rows; every source must have the same columns
+// - horizontal: place columns side by side; shorter sources are padded with nulls
+// - diagonal: append rows, matching columns by name and filling missing ones with nulls
+fn concat_stage_frames(
+    stage_name: &str,
+    frames: Vec<LazyFrame>,
+    how: &str,
+) -> Result<LazyFrame, QsvError> {
+    let schemas = frames
+        .iter()
+        .map(|lf| lf.clone().collect_schema())
+        .collect::<Result<Vec<_>, _>>()?;
+    let column_names = |i: usize| -> Vec<String> {
+        schemas[i]
+            .iter_names()
+            .map(|name| name.to_string())
+            .collect()
     };
-    let quilt_config: QuiltConfig = match serde_yml::from_str(&config_content) {
-        Ok(config) => config,
-        Err(e) => {
-            eprintln!("Error parsing YAML config: {e}");
-            std::process::exit(1);
+    match how.to_lowercase().as_str() {
+        "vertical" | "v" => {
+            let first = column_names(0);
+            if let Some(i) = (1..frames.len()).find(|&i| column_names(i) != first) {
+                return Err(QsvError::Config(format!(
+                    "Concat stage '{stage_name}': source {} has columns {:?} but source 1 has {first:?}; use 'how: diagonal' to combine sources by column name",
+                    i + 1,
+                    column_names(i)
+                )));
+            }
+            Ok(polars::prelude::concat(
+                frames,
+                polars::prelude::UnionArgs {
+                    to_supertypes: true,
+                    ..Default::default()
+                },
+            )?)
         }
-    };
-    LogController::info(&format!(
-        "Executing quilt '{}' with {} stage entries in YAML",
-        quilt_config.title,
-        quilt_config.stages.len()
-    ));
-    let mut stage_results: HashMap<String, LazyFrame> = HashMap::new();
-    let mut last_processed_df: Option<LazyFrame> = None;
-    for (stage_name_val, stage_config_val) in &quilt_config.stages {
-        let stage_name = stage_name_val
-            .as_str()
-            .unwrap_or("unknown_stage")
-            .to_string();
-        let stage_config: StageConfig = match serde_yml::from_value(stage_config_val.clone()) {
-            Ok(sc) => sc,
-            Err(e) => {
-                LogController::error(&format!(
-                    "Error parsing config for stage '{stage_name}': {e}. Skipping."
-                ));
-                continue;
+        "horizontal" | "h" => {
+            let mut seen = BTreeSet::new();
+            let duplicates: BTreeSet<String> = (0..frames.len())
+                .flat_map(column_names)
+                .filter(|name| !seen.insert(name.clone()))
+                .collect();
+            if !duplicates.is_empty() {
+                return Err(QsvError::Config(format!(
+                    "Concat stage '{stage_name}': columns {duplicates:?} appear in more than one source; rename them with 'renamecol' before a horizontal concat"
+                )));
+            }
+            Ok(polars::prelude::concat_lf_horizontal(
+                frames,
+                polars::prelude::UnionArgs::default(),
+            )?)
+        }
+        "diagonal" | "d" => Ok(polars::prelude::concat_lf_diagonal(
+            frames,
+            polars::prelude::UnionArgs {
+                to_supertypes: true,
+                ..Default::default()
+            },
+        )?),
+        _ => Err(QsvError::Config(format!(
+            "Invalid concat method '{how}' for stage '{stage_name}'. Supported methods: {}",
+            CONCAT_STAGE_TYPES.join(", ")
+        ))),
+    }
+}
+// Join two frames as configured by a join stage's `params`
+fn join_stage_frames(
+    left: &LazyFrame,
+    right: &LazyFrame,
+    params: &Value,
+) -> Result<LazyFrame, QsvError> {
+    let how = get_string_from_value(params, "how").unwrap_or_else(|| "inner".to_string());
+    let on = get_list_from_value(params, "key").or_else(|| get_list_from_value(params, "on"));
+    let left_on = get_list_from_value(params, "left_on")
+        .or_else(|| on.clone())
+        .unwrap_or_default();
+    let right_on = get_list_from_value(params, "right_on")
+        .or(on)
+        .unwrap_or_default();
+    let suffix =
+        get_string_from_value(params, "suffix").unwrap_or_else(|| DEFAULT_JOIN_SUFFIX.to_string());
+
+    if how.eq_ignore_ascii_case("asof") {
+        let (left_key, right_key) = match (left_on.as_slice(), right_on.as_slice()) {
+            ([l], [r]) => (l, r),
+            _ => {
+                return Err(QsvError::Config(
+                    "As-of joins need exactly one key column on each side".to_string(),
+                ))
             }
         };
-        LogController::debug(&format!(
-            "Processing stage: {} (type: {})",
-            stage_name, stage_config.stage_type
-        ));
-        let mut current_stage_input_df: Option<LazyFrame> = None;
-        if let Some(source_name) = &stage_config.source {
-            if let Some(df) = stage_results.get(source_name) {
-                current_stage_input_df = Some(df.clone());
-                LogController::debug(&format!(
-                    "Stage '{stage_name}' is using data from source stage '{source_name}'"
-                ));
-            } else {
-                LogController::error(&format!(
-                    "Source stage '{source_name}' not found for stage '{stage_name}'. Skipping stage."
-                ));
-                continue;
+        let by = get_list_from_value(params, "by");
+        let options = AsofJoinOptions {
+            strategy: get_string_from_value(params, "strategy"),
+            tolerance: get_string_from_value(params, "tolerance"),
+            by_left: get_list_from_value(params, "by_left")
+                .or_else(|| by.clone())
+                .unwrap_or_default(),
+            by_right: get_list_from_value(params, "by_right")
+                .or(by)
+                .unwrap_or_default(),
+            parser: get_datetime_parser(params)?,
+        };
+        return join_op::asof_join_frames(left, right, left_key, right_key, &options, &suffix);
+    }
+
+    let join_type = join_op::parse_join_type(&how).ok_or_else(|| {
+        QsvError::Config(format!(
+            "Unsupported join type '{how}'. Supported types: {}",
+            JOIN_STAGE_TYPES.join(", ")
+        ))
+    })?;
+    let coalesce = get_bool_from_value(params, "coalesce");
+    join_op::join_frames(
+        left, right, &left_on, &right_on, join_type, &suffix, coalesce,
+    )
+}
+// Substitute `${name}` variables in every stage, also returning one message per
+// undefined variable so they can all be reported together
+pub(crate) fn interpolate_stages(
+    quilt_config: &QuiltConfig,
+    options: &QuiltOptions,
+) -> Result<(serde_yml::Mapping, Vec<String>), QsvError> {
+    let params = resolve_params(quilt_config.params.as_ref(), &options.params)?;
+    let mut stages = serde_yml::Mapping::new();
+    let mut undefined = Vec::new();
+    for (stage_name, stage_config) in &quilt_config.stages {
+        let mut missing = Vec::new();
+        stages.insert(
+            stage_name.clone(),
+            interpolate(stage_config, &params, &mut missing)?,
+        );
+        let stage_name = stage_name.as_str().unwrap_or("unknown_stage");
+        undefined.extend(missing.into_iter().map(|name| {
+            format!("stage '{stage_name}': undefined variable '${{{name}}}'; set it under 'params:' or with --param {name}=<value>")
+        }));
+    }
+    Ok((stages, undefined))
+}
+// Files and directories a stage's finalizers write to
+fn stage_outputs(stage: &Stage) -> Vec<String> {
+    let mut outputs = Vec::new();
+    for (name, args) in stage.config.steps.iter().flatten() {
+        let output = match name.as_str().unwrap_or("").trim_end_matches('_') {
+            "dump" => Some(
+                get_string_from_value(args, "path")
+                    .or_else(|| get_string_from_value(args, "output"))
+                    .unwrap_or_else(|| "output.csv".to_string()),
+            ),
+            "dumpcache" => get_string_from_value(args, "output"),
+            "partition" => Some(
+                get_string_from_value(args, "output_dir")
+                    .or_else(|| get_string_from_value(args, "output_directory"))
+                    .unwrap_or_else(|| "./partitions".to_string()),
+            ),
+            _ => None,
+        };
+        outputs.extend(output);
+    }
+    outputs
+}
+// Stages each stage must wait for when stages run concurrently. Besides the declared
+// dependencies, a `sql` step without `depends_on` may query any earlier stage, and stages
+// writing the same file keep their order, so results match a sequential run.
+fn schedule_dependencies(stages: &[Stage], order: &[usize]) -> Vec<Vec<usize>> {
+    let mut deps = dependency_indices(stages);
+    let outputs: Vec<Vec<String>> = stages.iter().map(stage_outputs).collect();
+    for (pos, &i) in order.iter().enumerate() {
+        let has_sql = stages[i]
+            .config
+            .steps
+            .iter()
+            .flatten()
+            .any(|(name, _)| name.as_str().unwrap_or("").trim_end_matches('_') == "sql");
+        for &earlier in &order[..pos] {
+            let shares_output = outputs[i].iter().any(|o| outputs[earlier].contains(o));
+            if ((has_sql && stages[i].config.depends_on.is_none()) || shares_output)
+                && !deps[i].contains(&earlier)
+            {
+                deps[i].push(earlier);
             }
         }
-        let mut stage_output_df: Option<LazyFrame> = current_stage_input_df.clone();
-        if stage_config.stage_type == "process" {
-            // Create dispatch tables
-            let chainable_ops = create_chainable_dispatch_table();
-            let finalizer_ops = create_finalizer_dispatch_table();
-            if let Some(steps) = &stage_config.steps {
-                for (command_name_val, command_args_val) in steps {
-                    // Handle command name with trailing underscores (for duplicates)
-                    let raw_command_name = command_name_val.as_str().unwrap_or("");
-                    let command_name = if raw_command_name.ends_with('_') {
-                        raw_command_name.trim_end_matches('_')
-                    } else {
-                        raw_command_name
-                    };
-                    LogController::debug(&format!(
-                        "Applying step: {command_name} to stage '{stage_name}'"
-                    ));
-                    if command_name != "load" && stage_output_df.is_none() {
-                        LogController::error(&format!("No DataFrame available for step '{command_name}' in stage '{stage_name}'. Load data first or specify a valid source. Skipping step."));
-                        continue;
-                    }
-                    match command_name {
-                        "load" => {
-                            let file_to_load_str = get_string_from_value(command_args_val, "path");
-                            let mut loaded_df: Option<LazyFrame> = None;
-                            if let Some(file_str) = file_to_load_str {
-                                let source_path = Path::new(&file_str);
-                                let path_to_load = if source_path.is_absolute() {
-                                    source_path.to_path_buf()
-                                } else {
-                                    config_path
-                                        .parent()
-                                        .unwrap_or_else(|| Path::new("."))
-                                        .join(source_path)
-                                };
-                                LogController::debug(&format!("Loading data from: {} (specified in quilt YAML for stage '{}')", path_to_load.display(), stage_name));
-                                let separator =
-                                    get_string_from_value(command_args_val, "separator")
-                                        .unwrap_or_else(|| ",".to_string());
-                                let low_memory =
-                                    get_bool_from_value(command_args_val, "low_memory");
-                                let no_headers =
-                                    get_bool_from_value(command_args_val, "no_headers");
-                                let chunk_size =
-                                    get_usize_from_value(command_args_val, "chunk_size");
-                                loaded_df = Some(load_op::load(
-                                    &[path_to_load],
-                                    &separator,
-                                    low_memory,
-                                    no_headers,
-                                    chunk_size,
-                                ));
-                            } else if let Some(ref cli_files) = cli_input_files {
-                                if stage_output_df.is_none() && !cli_files.is_empty() {
-                                    LogController::debug(&format!(
-                                        "Loading data from CLI for stage '{stage_name}': {cli_files:?}"
-                                    ));
-                                    loaded_df =
-                                        Some(load_op::load(cli_files, ",", false, false, None));
-                                } else if stage_output_df.is_some() {
-                                    LogController::debug(&format!("Stage '{stage_name}' already has data from source, 'load' step without path will not use CLI files."));
-                                } else {
-                                    LogController::warn(&format!("Load step in YAML for stage '{stage_name}' has no path, and no files provided via CLI for this quilt command, or stage already sourced."));
-                                }
-                            } else {
-                                LogController::warn(&format!("No data source specified for load in stage '{stage_name}'. Trying default test data."));
-                                let default_data_path = config_path
-                                    .parent()
-                                    .unwrap_or_else(|| Path::new("."))
-                                    .join("../sample/simple.csv");
-                                if default_data_path.exists() {
-                                    loaded_df = Some(load_op::load(
-                                        &[default_data_path],
-                                        ",",
-                                        false,
-                                        false,
-                                        None,
-                                    ));
-                                }
-                            }
-                            if let Some(ref new_lf) = loaded_df {
-                                stage_output_df = Some(new_lf.clone());
-                            } else if stage_output_df.is_none() {
-                                LogController::error(&format!("Failed to load any data for stage '{stage_name}' via 'load' step and no prior data for stage."));
-                                continue;
-                            }
-                        }
-                        _ => {
-                            // Try chainable operations first
-                            if let Some(operation) = chainable_ops.get(command_name) {
-                                if let Some(ref df) = stage_output_df {
-                                    stage_output_df = Some(operation(df, command_args_val));
-                                } else {
-                                    LogController::error(&format!("No DataFrame available for chainable operation '{command_name}' in stage '{stage_name}'"));
-                                }
-                            }
-                            // Try finalizer operations
-                            else if let Some(operation) = finalizer_ops.get(command_name) {
-                                if let Some(ref df) = stage_output_df {
-                                    operation(df, command_args_val);
-                                } else {
-                                    LogController::warn(&format!("No DataFrame available for finalizer operation '{command_name}' in stage '{stage_name}'"));
-                                }
-                            }
-                            // Unknown operation
-                            else {
-                                LogController::error(&format!("Error: Unknown or unsupported step '{command_name}' in 'process' stage '{stage_name}'. Halting quilt execution."));
-                                eprintln!("Error: Unknown or unsupported step '{command_name}' in 'process' stage '{stage_name}'. See qsv logs for more details.");
-                                std::process::exit(1);
-                            }
-                        }
-                    }
-                }
-            } else {
-                LogController::warn(&format!(
-                    "Stage '{stage_name}' is of type 'process' but has no steps defined."
-                ));
-            }
-        } else if stage_config.stage_type == "concat" {
-            if let Some(sources_vec) = &stage_config.sources {
-                if sources_vec.len() >= 2 {
-                    let mut dataframes_to_concat: Vec<LazyFrame> = Vec::new();
-                    let mut missing_sources = Vec::new();
+    }
+    deps
+}
+// Run stages on up to `max_parallel` threads, each as soon as its dependencies are done.
+// Console output is replayed on this thread in `order`, and the first failing stage in
+// `order` determines the error, so the outcome does not depend on thread timing.
+fn run_stages(
+    stages: &[Stage],
+    order: &[usize],
+    max_parallel: usize,
+    config_path: &Path,
+    cli_input_files: &Option<Vec<PathBuf>>,
+) -> Result<Vec<Option<LazyFrame>>, QsvError> {
+    let max_parallel = max_parallel.max(1);
+    LogController::debug(&format!(
+        "Running quilt stages with max_parallel = {max_parallel}"
+    ));
+    let deps = schedule_dependencies(stages, order);
+    let mut position = vec![0; stages.len()];
+    for (pos, &i) in order.iter().enumerate() {
+        position[i] = pos;
+    }
+    let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); stages.len()];
+    for (i, stage_deps) in deps.iter().enumerate() {
+        for &d in stage_deps {
+            dependents[d].push(i);
+        }
+    }
+    let mut waiting: Vec<usize> = deps.iter().map(|d| d.len()).collect();
+    // Positions in `order` of stages whose dependencies are done
+    let mut ready: BTreeSet<usize> = (0..stages.len())
+        .filter(|&i| waiting[i] == 0)
+        .map(|i| position[i])
+        .collect();
+    let mut results: Vec<Option<LazyFrame>> = vec![None; stages.len()];
+    let mut finished: Vec<Option<Result<StageOutput, QsvError>>> =
+        (0..stages.len()).map(|_| None).collect();
+    let mut flushed = 0;
+    let mut failed = false;
+    let mut error = None;
 
-                    for source_name in sources_vec {
-                        if let Some(source_df) = stage_results.get(source_name) {
-                            dataframes_to_concat.push(source_df.clone());
-                        } else {
-                            missing_sources.push(source_name.as_str());
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(max_parallel)
+        .build()
+        .map_err(|e| QsvError::Config(format!("creating quilt thread pool: {e}")))?;
+    let (tx, rx) = mpsc::channel();
+    pool.in_place_scope(|scope| {
+        let mut running = 0;
+        loop {
+            while !failed && running < max_parallel {
+                let Some(pos) = ready.pop_first() else { break };
+                let i = order[pos];
+                // Only stages earlier in `order` are visible, as in a sequential run
+                let inputs: HashMap<String, LazyFrame> = order[..pos]
+                    .iter()
+                    .filter_map(|&j| results[j].clone().map(|df| (stages[j].name.clone(), df)))
+                    .collect();
+                let stage = &stages[i];
+                let tx = tx.clone();
+                scope.spawn(move |_| {
+                    LogController::set_stage(Some(&stage.name));
+                    let output = run_stage(stage, &inputs, config_path, cli_input_files);
+                    LogController::set_stage(None);
+                    // The receiver outlives the scope, so this cannot fail
+                    let _ = tx.send((i, output));
+                });
+                running += 1;
+            }
+            if running == 0 {
+                break;
+            }
+            let Ok((i, output)) = rx.recv() else { break };
+            running -= 1;
+            match &output {
+                Ok(out) => {
+                    results[i] = out.df.clone();
+                    for &next in &dependents[i] {
+                        waiting[next] -= 1;
+                        if waiting[next] == 0 {
+                            ready.insert(position[next]);
                         }
                     }
-
-                    if !missing_sources.is_empty() {
-                        LogController::error(&format!(
-                            "Could not find source DataFrame(s): {missing_sources:?} for concat stage '{stage_name}'. Skipping."
-                        ));
-                        continue;
-                    }
-
-                    if dataframes_to_concat.len() >= 2 {
-                        // Get concatenation method from params.how (default: vertical)
-                        let concat_how = stage_config
-                            .params
-                            .as_ref()
-                            .and_then(|p| get_string_from_value(p, "how"))
-                            .unwrap_or_else(|| "vertical".to_string());
-
-                        let result_df = match concat_how.to_lowercase().as_str() {
-                            "vertical" | "v" => {
-                                // Vertical concatenation (row-wise) - default behavior
-                                let mut result = dataframes_to_concat[0].clone();
-                                for df in dataframes_to_concat.into_iter().skip(1) {
-                                    result = polars::prelude::concat(
-                                        [result, df],
-                                        polars::prelude::UnionArgs::default(),
-                                    )
-                                    .expect("Failed to concatenate DataFrames vertically");
-                                }
-                                result
-                            }
-                            "horizontal" | "h" => {
-                                // Horizontal concatenation (column-wise) - Not yet supported
-                                LogController::error(&format!(
-                                    "Horizontal concatenation is not yet implemented for stage '{stage_name}'. Use 'vertical' instead."
-                                ));
-                                continue;
-                            }
-                            _ => {
-                                LogController::error(&format!(
-                                    "Invalid concat method '{concat_how}' for stage '{stage_name}'. Use 'vertical' or 'horizontal'. Skipping."
-                                ));
-                                continue;
-                            }
-                        };
-
-                        stage_output_df = Some(result_df);
-                        LogController::debug(&format!(
-                            "Concat stage '{stage_name}' completed, concatenated {} sources: {sources_vec:?} using {concat_how} method",
-                            sources_vec.len()
-                        ));
-                    } else {
-                        LogController::warn(&format!(
-                            "Concat stage '{stage_name}' needs at least 2 valid DataFrames, found {}. Skipping.",
-                            dataframes_to_concat.len()
-                        ));
-                    }
-                } else {
-                    LogController::error(&format!(
-                        "Concat stage '{stage_name}' must have at least two sources. Found {}. Skipping.",
-                        sources_vec.len()
-                    ));
-                    continue;
                 }
-            } else {
-                LogController::error(&format!(
-                    "Concat stage '{stage_name}' missing 'sources' parameter. Skipping."
-                ));
-                continue;
+                Err(_) => failed = true,
             }
-        } else if stage_config.stage_type == "join" {
-            if let Some(sources_string_vec) = &stage_config.sources {
-                if sources_string_vec.len() == 2 {
-                    let left_name: &str = sources_string_vec[0].as_str();
-                    let right_name: &str = sources_string_vec[1].as_str();
-                    if left_name.is_empty() || right_name.is_empty() {
-                        LogController::error(&format!(
-                            "Join stage '{stage_name}' has empty source names. Skipping."
-                        ));
-                        continue;
-                    }
-                    if let (Some(left_df), Some(right_df)) =
-                        (stage_results.get(left_name), stage_results.get(right_name))
-                    {
-                        let join_params = stage_config.params.as_ref();
-                        let how_str = join_params
-                            .and_then(|p| get_string_from_value(p, "how"))
-                            .unwrap_or_else(|| "inner".to_string());
-                        let key_col_name = join_params
-                            .and_then(|p| get_string_from_value(p, "key"))
-                            .or_else(|| join_params.and_then(|p| get_string_from_value(p, "on")));
-                        if key_col_name.is_none() {
-                            LogController::error(&format!(
-                                "Join stage '{stage_name}' missing 'key' (or 'on') parameter. Skipping."
-                            ));
-                            continue;
-                        }
-                        let key = key_col_name.unwrap();
-                        let join_type = match how_str.to_lowercase().as_str() {
-                            "inner" => JoinType::Inner,
-                            "left" => JoinType::Left,
-                            "outer" | "full" => JoinType::Full,
-                            _ => {
-                                LogController::warn(&format!("Unsupported join type '{how_str}' for stage '{stage_name}'. Defaulting to inner join."));
-                                JoinType::Inner
-                            }
-                        };
-                        let coalesce = join_params
-                            .and_then(|p| p.get("coalesce"))
-                            .and_then(|v| v.as_bool())
-                            .unwrap_or(false);
-                        let mut join_args = polars::prelude::JoinArgs::new(join_type);
-                        if coalesce {
-                            join_args = join_args
-                                .with_coalesce(polars::prelude::JoinCoalesce::CoalesceColumns);
-                        }
-                        let joined_df_result = left_df.clone().join(
-                            right_df.clone(),
-                            &[col(&key)],
-                            &[col(&key)],
-                            join_args,
-                        );
-                        stage_output_df = Some(joined_df_result); // Result is a LazyFrame, not Result<LazyFrame, Error>
-                        LogController::debug(&format!(
-                            "Join stage '{stage_name}' completed using key '{key}', type '{how_str}', coalesce: {coalesce}"
-                        ));
-                    } else {
-                        let mut missing_sources = Vec::new();
-                        if !stage_results.contains_key(left_name) {
-                            missing_sources.push(left_name);
-                        }
-                        if !stage_results.contains_key(right_name) {
-                            missing_sources.push(right_name);
-                        }
-                        LogController::error(&format!("Could not find source DataFrame(s): {missing_sources:?} for join stage '{stage_name}'. Skipping."));
-                        continue;
+            finished[i] = Some(output);
+
+            while error.is_none() && flushed < order.len() {
+                let i = order[flushed];
+                let Some(output) = finished[i].take() else {
+                    break;
+                };
+                LogController::set_stage(Some(&stages[i].name));
+                let flushed_stage = output.and_then(|out| finish_stage(&stages[i], out));
+                LogController::set_stage(None);
+                match flushed_stage {
+                    Ok(()) => flushed += 1,
+                    Err(e) => {
+                        error = Some(e);
+                        failed = true;
                     }
-                } else {
-                    LogController::error(&format!(
-                        "Join stage '{}' must have exactly two sources. Found {}. Skipping.",
-                        stage_name,
-                        sources_string_vec.len()
-                    ));
-                    continue;
                 }
-            } else {
-                LogController::error(&format!(
-                    "Join stage '{stage_name}' is missing 'sources' attribute. Skipping."
-                ));
-                continue;
             }
-        } else {
-            LogController::warn(&format!(
-                "Unknown stage type: {} for stage '{}'",
-                stage_config.stage_type, stage_name
-            ));
-        }
-        if let Some(df_to_store) = &stage_output_df {
-            stage_results.insert(stage_name.clone(), df_to_store.clone());
-            last_processed_df = Some(df_to_store.clone());
-            LogController::debug(&format!(
-                "Finished processing stage '{stage_name}'. Result stored."
-            ));
-        } else {
-            LogController::warn(&format!(
-                "Stage '{stage_name}' did not produce a DataFrame."
-            ));
         }
+    });
+
+    // A failure may have stopped stages before it in `order` from ever starting
+    if let Some(e) = error.or_else(|| {
+        order[flushed..]
+            .iter()
+            .find_map(|&i| match finished[i].take() {
+                Some(Err(e)) => Some(e),
+                _ => None,
+            })
+    }) {
+        return Err(e);
+    }
+    Ok(results)
+}
+// Run a finished stage's console finalizers
+fn finish_stage(stage: &Stage, output: StageOutput) -> Result<(), QsvError> {
+    for (operation, df, args) in &output.deferred {
+        operation(df, args)?;
+    }
+    if output.df.is_some() {
+        LogController::debug(&format!(
+            "Finished processing stage '{}'. Result stored.",
+            stage.name
+        ));
+    } else {
+        LogController::warn(&format!(
+            "Stage '{}' did not produce a DataFrame.",
+            stage.name
+        ));
+    }
+    Ok(())
+}
+pub fn quilt(
+    controller: &mut DataFrameController,
+    config_path_str: &str,
+    cli_input_files: Option<Vec<PathBuf>>,
+    output_path_str: Option<&str>,
+    options: &QuiltOptions,
+) -> Result<(), QsvError> {
+    let config_path = Path::new(config_path_str);
+    let (quilt_config, mut problems) = read_quilt(config_path)?;
+    LogController::info(&format!(
+        "Executing quilt '{}' with {} stage entries in YAML",
+        quilt_config.title,
+        quilt_config.stages.len()
+    ));
+    // Undefined variables, malformed stages and a broken stage graph stop the run
+    let (stages_map, undefined) = interpolate_stages(&quilt_config, options)?;
+    if !undefined.is_empty() {
+        return Err(invalid_quilt(&undefined));
     }
+    let stages = parse_stages(&stages_map)?;
+    let order = execution_order(&stages)?;
+    // Unknown keys and step arguments were always ignored by a run, so they only warn;
+    // --validate reports them as errors
+    problems.extend(check_quilt(&quilt_config, options));
+    for problem in &problems {
+        LogController::warn(&format!("Quilt problem: {problem}"));
+    }
+    LogController::debug(&format!(
+        "Stage execution order: {:?}",
+        order
+            .iter()
+            .map(|&i| stages[i].name.as_str())
+            .collect::<Vec<_>>()
+    ));
+    let max_parallel = options
+        .max_parallel
+        .or(quilt_config.max_parallel)
+        .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+    let results = run_stages(&stages, &order, max_parallel, config_path, &cli_input_files)?;
+    let last_processed_df = order.iter().rev().find_map(|&i| results[i].clone());
     LogController::info(&format!(
         "Quilt '{}' execution processing finished.",
         quilt_config.title
@@ -629,16 +805,17 @@ pub fn quilt(
             };
             if let Some(parent) = absolute_path.parent() {
                 if !parent.exists() {
-                    if let Err(e) = std::fs::create_dir_all(parent) {
-                        eprintln!("Error creating directory {}: {}", parent.display(), e);
-                    }
+                    std::fs::create_dir_all(parent).map_err(|e| {
+                        QsvError::Io(format!("creating directory {}: {e}", parent.display()))
+                    })?;
                 }
             }
             dump_op::dump(
                 &final_df_to_dump,
                 Some(absolute_path.to_str().unwrap_or(path_str)),
-                ',',
-            );
+                None,
+                None,
+            )?;
         } else {
             LogController::warn(
                 "No final DataFrame from quilt execution to save for --output CLI option.",
@@ -656,4 +833,241 @@ pub fn quilt(
         }
         LogController::debug("Quilt finished. Output handled by steps in YAML or by main CLI flow if no explicit output/show in YAML.");
     }
+    Ok(())
+}
+// Run one stage against the results of the stages before it. File finalizers run
+// here; console finalizers are returned so their output can be replayed in stage order.
+fn run_stage(
+    stage: &Stage,
+    stage_results: &HashMap<String, LazyFrame>,
+    config_path: &Path,
+    cli_input_files: &Option<Vec<PathBuf>>,
+) -> Result<StageOutput, QsvError> {
+    let stage_name = stage.name.clone();
+    let stage_config = &stage.config;
+    let mut deferred = Vec::new();
+    LogController::debug(&format!(
+        "Processing stage: {} (type: {})",
+        stage_name, stage_config.stage_type
+    ));
+    let mut current_stage_input_df: Option<LazyFrame> = None;
+    if let Some(source_name) = &stage_config.source {
+        let df = stage_results.get(source_name).ok_or_else(|| {
+            QsvError::Config(format!(
+                "Source stage '{source_name}' produced no data for stage '{stage_name}'"
+            ))
+        })?;
+        current_stage_input_df = Some(df.clone());
+        LogController::debug(&format!(
+            "Stage '{stage_name}' is using data from source stage '{source_name}'"
+        ));
+    }
+    let mut stage_output_df: Option<LazyFrame> = current_stage_input_df.clone();
+    if stage_config.stage_type == "process" {
+        // Create dispatch tables
+        let chainable_ops = create_chainable_dispatch_table();
+        let finalizer_ops = create_finalizer_dispatch_table();
+        if let Some(steps) = &stage_config.steps {
+            for (command_name_val, command_args_val) in steps {
+                // Handle command name with trailing underscores (for duplicates)
+                let raw_command_name = command_name_val.as_str().unwrap_or("");
+                let command_name = if raw_command_name.ends_with('_') {
+                    raw_command_name.trim_end_matches('_')
+                } else {
+                    raw_command_name
+                };
+                LogController::debug(&format!(
+                    "Applying step: {command_name} to stage '{stage_name}'"
+                ));
+                // A sql step may build its data purely from earlier stages
+                if command_name != "load" && command_name != "sql" && stage_output_df.is_none() {
+                    LogController::error(&format!("No DataFrame available for step '{command_name}' in stage '{stage_name}'. Load data first or specify a valid source. Skipping step."));
+                    continue;
+                }
+                match command_name {
+                    "load" => {
+                        let files_to_load = get_path_list(command_args_val, config_path);
+                        let mut loaded_df: Option<LazyFrame> = None;
+                        if let Some(paths_to_load) = files_to_load {
+                            LogController::debug(&format!(
+                                "Loading data from: {paths_to_load:?} (specified in quilt YAML for stage '{stage_name}')"
+                            ));
+                            let separator = get_string_from_value(command_args_val, "separator")
+                                .unwrap_or_else(|| ",".to_string());
+                            let low_memory = get_bool_from_value(command_args_val, "low_memory");
+                            let no_headers = get_bool_from_value(command_args_val, "no_headers");
+                            let chunk_size = get_usize_from_value(command_args_val, "chunk_size");
+                            loaded_df = Some(load_op::load(
+                                &paths_to_load,
+                                &separator,
+                                low_memory,
+                                no_headers,
+                                chunk_size,
+                            )?);
+                        } else if let Some(cli_files) = cli_input_files {
+                            if stage_output_df.is_none() && !cli_files.is_empty() {
+                                LogController::debug(&format!(
+                                    "Loading data from CLI for stage '{stage_name}': {cli_files:?}"
+                                ));
+                                loaded_df =
+                                    Some(load_op::load(cli_files, ",", false, false, None)?);
+                            } else if stage_output_df.is_some() {
+                                LogController::debug(&format!("Stage '{stage_name}' already has data from source, 'load' step without path will not use CLI files."));
+                            } else {
+                                LogController::warn(&format!("Load step in YAML for stage '{stage_name}' has no path, and no files provided via CLI for this quilt command, or stage already sourced."));
+                            }
+                        } else {
+                            LogController::warn(&format!("No data source specified for load in stage '{stage_name}'. Trying default test data."));
+                            let default_data_path = config_path
+                                .parent()
+                                .unwrap_or_else(|| Path::new("."))
+                                .join("../sample/simple.csv");
+                            if default_data_path.exists() {
+                                loaded_df = Some(load_op::load(
+                                    &[default_data_path],
+                                    ",",
+                                    false,
+                                    false,
+                                    None,
+                                )?);
+                            }
+                        }
+                        if let Some(ref new_lf) = loaded_df {
+                            stage_output_df = Some(new_lf.clone());
+                        } else if stage_output_df.is_none() {
+                            LogController::error(&format!("Failed to load any data for stage '{stage_name}' via 'load' step and no prior data for stage."));
+                            continue;
+                        }
+                    }
+                    "join" => {
+                        // Like `load`, relative paths are read from the quilt file's directory
+                        let Some(right_paths) = get_path_list(command_args_val, config_path) else {
+                            return Err(QsvError::Config(format!(
+                                "Join step in stage '{stage_name}' needs a 'path' to join with"
+                            )));
+                        };
+                        let on = get_list_from_value(command_args_val, "on");
+                        let left_on = get_list_from_value(command_args_val, "left_on")
+                            .or_else(|| on.clone())
+                            .unwrap_or_default();
+                        let right_on = get_list_from_value(command_args_val, "right_on")
+                            .or(on)
+                            .unwrap_or_default();
+                        let how = get_string_from_value(command_args_val, "how")
+                            .unwrap_or_else(|| "inner".to_string());
+                        let suffix = get_string_from_value(command_args_val, "suffix")
+                            .unwrap_or_else(|| DEFAULT_JOIN_SUFFIX.to_string());
+                        let separator = get_string_from_value(command_args_val, "separator")
+                            .unwrap_or_else(|| ",".to_string());
+                        if let Some(df) = &stage_output_df {
+                            stage_output_df = Some(join_op::join(
+                                df,
+                                &right_paths,
+                                &left_on,
+                                &right_on,
+                                &how,
+                                &suffix,
+                                &separator,
+                            )?);
+                        }
+                    }
+                    "sql" => {
+                        let query = get_string_from_value(command_args_val, "query")
+                            .or_else(|| command_args_val.as_str().map(|q| q.to_string()))
+                            .unwrap_or_default();
+                        // Earlier stages are queryable by their stage name
+                        let current = stage_output_df
+                            .clone()
+                            .unwrap_or_else(|| DataFrame::empty().lazy());
+                        stage_output_df = Some(sql::sql(&current, &query, stage_results)?);
+                    }
+                    _ => {
+                        // Try chainable operations first
+                        if let Some(operation) = chainable_ops.get(command_name) {
+                            if let Some(ref df) = stage_output_df {
+                                stage_output_df = Some(operation(df, command_args_val)?);
+                            } else {
+                                LogController::error(&format!("No DataFrame available for chainable operation '{command_name}' in stage '{stage_name}'"));
+                            }
+                        }
+                        // Try finalizer operations
+                        else if let Some(operation) = finalizer_ops.get(command_name) {
+                            if let Some(ref df) = stage_output_df {
+                                if CONSOLE_FINALIZERS.contains(&command_name) {
+                                    deferred.push((
+                                        *operation,
+                                        df.clone(),
+                                        command_args_val.clone(),
+                                    ));
+                                } else {
+                                    operation(df, command_args_val)?;
+                                }
+                            } else {
+                                LogController::warn(&format!("No DataFrame available for finalizer operation '{command_name}' in stage '{stage_name}'"));
+                            }
+                        }
+                        // Unknown operation
+                        else {
+                            return Err(QsvError::Config(format!(
+                                "Unknown or unsupported step '{command_name}' in 'process' stage '{stage_name}'"
+                            )));
+                        }
+                    }
+                }
+            }
+        } else {
+            LogController::warn(&format!(
+                "Stage '{stage_name}' is of type 'process' but has no steps defined."
+            ));
+        }
+    } else if stage_config.stage_type == "concat" {
+        let sources = stage_config.sources.as_deref().unwrap_or_default();
+        if sources.len() < 2 {
+            return Err(QsvError::Config(format!(
+                "Concat stage '{stage_name}' needs at least two sources, found {}",
+                sources.len()
+            )));
+        }
+        let frames = source_frames(&stage_name, "concat", sources, stage_results)?;
+        // Get concatenation method from params.how (default: vertical)
+        let how = stage_config
+            .params
+            .as_ref()
+            .and_then(|p| get_string_from_value(p, "how"))
+            .unwrap_or_else(|| "vertical".to_string());
+        stage_output_df = Some(concat_stage_frames(&stage_name, frames, &how)?);
+        LogController::debug(&format!(
+            "Concat stage '{stage_name}' completed, concatenated {} sources: {sources:?} using {how} method",
+            sources.len()
+        ));
+    } else if stage_config.stage_type == "join" {
+        let sources = stage_config.sources.as_deref().unwrap_or_default();
+        if sources.len() < 2 {
+            return Err(QsvError::Config(format!(
+                "Join stage '{stage_name}' needs at least two sources, found {}",
+                sources.len()
+            )));
+        }
+        let frames = source_frames(&stage_name, "join", sources, stage_results)?;
+        let params = stage_config.params.clone().unwrap_or(Value::Null);
+        // Each further source is joined onto the result so far
+        let mut joined = frames[0].clone();
+        for right in &frames[1..] {
+            joined = join_stage_frames(&joined, right, &params)?;
+        }
+        LogController::debug(&format!(
+            "Join stage '{stage_name}' joined {} sources with params {params:?}",
+            sources.len()
+        ));
+        stage_output_df = Some(joined);
+    } else {
+        LogController::warn(&format!(
+            "Unknown stage type: {} for stage '{}'",
+            stage_config.stage_type, stage_name
+        ));
+    }
+    Ok(StageOutput {
+        df: stage_output_df,
+        deferred,
+    })
 }
@@ -0,0 +1,618 @@
+use serde_json::{json, Map, Value as JsonValue};
+use serde_yml::Value;
+use ArgKind::{Bool, Int, List, Seq, Str};
+
+/// Value type expected for a quilt argument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgKind {
+    /// A string; numbers are accepted and read as text
+    Str,
+    /// A string (comma-separated where the step takes several names) or a list of strings
+    List,
+    /// A list of strings
+    Seq,
+    Bool,
+    /// A non-negative integer
+    Int,
+}
+
+impl ArgKind {
+    pub fn matches(self, value: &Value) -> bool {
+        match self {
+            ArgKind::Str => value.is_string() || value.is_number(),
+            ArgKind::List => value.is_string() || is_string_seq(value),
+            ArgKind::Seq => is_string_seq(value),
+            ArgKind::Bool => value.is_bool(),
+            ArgKind::Int => value.as_u64().is_some(),
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            ArgKind::Str => "a string",
+            ArgKind::List => "a string or a list of strings",
+            ArgKind::Seq => "a list of strings",
+            ArgKind::Bool => "true or false",
+            ArgKind::Int => "a non-negative integer",
+        }
+    }
+
+    fn json_schema(self) -> JsonValue {
+        let seq = json!({ "type": "array", "items": { "type": ["string", "number"] } });
+        match self {
+            ArgKind::Str => json!({ "type": ["string", "number"] }),
+            ArgKind::List => json!({ "anyOf": [{ "type": "string" }, seq] }),
+            ArgKind::Seq => seq,
+            ArgKind::Bool => json!({ "type": "boolean" }),
+            ArgKind::Int => json!({ "type": "integer", "minimum": 0 }),
+        }
+    }
+}
+
+fn is_string_seq(value: &Value) -> bool {
+    value
+        .as_sequence()
+        .is_some_and(|seq| seq.iter().all(|v| v.is_string() || v.is_number()))
+}
+
+/// One argument key of a quilt step or stage `params`
+#[derive(Debug)]
+pub struct ArgSpec {
+    pub name: &'static str,
+    /// Alternative keys read when `name` is absent
+    pub aliases: &'static [&'static str],
+    pub kind: ArgKind,
+    pub required: bool,
+}
+
+const fn arg(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec {
+        name,
+        aliases: &[],
+        kind,
+        required: false,
+    }
+}
+
+const fn required(name: &'static str, kind: ArgKind) -> ArgSpec {
+    ArgSpec {
+        name,
+        aliases: &[],
+        kind,
+        required: true,
+    }
+}
+
+const fn alias(spec: ArgSpec, aliases: &'static [&'static str]) -> ArgSpec {
+    ArgSpec { aliases, ..spec }
+}
+
+/// Arguments accepted by a quilt step (or stage type)
+#[derive(Debug)]
+pub struct StepSchema {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub args: &'static [ArgSpec],
+    /// Type of a bare value given instead of a mapping, e.g. `head: 5`
+    pub shorthand: Option<ArgKind>,
+}
+
+impl StepSchema {
+    pub fn arg(&self, key: &str) -> Option<&ArgSpec> {
+        self.args
+            .iter()
+            .find(|a| a.name == key || a.aliases.contains(&key))
+    }
+
+    /// Problems with the arguments given to this step, one message per problem
+    pub fn check(&self, args: &Value) -> Vec<String> {
+        let mut problems = Vec::new();
+        let map = match args {
+            Value::Null => None,
+            Value::Mapping(map) => Some(map),
+            other => {
+                if !self.shorthand.is_some_and(|kind| kind.matches(other)) {
+                    problems.push(format!(
+                        "arguments must be a mapping of {}",
+                        self.expected_keys()
+                    ));
+                }
+                return problems;
+            }
+        };
+        for (key, value) in map.into_iter().flatten() {
+            let Some(key) = key.as_str() else {
+                problems.push(format!("argument name {key:?} must be a string"));
+                continue;
+            };
+            match self.arg(key) {
+                None => problems.push(format!(
+                    "unknown argument '{key}' (expected {})",
+                    self.expected_keys()
+                )),
+                // An empty value means "not given", as in `colname:`
+                Some(_) if value.is_null() => {}
+                Some(spec) if !spec.kind.matches(value) => {
+                    problems.push(format!("argument '{key}' must be {}", spec.kind.describe()))
+                }
+                Some(_) => {}
+            }
+        }
+        for spec in self.args.iter().filter(|a| a.required) {
+            let given = map.is_some_and(|m| {
+                std::iter::once(spec.name)
+                    .chain(spec.aliases.iter().copied())
+                    .any(|k| m.get(k).is_some_and(|v| !v.is_null()))
+            });
+            if !given {
+                problems.push(format!("missing required argument '{}'", spec.name));
+            }
+        }
+        problems
+    }
+
+    fn expected_keys(&self) -> String {
+        if self.args.is_empty() {
+            return "no arguments".to_string();
+        }
+        self.args
+            .iter()
+            .map(|a| a.name)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn json_schema(&self) -> JsonValue {
+        let mut properties = Map::new();
+        for spec in self.args {
+            for key in std::iter::once(spec.name).chain(spec.aliases.iter().copied()) {
+                properties.insert(key.to_string(), spec.kind.json_schema());
+            }
+        }
+        let object = json!({
+            "type": "object",
+            "description": self.description,
+            "properties": properties,
+            "additionalProperties": false,
+        });
+        let mut variants = vec![object, json!({ "type": "null" })];
+        if let Some(kind) = self.shorthand {
+            variants.push(kind.json_schema());
+        }
+        json!({ "anyOf": variants })
+    }
+}
+
+/// Every step usable in a `process` stage, mirroring the keys the quilt dispatch tables read
+pub const STEP_SCHEMAS: &[StepSchema] = &[
+    StepSchema {
+        name: "load",
+        description: "Load CSV, TSV, JSON Lines or Parquet file(s)",
+        args: &[
+            arg("path", List),
+            arg("separator", Str),
+            arg("low_memory", Bool),
+            arg("no_headers", Bool),
+            arg("chunk_size", Int),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "select",
+        description: "Select columns",
+        args: &[arg("colnames", List)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "isin",
+        description: "Filter rows by values",
+        args: &[required("colname", Str), required("values", Seq)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "contains",
+        description: "Filter rows by pattern",
+        args: &[
+            required("colname", Str),
+            required("pattern", Str),
+            arg("ignorecase", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "filter",
+        description: "Filter rows by expression",
+        args: &[required("expr", Str)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "sed",
+        description: "Replace values by pattern",
+        args: &[
+            arg("colname", Str),
+            required("pattern", Str),
+            required("replacement", Str),
+            arg("ignorecase", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "grep",
+        description: "Filter rows by regex (any column)",
+        args: &[
+            required("pattern", Str),
+            arg("ignorecase", Bool),
+            arg("invert_match", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "head",
+        description: "Keep the first N rows",
+        args: &[arg("number", Int)],
+        shorthand: Some(Int),
+    },
+    StepSchema {
+        name: "tail",
+        description: "Keep the last N rows",
+        args: &[arg("number", Int)],
+        shorthand: Some(Int),
+    },
+    StepSchema {
+        name: "sort",
+        description: "Sort rows",
+        args: &[arg("colnames", List), arg("desc", Bool)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "count",
+        description: "Count duplicate rows",
+        args: &[],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "groupby",
+        description: "Group by key columns with named aggregations",
+        args: &[alias(required("by", List), &["keys"]), arg("agg", List)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "sql",
+        description: "Run a SQL query; earlier stages are tables",
+        args: &[required("query", Str)],
+        shorthand: Some(Str),
+    },
+    StepSchema {
+        name: "uniq",
+        description: "Remove duplicate rows",
+        args: &[],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "changetz",
+        description: "Change timezone",
+        args: &[
+            required("colname", Str),
+            required("from-tz", Str),
+            required("to-tz", Str),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            alias(arg("output_format", Str), &["output-format"]),
+            arg("ambiguous", Str),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "join",
+        description: "Join with another file",
+        args: &[
+            required("path", List),
+            arg("on", List),
+            arg("left_on", List),
+            arg("right_on", List),
+            arg("how", Str),
+            arg("suffix", Str),
+            arg("separator", Str),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "convert",
+        description: "Convert a column between formats",
+        args: &[
+            required("colname", Str),
+            required("from", Str),
+            required("to", Str),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "timeround",
+        description: "Round timestamps to a unit",
+        args: &[
+            required("colname", Str),
+            required("unit", Str),
+            arg("output", Str),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "todatetime",
+        description: "Turn a column into datetimes with a time zone",
+        args: &[
+            required("colname", Str),
+            arg("tz", Str),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "renamecol",
+        description: "Rename a column",
+        args: &[
+            alias(required("old_name", Str), &["from"]),
+            alias(required("new_name", Str), &["to"]),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "timeline",
+        description: "Aggregate rows into time buckets",
+        args: &[
+            required("time_column", Str),
+            required("interval", Str),
+            arg("agg_type", Str),
+            arg("agg_column", Str),
+            arg("sum", List),
+            arg("avg", List),
+            arg("min", List),
+            arg("max", List),
+            arg("std", List),
+            arg("agg", List),
+            arg("by", Str),
+            arg("fill", Bool),
+            arg("start", Str),
+            arg("end", Str),
+            alias(arg("week_start", Str), &["week-start"]),
+            arg("tz", Str),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "timeslice",
+        description: "Filter rows by time range",
+        args: &[
+            required("time_column", Str),
+            arg("start", Str),
+            arg("end", Str),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "pivot",
+        description: "Reshape into a wide table",
+        args: &[
+            arg("rows", Str),
+            alias(arg("cols", Str), &["columns"]),
+            alias(required("values", List), &["value"]),
+            alias(arg("agg", List), &["aggregation"]),
+            arg("fill", Str),
+            arg("row_totals", Bool),
+            arg("col_totals", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "melt",
+        description: "Unpivot wide columns into rows",
+        args: &[
+            arg("id", List),
+            arg("value_cols", List),
+            arg("variable_name", Str),
+            arg("value_name", Str),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "show",
+        description: "Print as CSV",
+        args: &[arg("batch-size", Str)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "showtable",
+        description: "Print as a table",
+        args: &[],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "chart",
+        description: "Draw a bar chart, histogram or sparkline",
+        args: &[
+            required("column", Str),
+            arg("type", Str),
+            arg("x", Str),
+            arg("by", Str),
+            arg("width", Int),
+            arg("height", Int),
+            arg("bins", Int),
+            arg("log", Bool),
+            arg("ascii", Bool),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "headers",
+        description: "Print column names",
+        args: &[arg("plain", Bool)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "stats",
+        description: "Print summary statistics",
+        args: &[],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "showquery",
+        description: "Print the query plan",
+        args: &[],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "dump",
+        description: "Write to a file",
+        args: &[
+            alias(arg("path", Str), &["output"]),
+            arg("separator", Str),
+            arg("format", Str),
+            arg("batch-size", Str),
+        ],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "dumpcache",
+        description: "Write a Parquet cache file",
+        args: &[arg("output", Str)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "partition",
+        description: "Write one file per column value",
+        args: &[
+            required("colname", Str),
+            alias(arg("output_dir", Str), &["output_directory"]),
+            arg("compression", Str),
+        ],
+        shorthand: None,
+    },
+];
+
+/// `params` accepted by the `concat` and `join` stage types
+pub const STAGE_PARAM_SCHEMAS: &[StepSchema] = &[
+    StepSchema {
+        name: "concat",
+        description: "Concatenate the results of the source stages",
+        args: &[arg("how", Str)],
+        shorthand: None,
+    },
+    StepSchema {
+        name: "join",
+        description: "Join the results of the source stages, left to right",
+        args: &[
+            arg("how", Str),
+            alias(arg("key", List), &["on"]),
+            arg("left_on", List),
+            arg("right_on", List),
+            arg("suffix", Str),
+            arg("coalesce", Bool),
+            arg("strategy", Str),
+            arg("tolerance", Str),
+            arg("by", List),
+            arg("by_left", List),
+            arg("by_right", List),
+            alias(arg("input_format", Str), &["input-format", "format"]),
+            arg("epoch", Str),
+            arg("strict", Bool),
+        ],
+        shorthand: None,
+    },
+];
+
+/// Keys allowed in a stage definition
+pub const STAGE_KEYS: &[&str] = &["type", "source", "sources", "depends_on", "params", "steps"];
+
+/// Keys allowed at the top level of a quilt file
+pub const QUILT_KEYS: &[&str] = &[
+    "title",
+    "description",
+    "version",
+    "author",
+    "params",
+    "max_parallel",
+    "stages",
+];
+
+pub fn step_schema(name: &str) -> Option<&'static StepSchema> {
+    STEP_SCHEMAS.iter().find(|s| s.name == name)
+}
+
+pub fn stage_param_schema(stage_type: &str) -> Option<&'static StepSchema> {
+    STAGE_PARAM_SCHEMAS.iter().find(|s| s.name == stage_type)
+}
+
+/// JSON Schema (draft 2020-12) describing quilt YAML files, for editor completion
+pub fn quilt_json_schema() -> JsonValue {
+    let steps: Map<String, JsonValue> = STEP_SCHEMAS
+        .iter()
+        .map(|s| (s.name.to_string(), s.json_schema()))
+        .collect();
+    let names = json!({ "type": "array", "items": { "type": "string" } });
+    let stage_variant = |stage_type: &str, params: Option<JsonValue>| {
+        let mut properties = json!({
+            "type": { "const": stage_type },
+            "source": { "type": "string" },
+            "sources": names,
+            "depends_on": names,
+        });
+        if let Some(params) = params {
+            properties["params"] = params;
+        }
+        if stage_type == "process" {
+            properties["steps"] = json!({
+                "type": "object",
+                "description": "Steps run in order; append '_' to a step name to repeat it",
+                "patternProperties": steps
+                    .iter()
+                    .map(|(name, schema)| (format!("^{name}_*$"), schema.clone()))
+                    .collect::<Map<_, _>>(),
+                "additionalProperties": false,
+            });
+        }
+        json!({
+            "type": "object",
+            "properties": properties,
+            "required": ["type"],
+            "additionalProperties": false,
+        })
+    };
+    let mut stage_variants = vec![stage_variant("process", None)];
+    for schema in STAGE_PARAM_SCHEMAS {
+        stage_variants.push(stage_variant(schema.name, Some(schema.json_schema())));
+    }
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "qsv quilt",
+        "type": "object",
+        "properties": {
+            "title": { "type": "string" },
+            "description": { "type": "string" },
+            "version": { "type": "string" },
+            "author": { "type": "string" },
+            "params": {
+                "type": "object",
+                "description": "Defaults for ${name} variables",
+                "additionalProperties": { "type": ["string", "number", "boolean", "null"] },
+            },
+            "max_parallel": { "type": "integer", "minimum": 1 },
+            "stages": {
+                "type": "object",
+                "additionalProperties": { "oneOf": stage_variants },
+            },
+        },
+        "required": ["title", "stages"],
+        "additionalProperties": false,
+    })
+}
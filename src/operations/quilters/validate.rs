@@ -0,0 +1,301 @@
+use crate::error::QsvError;
+use crate::operations::chainables::join::parse_asof_strategy;
+use crate::operations::quilters::graph::{execution_order, parse_stage, stage_problems};
+use crate::operations::quilters::quilt::{
+    interpolate_stages, QuiltConfig, QuiltOptions, CONCAT_STAGE_TYPES, JOIN_STAGE_TYPES,
+};
+use crate::operations::quilters::schema::{
+    stage_param_schema, step_schema, QUILT_KEYS, STAGE_KEYS, STEP_SCHEMAS,
+};
+use serde_yml::Value;
+use std::fs;
+use std::path::Path;
+
+/// Check a quilt file without reading any data.
+///
+/// Returns every problem found, each prefixed with its stage and step; an empty list
+/// means the quilt is valid. Only an unreadable or unparsable file is an error.
+pub fn validate_quilt(
+    config_path_str: &str,
+    options: &QuiltOptions,
+) -> Result<Vec<String>, QsvError> {
+    let (quilt_config, mut problems) = read_quilt(Path::new(config_path_str))?;
+    problems.extend(check_quilt(&quilt_config, options));
+    Ok(problems)
+}
+
+/// Read a quilt file, also returning problems with its top-level keys
+pub(crate) fn read_quilt(config_path: &Path) -> Result<(QuiltConfig, Vec<String>), QsvError> {
+    let config_content = fs::read_to_string(config_path).map_err(|e| {
+        QsvError::Io(format!(
+            "reading config file {}: {e}",
+            config_path.display()
+        ))
+    })?;
+    let raw: Value = serde_yml::from_str(&config_content)
+        .map_err(|e| QsvError::Parse(format!("YAML config: {e}")))?;
+    let problems = raw
+        .as_mapping()
+        .into_iter()
+        .flatten()
+        .filter(|(key, _)| !key.as_str().is_some_and(|k| QUILT_KEYS.contains(&k)))
+        .map(|(key, _)| {
+            format!(
+                "unknown top-level key {} (expected one of: {})",
+                describe_key(key),
+                QUILT_KEYS.join(", ")
+            )
+        })
+        .collect();
+    let quilt_config: QuiltConfig =
+        serde_yml::from_value(raw).map_err(|e| QsvError::Parse(format!("YAML config: {e}")))?;
+    Ok((quilt_config, problems))
+}
+
+/// Error reporting every problem of an invalid quilt
+pub fn invalid_quilt(problems: &[String]) -> QsvError {
+    QsvError::Config(format!(
+        "Invalid quilt ({} problem{}):\n  {}",
+        problems.len(),
+        if problems.len() == 1 { "" } else { "s" },
+        problems.join("\n  ")
+    ))
+}
+
+/// Check variables, stage definitions, the stage graph and every step's arguments
+pub(crate) fn check_quilt(quilt_config: &QuiltConfig, options: &QuiltOptions) -> Vec<String> {
+    let (stages_map, mut problems) = match interpolate_stages(quilt_config, options) {
+        Ok(result) => result,
+        Err(e) => return vec![error_message(&e)],
+    };
+
+    let mut stages = Vec::new();
+    let mut all_parsed = true;
+    for (name, config) in &stages_map {
+        let label = describe_key(name);
+        if let Some(map) = config.as_mapping() {
+            for key in map.keys() {
+                if !key.as_str().is_some_and(|k| STAGE_KEYS.contains(&k)) {
+                    problems.push(format!(
+                        "stage {label}: unknown key {} (expected one of: {})",
+                        describe_key(key),
+                        STAGE_KEYS.join(", ")
+                    ));
+                }
+            }
+        }
+        match parse_stage(name, config) {
+            Ok(stage) => stages.push(stage),
+            Err(e) => {
+                all_parsed = false;
+                problems.push(format!("stage {label}: {}", error_message(&e)));
+            }
+        }
+    }
+
+    // References to stages that failed to parse would be reported twice
+    if all_parsed {
+        let graph_problems = stage_problems(&stages);
+        if graph_problems.is_empty() {
+            if let Err(e) = execution_order(&stages) {
+                let message = error_message(&e);
+                problems.push(
+                    message
+                        .strip_prefix("Invalid quilt stages: ")
+                        .unwrap_or(&message)
+                        .to_string(),
+                );
+            }
+        }
+        problems.extend(graph_problems);
+    }
+
+    for stage in &stages {
+        let name = &stage.name;
+        let config = &stage.config;
+        match config.stage_type.as_str() {
+            "process" => {
+                if config.params.is_some() {
+                    problems.push(format!(
+                        "stage '{name}': 'params' is only used by concat and join stages"
+                    ));
+                }
+                let steps = config.steps.iter().flatten().collect::<Vec<_>>();
+                if steps.is_empty() && config.source.is_none() {
+                    problems.push(format!("stage '{name}': process stage has no steps"));
+                }
+                let mut has_data = config.source.is_some();
+                for (step_name, args) in steps {
+                    let raw_step = step_name.as_str().unwrap_or("");
+                    let step = raw_step.trim_end_matches('_');
+                    let Some(schema) = step_schema(step) else {
+                        problems.push(format!(
+                            "stage '{name}': Unknown or unsupported step {}{}",
+                            describe_key(step_name),
+                            suggestion(step)
+                        ));
+                        continue;
+                    };
+                    for problem in schema.check(args) {
+                        problems.push(format!("stage '{name}', step '{raw_step}': {problem}"));
+                    }
+                    if step == "load" || step == "sql" {
+                        has_data = true;
+                    } else if !has_data {
+                        problems.push(format!(
+                            "stage '{name}', step '{raw_step}': no data yet; start the stage with 'load' or give it a 'source'"
+                        ));
+                        has_data = true;
+                    }
+                }
+            }
+            stage_type @ ("concat" | "join") => {
+                if config.steps.is_some() {
+                    problems.push(format!(
+                        "stage '{name}': 'steps' are only used by process stages"
+                    ));
+                }
+                if config.source.is_some() {
+                    problems.push(format!(
+                        "stage '{name}': {stage_type} stages read 'sources', not 'source'"
+                    ));
+                }
+                let source_count = config.sources.as_ref().map_or(0, |s| s.len());
+                if source_count == 1 {
+                    problems.push(format!(
+                        "stage '{name}': {stage_type} stages need at least two sources, found 1"
+                    ));
+                }
+                let params = config.params.clone().unwrap_or(Value::Null);
+                if let Some(schema) = stage_param_schema(stage_type) {
+                    for problem in schema.check(&params) {
+                        problems.push(format!("stage '{name}', params: {problem}"));
+                    }
+                }
+                if stage_type == "concat" {
+                    if let Some(how) = params.get("how").and_then(|v| v.as_str()) {
+                        if !CONCAT_STAGE_TYPES.contains(&how.to_lowercase().as_str()) {
+                            problems.push(format!(
+                                "stage '{name}', params: unsupported concat method '{how}' (expected one of: {})",
+                                CONCAT_STAGE_TYPES.join(", ")
+                            ));
+                        }
+                    }
+                }
+                if stage_type == "join" {
+                    problems.extend(
+                        join_problems(&params)
+                            .into_iter()
+                            .map(|problem| format!("stage '{name}', params: {problem}")),
+                    );
+                }
+            }
+            // Unknown stage types are reported with the stage graph
+            _ => {}
+        }
+    }
+    problems
+}
+
+// Join parameters that are well-typed but cannot work together
+fn join_problems(params: &Value) -> Vec<String> {
+    let mut problems = Vec::new();
+    let how = params
+        .get("how")
+        .and_then(|v| v.as_str())
+        .unwrap_or("inner")
+        .to_lowercase();
+    if !JOIN_STAGE_TYPES.contains(&how.as_str()) {
+        problems.push(format!(
+            "unsupported join type '{how}' (expected one of: {})",
+            JOIN_STAGE_TYPES.join(", ")
+        ));
+        return problems;
+    }
+    let key_count = |key: &str| match params.get(key) {
+        Some(Value::String(s)) => Some(s.split(',').count()),
+        Some(Value::Sequence(seq)) => Some(seq.len()),
+        Some(Value::Number(_)) => Some(1),
+        _ => None,
+    };
+    let on = key_count("key").or_else(|| key_count("on"));
+    let (left, right) = (key_count("left_on").or(on), key_count("right_on").or(on));
+    match (left, right) {
+        _ if how == "cross" => {}
+        (None, _) | (_, None) => {
+            problems.push("missing join keys: set 'key', or 'left_on' and 'right_on'".to_string())
+        }
+        (Some(l), Some(r)) if l != r => problems.push(format!(
+            "'left_on' and 'right_on' must name the same number of columns (got {l} and {r})"
+        )),
+        (Some(l), _) if how == "asof" && l != 1 => {
+            problems.push("as-of joins need exactly one key column on each side".to_string())
+        }
+        _ => {}
+    }
+    if how != "asof" {
+        for key in [
+            "strategy",
+            "tolerance",
+            "by",
+            "by_left",
+            "by_right",
+            "input_format",
+            "input-format",
+            "format",
+            "epoch",
+            "strict",
+        ] {
+            if params.get(key).is_some() {
+                problems.push(format!("'{key}' is only used by 'how: asof' joins"));
+            }
+        }
+    } else if let Some(strategy) = params.get("strategy").and_then(|v| v.as_str()) {
+        if parse_asof_strategy(strategy).is_none() {
+            problems.push(format!(
+                "unsupported as-of strategy '{strategy}' (expected backward, forward or nearest)"
+            ));
+        }
+    }
+    problems
+}
+
+// Error text for listing alongside other problems
+fn error_message(e: &QsvError) -> String {
+    match e {
+        QsvError::Parse(msg) => format!("invalid {msg}"),
+        other => other.to_string(),
+    }
+}
+
+fn describe_key(key: &Value) -> String {
+    match key.as_str() {
+        Some(k) => format!("'{k}'"),
+        None => format!("{key:?}"),
+    }
+}
+
+// " (did you mean 'x'?)" for a step name within two edits of a known step
+fn suggestion(step: &str) -> String {
+    STEP_SCHEMAS
+        .iter()
+        .map(|s| (edit_distance(step, s.name), s.name))
+        .filter(|(distance, _)| *distance <= 2)
+        .min()
+        .map(|(_, name)| format!(" (did you mean '{name}'?)"))
+        .unwrap_or_default()
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
@@ -0,0 +1,211 @@
+//! Interactive mode: `qsv repl data.csv` reads the input once, then runs pipeline fragments
+//! typed one line at a time against it.
+
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use polars::prelude::IntoLazy;
+use qsv::controllers::command::{
+    command_line, parse_commands, print_chainable_help, split_command_line, Command,
+    FINALIZER_COMMANDS,
+};
+use qsv::controllers::dataframe::DataFrameController;
+use qsv::controllers::datetime::report_unparsed;
+use qsv::controllers::log::LogController;
+use qsv::error::QsvError;
+
+use crate::{process_command, save_pipeline_quilt};
+
+const PROMPT: &str = "qsv> ";
+
+// Commands that replace the input or run other pipelines
+const UNAVAILABLE_COMMANDS: &[&str] = &["load", "repl", "quilt"];
+
+/// One chainable of the current chain
+struct Step {
+    cmd: Command,
+    /// The query of this step, built on the previous step's result
+    query: DataFrameController,
+    /// The collected result, kept once it has been needed
+    result: Option<DataFrameController>,
+}
+
+struct Repl {
+    load: Command,
+    /// The collected input
+    input: DataFrameController,
+    chain: Vec<Step>,
+}
+
+impl Repl {
+    fn start(cmd: &Command) -> Result<Self, QsvError> {
+        if cmd.args.is_empty() {
+            return Err(QsvError::InvalidArgument(
+                "'repl' command requires at least one file path".to_string(),
+            ));
+        }
+        let load = Command {
+            name: "load".to_string(),
+            ..cmd.clone()
+        };
+        let mut controller = DataFrameController::new();
+        process_command(&mut controller, &load)?;
+        let df = controller.collect()?;
+        LogController::info(&format!(
+            "Loaded {} rows x {} columns for the REPL",
+            df.height(),
+            df.width()
+        ));
+        Ok(Self {
+            load,
+            input: DataFrameController::from_lazy_frame(df.lazy()),
+            chain: Vec::new(),
+        })
+    }
+
+    // The uncollected query of the current chain, for showquery
+    fn query(&self) -> &DataFrameController {
+        self.chain.last().map_or(&self.input, |step| &step.query)
+    }
+
+    // The result of the current chain, collected once and reused by later commands
+    fn result(&mut self) -> Result<&DataFrameController, QsvError> {
+        let Some(step) = self.chain.last_mut() else {
+            return Ok(&self.input);
+        };
+        let result = match step.result.take() {
+            Some(result) => result,
+            None => DataFrameController::from_lazy_frame(step.query.collect()?.lazy()),
+        };
+        Ok(step.result.insert(result))
+    }
+
+    // Next steps build on the last collected result when there is one
+    fn current(&self) -> &DataFrameController {
+        self.chain.last().map_or(&self.input, |step| {
+            step.result.as_ref().unwrap_or(&step.query)
+        })
+    }
+
+    // The load command followed by the current chain
+    fn pipeline(&self) -> Vec<Command> {
+        std::iter::once(self.load.clone())
+            .chain(self.chain.iter().map(|step| step.cmd.clone()))
+            .collect()
+    }
+
+    fn history(&self) {
+        let pipeline = self.pipeline();
+        println!(
+            "qsv {}",
+            pipeline
+                .iter()
+                .map(command_line)
+                .collect::<Vec<_>>()
+                .join(" - ")
+        );
+    }
+
+    fn undo(&mut self) {
+        match self.chain.pop() {
+            Some(step) => println!("Removed: {}", command_line(&step.cmd)),
+            None => println!("Nothing to undo"),
+        }
+    }
+
+    // Run one line; a failing line leaves the chain as it was before it
+    fn run_line(&mut self, line: &str) -> Result<(), QsvError> {
+        let commands = parse_commands(&split_command_line(line)?);
+        let depth = self.chain.len();
+        let result = self.run_commands(&commands);
+        if result.is_err() {
+            self.chain.truncate(depth);
+        }
+        result
+    }
+
+    fn run_commands(&mut self, commands: &[Command]) -> Result<(), QsvError> {
+        for (pos, cmd) in commands.iter().enumerate() {
+            let name = cmd.name.as_str();
+            if UNAVAILABLE_COMMANDS.contains(&name) {
+                return Err(QsvError::InvalidArgument(format!(
+                    "'{name}' is not available in the REPL"
+                )));
+            }
+            if name == "toquilt" {
+                if pos + 1 != commands.len() {
+                    return Err(QsvError::InvalidArgument(
+                        "'toquilt' must be the last command of the line".to_string(),
+                    ));
+                }
+                return save_pipeline_quilt(&self.pipeline(), cmd);
+            }
+            if FINALIZER_COMMANDS.contains(&name) {
+                let mut target = if name == "showquery" {
+                    self.query().clone()
+                } else {
+                    self.result()?.clone()
+                };
+                process_command(&mut target, cmd)?;
+            } else {
+                let mut query = self.current().clone();
+                process_command(&mut query, cmd)?;
+                self.chain.push(Step {
+                    cmd: cmd.clone(),
+                    query,
+                    result: None,
+                });
+            }
+        }
+        // As on the command line, a line without a finalizer prints the result as a table
+        if commands
+            .last()
+            .is_some_and(|cmd| !FINALIZER_COMMANDS.contains(&cmd.name.as_str()))
+        {
+            self.result()?.showtable()?;
+        }
+        Ok(())
+    }
+}
+
+/// Load the files of a `repl` command and read commands from stdin until `exit` or EOF.
+///
+/// Errors in a line are reported and the session continues.
+pub fn run(cmd: &Command) -> Result<(), QsvError> {
+    let mut repl = Repl::start(cmd)?;
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    if interactive {
+        println!(
+            "Type commands such as 'head 5' or 'select col1 - show'; 'help' lists REPL commands."
+        );
+    }
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("{PROMPT}");
+            io::stdout()
+                .flush()
+                .map_err(|e| QsvError::Io(format!("writing prompt: {e}")))?;
+        }
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(|e| QsvError::Io(format!("reading input: {e}")))?;
+        match line.trim() {
+            "" => {}
+            line if line.starts_with('#') => {}
+            "exit" | "quit" => break,
+            "undo" => repl.undo(),
+            "history" => repl.history(),
+            "help" => print_chainable_help("repl"),
+            line => {
+                let result = repl.run_line(line);
+                report_unparsed();
+                if let Err(e) = result {
+                    eprintln!("Error: {e}");
+                }
+            }
+        }
+    }
+    Ok(())
+}
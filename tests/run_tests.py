@@ -11,19 +11,25 @@ from test_chainables_select import TestSelect
 from test_chainables_head import TestHead
 from test_chainables_tail import TestTail
 from test_chainables_contains import TestContains
+from test_chainables_filter import TestFilter
 from test_chainables_grep import TestGrep
+from test_chainables_join import TestJoin
 from test_chainables_changetz import TestChangetz
 from test_chainables_isin import TestIsin
 from test_chainables_sed import TestSed
 from test_chainables_sort import TestSort
 from test_chainables_count import TestCount
+from test_chainables_groupby import TestGroupby
+from test_chainables_sql import TestSql
 from test_chainables_uniq import TestUniq
 from test_chainables_renamecol import TestRenamecol
 from test_chainables_convert import TestConvert
 from test_chainables_pivot import TestPivot
+from test_chainables_melt import TestMelt
 from test_chainables_timeline import TestTimeline
 from test_chainables_timeslice import TestTimeslice
 from test_chainables_timeround import TestTimeround
+from test_chainables_todatetime import TestTodatetime
 
 # Finalizers
 from test_finalizers_headers import TestHeaders
@@ -33,10 +39,17 @@ from test_finalizers_partition import TestPartition
 from test_finalizers_showquery import TestShowquery
 from test_finalizers_showtable import TestShowtable
 from test_finalizers_show import TestShow
+from test_finalizers_chart import TestChart
 
 # Quilters
 from test_quilters_quilt import TestQuilt
 
+# Interactive
+from test_repl import TestRepl
+
+# Cache
+from test_cache import TestCache
+
 def run_test_suite():
     loader = unittest.TestLoader()
     suite = unittest.TestSuite()
@@ -54,19 +67,25 @@ def run_test_suite():
         TestHead,
         TestTail,
         TestContains,
+        TestFilter,
         TestGrep,
+        TestJoin,
         TestChangetz,
         TestIsin,
         TestSed,
         TestSort,
         TestCount,
+        TestGroupby,
+        TestSql,
         TestUniq,
         TestRenamecol,
         TestConvert,
         TestPivot,
+        TestMelt,
         TestTimeline,
         TestTimeslice,
         TestTimeround,
+        TestTodatetime,
     ]
     for chainable in chainables:
         suite.addTest(loader.loadTestsFromTestCase(chainable))
@@ -80,6 +99,7 @@ def run_test_suite():
         TestShowquery,
         TestShowtable,
         TestShow,
+        TestChart,
     ]
     for finalizer in finalizers:
         suite.addTest(loader.loadTestsFromTestCase(finalizer))
@@ -90,6 +110,12 @@ def run_test_suite():
     ]
     for quilter in quilters:
         suite.addTest(loader.loadTestsFromTestCase(quilter))
+
+    # Interactive
+    suite.addTest(loader.loadTestsFromTestCase(TestRepl))
+
+    # Cache
+    suite.addTest(loader.loadTestsFromTestCase(TestCache))
     
     # Run the tests
     print("\nRunning tests...")
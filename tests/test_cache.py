@@ -0,0 +1,107 @@
+import unittest
+import os
+import shutil
+import tempfile
+from test_base import QsvTestBase
+
+class TestCache(QsvTestBase):
+
+    def setUp(self):
+        super().setUp()
+        self.temp_dir = tempfile.mkdtemp()
+        self.cache_dir = os.path.join(self.temp_dir, "cache")
+        self.input = os.path.join(self.temp_dir, "simple.csv")
+        shutil.copy(self.get_fixture_path('simple.csv'), self.input)
+        self.saved_env = {k: os.environ.get(k) for k in ("QSV_CACHE_DIR", "QSV_CACHE_MAX_SIZE", "RUST_LOG")}
+        os.environ["QSV_CACHE_DIR"] = self.cache_dir
+        os.environ["RUST_LOG"] = "info"
+        os.environ.pop("QSV_CACHE_MAX_SIZE", None)
+
+    def tearDown(self):
+        for key, value in self.saved_env.items():
+            if value is None:
+                os.environ.pop(key, None)
+            else:
+                os.environ[key] = value
+        shutil.rmtree(self.temp_dir)
+
+    def cached_files(self):
+        return sorted(f for f in os.listdir(self.cache_dir) if f.endswith(".parquet"))
+
+    def test_cache_reuses_result(self):
+        """Test that a repeated pipeline starts from the cached result"""
+        first = self.run_qsv_command(f"load {self.input} - grep foo - show")
+        self.assertEqual(first.returncode, 0, first.stderr)
+        self.assertNotIn("Using cached result", first.stderr)
+        # Both the parsed input and the result of the chainables are cached
+        self.assertEqual(len(self.cached_files()), 2)
+
+        second = self.run_qsv_command(f"load {self.input} - grep foo - show")
+        self.assertEqual(second.returncode, 0, second.stderr)
+        self.assertIn("Using cached result", second.stderr)
+        self.assertEqual(second.stdout, first.stdout)
+
+    def test_cache_shared_prefix(self):
+        """Test that another pipeline over the same file starts from the cached input"""
+        self.run_qsv_command(f"load {self.input} - grep foo - show")
+        result = self.run_qsv_command(f"load {self.input} - select col1 - tail 1 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn("Using cached result", result.stderr)
+        self.assertIn(f"for: load {self.input}\n", result.stderr)
+        self.assertEqual(result.stdout.strip(), "col1\n7")
+
+    def test_cache_changed_input(self):
+        """Test that results of a changed input are not used and are pruned"""
+        self.run_qsv_command(f"load {self.input} - head 2 - show")
+        with open(self.input, "a") as f:
+            f.write("\n2023-01-01 15:00:00,10,11,12,qux\n")
+        result = self.run_qsv_command(f"load {self.input} - head 2 - show")
+        self.assertNotIn("Using cached result", result.stderr)
+
+        listing = self.run_qsv_command("cache list")
+        self.assertEqual(listing.returncode, 0, listing.stderr)
+        self.assertEqual(listing.stdout.count("(stale)"), 2)
+        self.assertIn("4 cached result(s)", listing.stdout)
+
+        pruned = self.run_qsv_command("cache prune")
+        self.assertEqual(pruned.returncode, 0, pruned.stderr)
+        self.assertIn("Removed 2 cached result(s)", pruned.stdout)
+        self.assertEqual(len(self.cached_files()), 2)
+
+    def test_cache_size_limit(self):
+        """Test that the least recently used results are evicted beyond the size limit"""
+        os.environ["QSV_CACHE_MAX_SIZE"] = "5KB"
+        self.run_qsv_command(f"load {self.input} - head 1 - show")
+        self.run_qsv_command(f"load {self.input} - tail 1 - show")
+        listing = self.run_qsv_command("cache list")
+        self.assertNotIn("head 1", listing.stdout)
+        self.assertIn("tail 1", listing.stdout)
+
+        pruned = self.run_qsv_command("cache prune --max-size 0")
+        self.assertEqual(pruned.returncode, 0, pruned.stderr)
+        self.assertEqual(self.cached_files(), [])
+
+    def test_cache_remove_and_clear(self):
+        """Test removing one result and clearing the cache"""
+        self.run_qsv_command(f"load {self.input} - head 1 - show")
+        key = self.cached_files()[0].split(".")[0]
+        removed = self.run_qsv_command(f"cache remove {key}")
+        self.assertEqual(removed.returncode, 0, removed.stderr)
+        self.assertEqual(len(self.cached_files()), 1)
+        missing = self.run_qsv_command(f"cache remove {key}")
+        self.assertEqual(missing.returncode, 1)
+
+        cleared = self.run_qsv_command("cache clear")
+        self.assertIn("Removed 1 cached result(s)", cleared.stdout)
+        self.assertIn("No cached results", self.run_qsv_command("cache list").stdout)
+
+    def test_cache_off(self):
+        """Test that nothing is cached without QSV_CACHE_DIR"""
+        del os.environ["QSV_CACHE_DIR"]
+        result = self.run_qsv_command(f"load {self.input} - head 1 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertFalse(os.path.exists(self.cache_dir))
+        self.assertEqual(self.run_qsv_command("cache list").returncode, 1)
+
+if __name__ == "__main__":
+    unittest.main()
@@ -116,5 +116,57 @@ class TestChangetz(QsvTestBase):
         self.assertNotEqual(result.returncode, 0)  # Should exit with error code
         self.assertIn("Error: Invalid target timezone", result.stderr)  # Should contain error message
 
+    def test_changetz_epoch_inputs(self):
+        """Test changetz decodes FILETIME, WebKit, Cocoa and Unix epoch columns"""
+        for column in ['filetime', 'filetime_hex', 'filetime_bytes', 'webkit', 'unix_s', 'unix_ms', 'unix_us', 'unix_ns']:
+            epoch = 'filetime_hex' if column == 'filetime_bytes' else column
+            with self.subTest(column=column):
+                result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - select {column} - changetz {column} --from-tz UTC --to-tz Asia/Tokyo --epoch {epoch} --output-format '%Y-%m-%d %H:%M:%S' - show")
+                self.assertEqual(result.returncode, 0)
+                self.assertEqual(result.stdout.strip(), '\n'.join([
+                    column,
+                    "2023-01-01 21:00:00",
+                    "2023-01-01 22:30:15",
+                    "2023-01-02 18:05:00",
+                ]))
+
+    def test_changetz_epoch_cocoa_fraction(self):
+        """Test changetz keeps the fractional seconds of Mac absolute time"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - select cocoa - head 1 - changetz cocoa --from-tz UTC --to-tz UTC --epoch cocoa - show")
+        self.assertEqual(result.stdout.strip(), "cocoa\n2023-01-01T12:00:00.500000+00:00")
+
+    def test_changetz_epoch_with_input_format(self):
+        """Test changetz rejects --epoch combined with --input-format"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - changetz unix_s --from-tz UTC --to-tz UTC --epoch unix_s --input-format '%s' - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("either --epoch or --input-format", result.stderr)
+
+    def test_changetz_invalid_epoch(self):
+        """Test changetz with an unknown epoch exits with error"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - changetz unix_s --from-tz UTC --to-tz UTC --epoch julian - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Unsupported epoch 'julian'", result.stderr)
+
+    def test_changetz_reports_unparsable_values(self):
+        """Test changetz leaves unparsable values empty and reports them"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - changetz when --from-tz UTC --to-tz UTC --output-format '%Y-%m-%d %H:%M' - select when - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "when",
+            "2023-01-15 14:30",
+            "2023-01-15 15:45",
+            "2023-01-16 14:30",
+            "",
+            "",
+            "2023-01-16 16:00",
+        ]))
+        self.assertIn("changetz could not parse 2 distinct value(s) of column 'when'", result.stderr)
+
+    def test_changetz_strict(self):
+        """Test changetz --strict fails on unparsable values"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - changetz when --from-tz UTC --to-tz UTC --strict - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("changetz could not parse", result.stderr)
+
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
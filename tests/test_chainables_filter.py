@@ -0,0 +1,87 @@
+import unittest
+from test_base import QsvTestBase
+
+class TestFilter(QsvTestBase):
+
+    def test_filter_numeric_comparison(self):
+        """Test filter with a numeric comparison"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - filter 'col1 > 3' - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 13:00:00,4,5,6,bar",
+            "2023-01-01 14:00:00,7,8,9,baz",
+        ]))
+
+    def test_filter_boolean_logic_with_parentheses(self):
+        """Test filter with and/or and grouping"""
+        result = self.run_qsv_command(f"""load {self.get_fixture_path('simple.csv')} - filter 'col1 >= 4 and (str == "bar" or col3 < 0)' - show""")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 13:00:00,4,5,6,bar",
+        ]))
+
+    def test_filter_not_and_symbolic_operators(self):
+        """Test filter with negation and symbolic boolean operators"""
+        result = self.run_qsv_command(f"""load {self.get_fixture_path('simple.csv')} - filter '!(str == "foo") && col2 != 8' - show""")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 13:00:00,4,5,6,bar",
+        ]))
+
+    def test_filter_regex_match(self):
+        """Test filter with regex match and negated regex match"""
+        result = self.run_qsv_command(f"""load {self.get_fixture_path('simple.csv')} - filter 'str =~ "^ba" and str !~ "z$"' - show""")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 13:00:00,4,5,6,bar",
+        ]))
+
+    def test_filter_datetime_literal(self):
+        """Test filter with datetime literals against a string column"""
+        result = self.run_qsv_command(f"""load {self.get_fixture_path('simple.csv')} - filter 'datetime > dt"2023-01-01 12:30:00" and datetime < 2023-01-01T14:00:00' - show""")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 13:00:00,4,5,6,bar",
+        ]))
+
+    def test_filter_null_checks(self):
+        """Test filter with is null / is not null"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('nested.ndjson')} - filter 'Event.EventData.DestinationPort is not null' - select Event.System.EventID,Event.EventData.DestinationPort - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "Event.System.EventID,Event.EventData.DestinationPort",
+            "3,443",
+        ]))
+        result = self.run_qsv_command(f"load {self.get_fixture_path('nested.ndjson')} - filter 'score is null' - select Event.System.EventID - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "Event.System.EventID",
+            "1",
+        ]))
+
+    def test_filter_unquoted_arguments(self):
+        """Test filter expression split across several arguments"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - filter col1 == 7 - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 14:00:00,7,8,9,baz",
+        ]))
+
+    def test_filter_nonexistent_column(self):
+        """Test filter with a column that does not exist"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - filter 'nope > 1' - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("Column 'nope' not found", result.stderr)
+
+    def test_filter_invalid_expression(self):
+        """Test filter with a syntax error"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - filter 'col1 > (2' - show")
+        self.assertEqual(result.returncode, 3)
+        self.assertIn("Error: failed to parse filter expression 'col1 > (2'", result.stderr)
+
+    def test_filter_invalid_operator(self):
+        """Test that an unknown operator is a parse error"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - filter 'col1 >>= (' - show")
+        self.assertEqual(result.returncode, 3)
+        self.assertIn("unexpected '>='", result.stderr)
+
+if __name__ == "__main__":
+    unittest.main()
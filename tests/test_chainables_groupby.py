@@ -0,0 +1,104 @@
+import unittest
+import os
+import tempfile
+import shutil
+from test_base import QsvTestBase
+
+class TestGroupby(QsvTestBase):
+
+    def setUp(self):
+        super().setUp()
+        self.temp_dir = tempfile.mkdtemp()
+
+    def tearDown(self):
+        if os.path.exists(self.temp_dir):
+            shutil.rmtree(self.temp_dir)
+
+    def test_groupby_default_count(self):
+        """Test groupby without --agg counts rows per group"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,count",
+            "East,5",
+            "North,5",
+            "South,5",
+            "West,5",
+        ]))
+
+    def test_groupby_multiple_aggregations(self):
+        """Test several named aggregations over multiple keys"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region,product --agg \"count(*) as n, n_unique(quarter), min(sales), max(sales), sum(sales)\" - head 3 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,product,n,n_unique_quarter,min_sales,max_sales,sum_sales",
+            "East,Laptop,2,1,1100,1500,2600",
+            "East,Phone,1,1,900,900,900",
+            "East,Tablet,2,2,600,650,1250",
+        ]))
+
+    def test_groupby_first_last_list_concat(self):
+        """Test first/last, list collection and string concatenation"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region --agg \"first(product), last(product), list(quarter), concat(product, ';') as products\" - head 1 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,first_product,last_product,list_quarter,products",
+            'East,Tablet,Phone,"[Q2, Q2, Q4, Q2, Q4]",Tablet;Laptop;Tablet;Laptop;Phone',
+        ]))
+
+    def test_groupby_percentiles_and_mode(self):
+        """Test percentile spellings and mode"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region --agg \"p50(sales), percentile(sales, 90), quantile(sales, 0.25), mode(product)\" - head 1 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,p50_sales,p90_sales,p25_sales,mode_product",
+            "East,900.0,1340.0,650.0,Laptop",
+        ]))
+
+    def test_groupby_invalid_column(self):
+        """Test groupby with a non-existent aggregation column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region --agg \"sum(nonexistent)\" - show")
+        self.assertEqual(result.returncode, 2)
+        self.assertIn("not found", result.stderr)
+
+    def test_groupby_unknown_function(self):
+        """Test groupby with an unknown aggregation function"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region --agg \"bogus(sales)\" - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Unknown aggregation function", result.stderr)
+
+    def test_groupby_duplicate_output_name(self):
+        """Test that clashing output names are rejected"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - groupby region --agg \"sum(sales), sum(sales)\" - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Duplicate output column", result.stderr)
+
+    def test_groupby_quilt(self):
+        """Test groupby from a quilt workflow with a list of aggregations"""
+        quilt_content = f"""title: 'Groupby Test Quilt'
+stages:
+  summarize:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('comprehensive.csv')}"
+      groupby:
+        by: region
+        agg:
+          - count(*) as n
+          - concat(quarter, '|') as quarters
+      head:
+        number: 1
+      show:
+"""
+        quilt_file = os.path.join(self.temp_dir, "groupby_quilt.yaml")
+        with open(quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("region,n,quarters", result.stdout)
+        self.assertIn("East,5,Q2|Q2|Q4|Q2|Q4", result.stdout)
+
+if __name__ == '__main__':
+    unittest.main()
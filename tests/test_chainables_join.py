@@ -0,0 +1,106 @@
+import unittest
+from test_base import QsvTestBase
+
+class TestJoin(QsvTestBase):
+
+    def test_join_inner_with_left_and_right_keys(self):
+        """Test inner join with different key names on each side"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --left-on col1 --right-on id - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,str,str_right,label",
+            "1,foo,foo,one",
+            "4,bar,bar,four",
+        ]))
+
+    def test_join_left(self):
+        """Test left join keeps unmatched left rows"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --left-on col1 --right-on id --how left - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,str,str_right,label",
+            "1,foo,foo,one",
+            "4,bar,bar,four",
+            "7,baz,,",
+        ]))
+
+    def test_join_full(self):
+        """Test full join keeps unmatched rows from both sides"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --left-on col1 --right-on id --how full - sort col1 - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,str,str_right,label",
+            "1,foo,foo,one",
+            "4,bar,bar,four",
+            "7,baz,,",
+            "10,,qux,ten",
+        ]))
+
+    def test_join_semi_and_anti(self):
+        """Test semi and anti joins only filter the left side"""
+        semi = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --on str --how semi - show")
+        self.assertEqual(semi.stdout.strip(), '\n'.join([
+            "col1,str",
+            "1,foo",
+            "4,bar",
+        ]))
+        anti = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --on str --how anti - show")
+        self.assertEqual(anti.stdout.strip(), '\n'.join([
+            "col1,str",
+            "7,baz",
+        ]))
+
+    def test_join_multiple_keys(self):
+        """Test join on multiple key columns"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --left-on col1,str --right-on id,str - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,str,label",
+            "1,foo,one",
+            "4,bar,four",
+        ]))
+
+    def test_join_custom_suffix(self):
+        """Test suffix for clashing column names"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup.csv')} --left-on col1 --right-on id --suffix _lookup - headers --plain")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1",
+            "str",
+            "str_lookup",
+            "label",
+        ]))
+
+    def test_join_cross(self):
+        """Test cross join"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1 - head 2 - join {self.get_fixture_path('simple_lookup.csv')} --how cross - select col1,id - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,id",
+            "1,1",
+            "1,4",
+            "1,10",
+            "4,1",
+            "4,4",
+            "4,10",
+        ]))
+
+    def test_join_separator(self):
+        """Test joining a file with a different separator than the loaded data"""
+        for option in ["--separator", "-s"]:
+            result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - join {self.get_fixture_path('simple_lookup_semicolon.csv')} --left-on col1 --right-on id {option} ';' - show")
+            self.assertEqual(result.returncode, 0, result.stderr)
+            self.assertEqual(result.stdout.strip(), '\n'.join([
+                "col1,str,str_right,label",
+                "1,foo,foo,one",
+                "4,bar,bar,four",
+            ]))
+
+    def test_join_nonexistent_key(self):
+        """Test join with a key column that does not exist"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - join {self.get_fixture_path('simple_lookup.csv')} --on nope - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("Column 'nope' not found", result.stderr)
+
+    def test_join_invalid_type(self):
+        """Test join with an unsupported join type"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - join {self.get_fixture_path('simple_lookup.csv')} --on str --how sideways - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("Unsupported join type", result.stderr)
+
+if __name__ == "__main__":
+    unittest.main()
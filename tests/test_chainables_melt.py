@@ -0,0 +1,106 @@
+import unittest
+import os
+import tempfile
+import shutil
+from test_base import QsvTestBase
+
+class TestMelt(QsvTestBase):
+
+    def setUp(self):
+        super().setUp()
+        self.temp_dir = tempfile.mkdtemp()
+
+    def tearDown(self):
+        if os.path.exists(self.temp_dir):
+            shutil.rmtree(self.temp_dir)
+
+    def test_melt_colon_range(self):
+        """Test melting a colon range of value columns"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - melt --id str --value-cols col1:col3 - show")
+        self.assertEqual(result.returncode, 0)
+        expected_output = "\n".join([
+            "str,variable,value",
+            "foo,col1,1",
+            "bar,col1,4",
+            "baz,col1,7",
+            "foo,col2,2",
+            "bar,col2,5",
+            "baz,col2,8",
+            "foo,col3,3",
+            "bar,col3,6",
+            "baz,col3,9",
+        ])
+        self.assertEqual(result.stdout.strip(), expected_output)
+
+    def test_melt_numeric_range_and_names(self):
+        """Test melting a numeric range with custom variable/value names"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - melt --id str --value-cols 2:3 --variable-name metric --value-name reading - show")
+        self.assertEqual(result.returncode, 0)
+        lines = result.stdout.strip().split('\n')
+        self.assertEqual(lines[0], "str,metric,reading")
+        self.assertEqual(lines[1], "foo,col1,1")
+        self.assertEqual(len(lines), 7)
+
+    def test_melt_defaults_to_non_id_columns(self):
+        """Test that all non-id columns are melted by default, mixed types as strings"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - melt --id col1 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "col1,variable,value",
+            "1,str,foo",
+            "4,str,bar",
+            "7,str,baz",
+        ]))
+
+    def test_melt_inverts_pivot(self):
+        """Test that melting a pivot table recovers the long records"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols product --values sales - melt --id region --variable-name product --value-name sales - filter \"region == 'East'\" - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,product,sales",
+            "East,Laptop,2600",
+            "East,Phone,900",
+            "East,Tablet,1250",
+        ]))
+
+    def test_melt_invalid_column(self):
+        """Test melt with a non-existent id column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - melt --id nonexistent - show")
+        self.assertEqual(result.returncode, 2)
+        self.assertIn("not found", result.stderr)
+
+    def test_melt_overlapping_columns(self):
+        """Test melt rejects a column used as both id and value"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - melt --id col1 --value-cols col1:col2 - show")
+        self.assertNotEqual(result.returncode, 0)
+
+    def test_melt_quilt(self):
+        """Test melt from a quilt workflow"""
+        quilt_content = f"""title: 'Melt Test Quilt'
+stages:
+  reshape:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+      melt:
+        id: str
+        value_cols:
+          - col1
+          - col2
+        variable_name: column
+      head:
+        number: 2
+      show:
+"""
+        quilt_file = os.path.join(self.temp_dir, "melt_quilt.yaml")
+        with open(quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("str,column,value", result.stdout)
+        self.assertIn("foo,col1,1", result.stdout)
+
+if __name__ == '__main__':
+    unittest.main()
@@ -85,5 +85,69 @@ class TestPivot(QsvTestBase):
         # Should have data for 4 regions (North, South, East, West)
         self.assertEqual(len(lines), 5)  # Header + 4 regions
 
+    def test_pivot_wide_columns(self):
+        """Test that each distinct --cols value becomes its own column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols product --values sales - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "region,Laptop,Phone,Tablet",
+            "East,2600,900,1250",
+            "North,2300,1750,600",
+            "South,2800,1750,700",
+            "West,1400,1550,1300",
+        ]))
+
+    def test_pivot_missing_cells_empty_by_default(self):
+        """Test that cells without matching rows are left empty"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols quarter --values sales - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "region,Q1,Q2,Q3,Q4",
+            "East,,3200,,1550",
+            "North,2750,,1900,",
+            "South,1900,900,1600,850",
+            "West,,800,1300,2150",
+        ]))
+
+    def test_pivot_fill(self):
+        """Test filling missing cells with --fill"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols quarter --values sales --fill 0 - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "region,Q1,Q2,Q3,Q4",
+            "East,0,3200,0,1550",
+            "North,2750,0,1900,0",
+            "South,1900,900,1600,850",
+            "West,0,800,1300,2150",
+        ]))
+
+    def test_pivot_totals(self):
+        """Test row and column totals"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols product --values sales --row-totals --col-totals - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "region,Laptop,Phone,Tablet,Total",
+            "East,2600,900,1250,4750",
+            "North,2300,1750,600,4650",
+            "South,2800,1750,700,5250",
+            "West,1400,1550,1300,4250",
+            "Total,9100,5950,3850,18900",
+        ]))
+
+    def test_pivot_multiple_values_and_aggregations(self):
+        """Test several value columns and aggregation functions in one call"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --rows region --cols quarter --values sales,value --agg sum,count --fill 0 - show")
+        self.assertEqual(result.returncode, 0)
+        lines = result.stdout.strip().split('\n')
+        self.assertEqual(lines[0], ",".join(
+            ["region"] + [f"{v}_{a}_{q}" for q in ["Q1", "Q2", "Q3", "Q4"] for v in ["sales", "value"] for a in ["sum", "count"]]
+        ))
+        self.assertEqual(lines[3], "South,1900,2,1700,2,900,1,400,1,1600,1,1800,1,850,1,1000,1")
+
+    def test_pivot_cols_only_wide(self):
+        """Test pivot with only --cols produces a single wide row"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - pivot --cols product --values sales - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "Laptop,Phone,Tablet",
+            "9100,5950,3850",
+        ]))
+
 if __name__ == "__main__":
     unittest.main() 
\ No newline at end of file
@@ -121,5 +121,11 @@ class TestSelect(QsvTestBase):
         for col in ["datetime", "col3"]:
             self.assertNotIn(col, result.stdout.strip())
 
+    def test_select_missing_column_exit_code(self):
+        """Test that selecting a missing column fails with the column-not-found exit code"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select nope - show")
+        self.assertEqual(result.returncode, 2)
+        self.assertIn("Error: Column 'nope' not found in DataFrame for select operation", result.stderr)
+
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
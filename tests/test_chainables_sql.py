@@ -0,0 +1,118 @@
+import unittest
+import os
+import tempfile
+import shutil
+from test_base import QsvTestBase
+
+class TestSql(QsvTestBase):
+
+    def setUp(self):
+        super().setUp()
+        self.temp_dir = tempfile.mkdtemp()
+
+    def tearDown(self):
+        if os.path.exists(self.temp_dir):
+            shutil.rmtree(self.temp_dir)
+
+    def test_sql_select_where(self):
+        """Test a basic SELECT with WHERE and ORDER BY against self"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - sql \"SELECT col1, str FROM self WHERE col1 > 1 ORDER BY col1 DESC\" - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "col1,str",
+            "7,baz",
+            "4,bar",
+        ]))
+
+    def test_sql_group_by(self):
+        """Test GROUP BY with aggregations"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - sql \"SELECT region, COUNT(*) AS n, SUM(sales) AS total FROM self GROUP BY region ORDER BY region\" - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,n,total",
+            "East,5,4750",
+            "North,5,4650",
+            "South,5,5250",
+            "West,5,4250",
+        ]))
+
+    def test_sql_cte(self):
+        """Test a common table expression"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - sql \"WITH t AS (SELECT region, SUM(sales) AS total FROM self GROUP BY region) SELECT * FROM t WHERE total > 4600 ORDER BY total DESC\" - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,total",
+            "South,5250",
+            "East,4750",
+            "North,4650",
+        ]))
+
+    def test_sql_window_function(self):
+        """Test a window aggregate over a partition"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('comprehensive.csv')} - sql \"SELECT region, sales, SUM(sales) OVER (PARTITION BY region) AS region_total FROM self\" - head 2 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "region,sales,region_total",
+            "North,1000,4650",
+            "North,800,4650",
+        ]))
+
+    def test_sql_extra_table(self):
+        """Test joining a file registered with --table"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - sql \"SELECT s.col1, l.label FROM self s JOIN lookup AS l ON s.str = l.str ORDER BY s.col1\" --table lookup={self.get_fixture_path('simple_lookup.csv')} - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "col1,label",
+            "1,one",
+            "4,four",
+        ]))
+
+    def test_sql_chain_continues(self):
+        """Test that the query result continues the chain"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - sql \"SELECT col1 * 10 AS x FROM self\" - sort x --desc - head 1 - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "x\n70")
+
+    def test_sql_invalid_column(self):
+        """Test SQL referencing a non-existent column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - sql \"SELECT nonexistent FROM self\" - show")
+        self.assertEqual(result.returncode, 2)
+        self.assertIn("not found", result.stderr)
+
+    def test_sql_syntax_error(self):
+        """Test SQL with a syntax error"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - sql \"SELEC col1 FROM self\" - show")
+        self.assertEqual(result.returncode, 3)
+        self.assertIn("SQL query", result.stderr)
+
+    def test_sql_quilt_stage_tables(self):
+        """Test querying earlier quilt stages by name"""
+        quilt_content = f"""title: 'SQL Test Quilt'
+stages:
+  events:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+  labels:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple_lookup.csv')}"
+  report:
+    type: process
+    steps:
+      sql:
+        query: "SELECT e.col1, l.label FROM events e JOIN labels l USING (str) ORDER BY e.col1"
+      show:
+"""
+        quilt_file = os.path.join(self.temp_dir, "sql_quilt.yaml")
+        with open(quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("col1,label\n1,one\n4,four", result.stdout)
+
+if __name__ == '__main__':
+    unittest.main()
@@ -28,5 +28,153 @@ class TestTimeline(QsvTestBase):
             "2023-01-01 12:00:00,13",
         ]))
 
+    def test_timeline_epoch(self):
+        """Test timeline buckets Unix millisecond timestamps with --epoch"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - timeline unix_ms --interval 1d --epoch unix_ms - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count",
+            "2023-01-01 00:00:00,2",
+            "2023-01-02 00:00:00,1",
+        ]))
+
+    def test_timeline_mixed_formats(self):
+        """Test timeline parses mixed layouts with the shared parser and reports the rest"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeline when --interval 1d --sum value - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count,sum_value",
+            ",3,160.0",
+            "2023-01-15 00:00:00,2,30.0",
+            "2023-01-16 00:00:00,2,90.0",
+        ]))
+        self.assertIn("timeline could not parse 2 distinct value(s) of column 'when'", result.stderr)
+
+    def test_timeline_multiple_aggregations(self):
+        """Test several column lists and --agg aggregations in one call"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --sum bytes,latency --max latency --agg 'p50(latency) as median, n_unique(host)' - head 2 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count,sum_bytes,sum_latency,max_latency,median,n_unique_host",
+            "2023-01-02 00:00:00,4,1000.0,100.0,40.0,25.0,2",
+            "2023-01-04 00:00:00,1,500.0,50.0,50.0,50.0,1",
+        ]))
+
+    def test_timeline_fill(self):
+        """Test --fill adds buckets with zero counts and sums between the first and last bucket"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --sum bytes --fill - head 4 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count,sum_bytes",
+            "2023-01-02 00:00:00,4,1000.0",
+            "2023-01-03 00:00:00,0,0.0",
+            "2023-01-04 00:00:00,1,500.0",
+            "2023-01-05 00:00:00,0,0.0",
+        ]))
+
+    def test_timeline_fill_agg(self):
+        """Test --fill zeroes counts and sums from --agg and leaves other aggregations empty"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --agg 'sum(bytes) as total, n_unique(host) as hosts, mean(latency) as avg' --fill - head 3 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count,total,hosts,avg",
+            "2023-01-02 00:00:00,4,1000,2,25.0",
+            "2023-01-03 00:00:00,0,0,0,",
+            "2023-01-04 00:00:00,1,500,1,50.0",
+        ]))
+
+    def test_timeline_fill_keeps_unparsed(self):
+        """Test --fill keeps rows with unparsable times in their empty bucket"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeline when --interval 1d --fill - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count",
+            ",3",
+            "2023-01-15 00:00:00,2",
+            "2023-01-16 00:00:00,2",
+        ]))
+
+    def test_timeline_start_end(self):
+        """Test --start and --end fill an explicit range and leave out rows outside it"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1mo --start '2022-12-15' --end '2023-03-01' - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1mo,count",
+            "2022-12-01 00:00:00,0",
+            "2023-01-01 00:00:00,6",
+            "2023-02-01 00:00:00,1",
+            "2023-03-01 00:00:00,0",
+        ]))
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --start 2023-01-03 --end 2023-01-04 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "timeline_1d,count\n2023-01-03 00:00:00,0\n2023-01-04 00:00:00,1")
+
+    def test_timeline_by_series(self):
+        """Test --by fills every series over the same buckets"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --by host --sum bytes --start 2023-01-02 --end 2023-01-04 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,host,count,sum_bytes",
+            "2023-01-02 00:00:00,web1,3,800.0",
+            "2023-01-02 00:00:00,web2,1,200.0",
+            "2023-01-03 00:00:00,web1,0,0.0",
+            "2023-01-03 00:00:00,web2,0,0.0",
+            "2023-01-04 00:00:00,web1,0,0.0",
+            "2023-01-04 00:00:00,web2,1,500.0",
+        ]))
+
+    def test_timeline_weeks(self):
+        """Test week buckets start on Monday by default and on --week-start otherwise"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1w - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "timeline_1w,count\n2023-01-02 00:00:00,6\n2023-02-13 00:00:00,1")
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1w --week-start sun - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1w,count",
+            "2023-01-01 00:00:00,5",
+            "2023-01-08 00:00:00,1",
+            "2023-02-12 00:00:00,1",
+        ]))
+
+    def test_timeline_tz_local_days(self):
+        """Test --tz buckets by local days of the zone"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --tz Asia/Tokyo - head 3 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count",
+            "2023-01-02T00:00:00.000000+09:00,4",
+            "2023-01-04T00:00:00.000000+09:00,1",
+            "2023-01-09T00:00:00.000000+09:00,1",
+        ]))
+
+    def test_timeline_fill_across_dst(self):
+        """Test filled hours skip the wall-clock hour lost to a DST change"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - todatetime time --tz Europe/Berlin - timeline time --interval 1h --start '2023-03-26 00:00:00' --end '2023-03-26 03:00:00' - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1h,count",
+            "2023-03-26T00:00:00.000000+01:00,0",
+            "2023-03-26T01:00:00.000000+01:00,0",
+            "2023-03-26T03:00:00.000000+02:00,0",
+        ]))
+
+    def test_timeline_invalid_options(self):
+        """Test invalid week starts, duplicate columns and oversized fills"""
+        fixture = self.get_fixture_path('timeline_hosts.csv')
+        result = self.run_qsv_command(f"load {fixture} - timeline time --interval 1d --week-start sun - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("--week-start only applies to week intervals", result.stderr)
+        result = self.run_qsv_command(f"load {fixture} - timeline time --interval 1w --week-start funday - show")
+        self.assertEqual(result.returncode, 1)
+        result = self.run_qsv_command(f"load {fixture} - timeline time --interval 1d --sum bytes --agg 'sum(bytes)' - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Duplicate output column 'sum_bytes'", result.stderr)
+        result = self.run_qsv_command(f"load {fixture} - timeline time --interval 1s --start 2000-01-01 - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("more than 1000000 buckets", result.stderr)
+        result = self.run_qsv_command(f"load {fixture} - timeline time --interval 1d --by missing - show")
+        self.assertEqual(result.returncode, 2)
+
 if __name__ == "__main__":
     unittest.main() 
\ No newline at end of file
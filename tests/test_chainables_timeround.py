@@ -84,5 +84,50 @@ class TestTimeround(QsvTestBase):
         self.assertEqual(len(lines), 4)  # Header + 3 data rows
         self.assertEqual(lines[0], "id,date_only,value")
 
+    def test_timeround_epoch(self):
+        """Test timeround decodes FILETIME values with --epoch"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - timeround filetime --unit h --output hour --epoch filetime - select event,hour - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "event,hour",
+            "e1,2023-01-01 12:00:00",
+            "e2,2023-01-01 13:00:00",
+            "e3,2023-01-02 09:00:00",
+        ]))
+
+    def test_timeround_reports_unparsable_values(self):
+        """Test timeround leaves unparsable values empty and reports them on stderr"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeround when --unit h --output hour - select id,hour - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "id,hour",
+            "1,2023-01-15 14:00:00",
+            "2,2023-01-15 15:00:00",
+            "3,2023-01-16 14:00:00",
+            "4,",
+            "5,",
+            "6,2023-01-16 16:00:00",
+            "7,",
+        ]))
+        self.assertIn("timeround could not parse 2 distinct value(s) of column 'when' as datetimes; they were left empty: 'not a date', 'unknown'", result.stderr)
+
+    def test_timeround_strict(self):
+        """Test timeround --strict fails on unparsable values"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeround when --unit d --strict - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("timeround could not parse", result.stderr)
+        self.assertIn("'not a date'", result.stderr)
+
+    def test_timeround_input_format(self):
+        """Test timeround parses only the given --input-format"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - head 2 - timeround when --unit m --input-format '%d/%b/%Y:%H:%M:%S' - select id,when - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "id,when",
+            "1,",
+            "2,2023-01-15 15:45:00",
+        ]))
+        self.assertIn("'2023-01-15 14:30:00'", result.stderr)
+
 if __name__ == "__main__":
     unittest.main() 
\ No newline at end of file
@@ -74,5 +74,27 @@ class TestTimeslice(QsvTestBase):
             "2023-01-01 06:00:00,Golf",
         ]))
 
+    def test_timeslice_epoch(self):
+        """Test timeslice filters WebKit timestamps with --epoch"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - timeslice webkit --start '2023-01-01 13:00:00' --end '2023-01-01 23:59:59' --epoch webkit - select event,webkit - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "event,webkit",
+            "e2,13317053415000000",
+        ]))
+
+    def test_timeslice_mixed_formats(self):
+        """Test timeslice parses mixed layouts and drops rows it cannot parse"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeslice when --start 2023-01-16 - select id - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "id\n3\n6")
+        self.assertIn("timeslice could not parse 2 distinct value(s) of column 'when'", result.stderr)
+
+    def test_timeslice_strict(self):
+        """Test timeslice --strict fails on unparsable values"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - timeslice when --start 2023-01-16 --strict - show")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("could not parse", result.stderr)
+
 if __name__ == "__main__":
     unittest.main() 
\ No newline at end of file
@@ -0,0 +1,105 @@
+#!/usr/bin/env python3
+
+import os
+import tempfile
+import unittest
+from test_base import QsvTestBase
+
+class TestTodatetime(QsvTestBase):
+
+    def test_todatetime_default_utc(self):
+        """Test todatetime reads wall-clock values as UTC by default"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime datetime - select datetime,str - head 2 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "datetime,str",
+            "2023-01-01T12:00:00.000000+00:00,foo",
+            "2023-01-01T13:00:00.000000+00:00,bar",
+        ]))
+
+    def test_todatetime_tz(self):
+        """Test todatetime reads wall-clock values in --tz"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime datetime --tz Asia/Tokyo - select datetime - head 1 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "datetime\n2023-01-01T12:00:00.000000+09:00")
+
+    def test_todatetime_epoch_converts_to_tz(self):
+        """Test todatetime converts epoch instants to --tz"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Asia/Tokyo - select event,unix_s - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "event,unix_s",
+            "e1,2023-01-01T21:00:00.000000+09:00",
+            "e2,2023-01-01T22:30:15.000000+09:00",
+            "e3,2023-01-02T18:05:00.000000+09:00",
+        ]))
+
+    def test_todatetime_sorts_chronologically(self):
+        """Test a converted column sorts by time rather than by text"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('mixed_datetimes.csv')} - todatetime when - sort when - tail 4 - select id - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "id\n1\n2\n3\n6")
+        self.assertIn("todatetime could not parse 2 distinct value(s)", result.stderr)
+
+    def test_todatetime_timeslice_in_zone(self):
+        """Test timeslice bounds are wall-clock times in the zone of the column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Asia/Tokyo - timeslice unix_s --start '2023-01-01 22:00:00' --end '2023-01-02 18:00:00' - select event - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "event\ne2")
+
+    def test_todatetime_filter_in_zone(self):
+        """Test filter literals are wall-clock times in the zone of the column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Asia/Tokyo - filter 'unix_s >= \"2023-01-01 22:00:00\"' - select event - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "event\ne2\ne3")
+
+    def test_todatetime_timeline_local_days(self):
+        """Test timeline buckets a zoned column by local days and keeps the zone"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Pacific/Kiritimati - timeline unix_s --interval 1d - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "timeline_1d,count",
+            "2023-01-02T00:00:00.000000+14:00,3",
+        ]))
+
+    def test_todatetime_timeround_local_date(self):
+        """Test timeround rounds a zoned column to its local date"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Pacific/Kiritimati - timeround unix_s --unit d --output day - timeround unix_s --unit h --output hour - select event,day,hour - head 1 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "event,day,hour\ne1,2023-01-02,2023-01-02T02:00:00.000000+14:00")
+
+    def test_todatetime_changetz(self):
+        """Test changetz converts a zoned column whatever --from-tz says"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Asia/Tokyo - changetz unix_s --from-tz Europe/Paris --to-tz UTC - select event,unix_s - head 1 - show")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "event,unix_s\ne1,2023-01-01T12:00:00.000000+00:00")
+
+    def test_todatetime_showtable(self):
+        """Test showtable formats datetimes like show"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime datetime --tz Asia/Tokyo - select datetime - head 1 - showtable")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn("2023-01-01T12:00:00.000000+09:00", result.stdout)
+
+    def test_todatetime_parquet_keeps_type(self):
+        """Test dumping to Parquet keeps the datetime and its zone"""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            output = os.path.join(temp_dir, "zoned.parquet")
+            result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime datetime --tz Asia/Tokyo - dump -o {output}")
+            self.assertEqual(result.returncode, 0, result.stderr)
+            result = self.run_qsv_command(f"load {output} - select datetime - head 1 - show")
+            self.assertEqual(result.returncode, 0, result.stderr)
+            self.assertEqual(result.stdout.strip(), "datetime\n2023-01-01T12:00:00.000000+09:00")
+
+    def test_todatetime_invalid_tz(self):
+        """Test todatetime rejects unknown time zones"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime datetime --tz Mars/Olympus - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Invalid timezone 'Mars/Olympus'", result.stderr)
+
+    def test_todatetime_column_not_found(self):
+        """Test todatetime with a missing column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - todatetime missing - show")
+        self.assertEqual(result.returncode, 2)
+
+if __name__ == '__main__':
+    unittest.main()
@@ -0,0 +1,112 @@
+#!/usr/bin/env python3
+
+import unittest
+from test_base import QsvTestBase
+
+class TestChart(QsvTestBase):
+
+    def test_chart_bar_timeline(self):
+        """Test a bar chart of timeline counts labelled by the bucket column"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --start 2023-01-01 --end 2023-01-04 - chart count --width 40")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "count by timeline_1d",
+            "2023-01-01 00:00:00 │ 0",
+            "2023-01-02 00:00:00 │█████████████████ 4",
+            "2023-01-03 00:00:00 │ 0",
+            "2023-01-04 00:00:00 │████▎ 1",
+        ]))
+
+    def test_chart_bar_series(self):
+        """Test --by draws one bar per series under each label"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --by host --sum bytes --start 2023-01-02 --end 2023-01-03 - chart sum_bytes --by host --width 40")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "sum_bytes by timeline_1d, per host",
+            "2023-01-02 00:00:00 web1 │██████████ 800",
+            "                    web2 │██▌ 200",
+            "2023-01-03 00:00:00 web1 │ 0",
+            "                    web2 │ 0",
+        ]))
+
+    def test_chart_ascii_x_and_height(self):
+        """Test --x labels, --ascii bars and --height limiting the bars drawn"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - chart bytes --x host --ascii --width 30 --height 2")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "bytes by host",
+            "web1 |### 100",
+            "web2 |###### 200",
+            "... 5 more; raise --height to draw them",
+        ]))
+
+    def test_chart_log_scale(self):
+        """Test --log keeps small values visible next to large ones"""
+        linear = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1h --fill - chart count --ascii --width 40 --height 3")
+        logged = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1h --fill - chart count --ascii --width 40 --height 3 --log")
+        self.assertEqual(logged.returncode, 0, logged.stderr)
+        self.assertIn("count by timeline_1h (log scale)", logged.stdout)
+        self.assertIn("2023-01-02 08:00:00 |######### 1", linear.stdout)
+        self.assertIn("2023-01-02 08:00:00 |########### 1", logged.stdout)
+
+    def test_chart_histogram(self):
+        """Test a histogram with equal-width bins, the last one closed"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - chart latency --type hist --bins 3 --width 30")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "latency histogram",
+            "[10, 30) │████████████ 2",
+            "[30, 50) │████████████ 2",
+            "[50, 70] │██████████████████ 3",
+        ]))
+
+    def test_chart_histogram_series(self):
+        """Test histograms of several series share their bins"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - chart latency --type hist --bins 2 --by host --width 30")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "latency histogram, per host",
+            "[10, 40) web1 │█████████████ 2",
+            "         web2 │██████▌ 1",
+            "[40, 70] web1 │█████████████ 2",
+            "         web2 │█████████████ 2",
+        ]))
+
+    def test_chart_sparkline_series(self):
+        """Test one sparkline per series, squeezed into the width"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --by host --fill - chart count --by host --type spark --width 60")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "count by timeline_1d, per host, largest of 2 rows per cell",
+            "web1 │█  ▃                    min 0 max 3",
+            "web2 │▃▃                   ▃  min 0 max 1",
+            "      2023-01-02 00:00:00 → 2023-02-14 00:00:00",
+        ]))
+
+    def test_chart_sparkline_height(self):
+        """Test --height draws sparklines over several rows"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('timeline_hosts.csv')} - chart latency --type spark --height 2 --ascii")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.rstrip(), '\n'.join([
+            "latency by time",
+            "        |   .-+#",
+            "latency |:*=####  min 10 max 70",
+            "         2023-01-02 08:15:00 -> 2023-02-14 06:00:00",
+        ]))
+
+    def test_chart_errors(self):
+        """Test chart rejects text columns, unknown types and bad sizes"""
+        fixture = self.get_fixture_path('timeline_hosts.csv')
+        result = self.run_qsv_command(f"load {fixture} - chart host")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("chart needs a numeric column", result.stderr)
+        result = self.run_qsv_command(f"load {fixture} - chart bytes --type pie")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Unsupported chart type 'pie'", result.stderr)
+        result = self.run_qsv_command(f"load {fixture} - chart bytes --width 0")
+        self.assertEqual(result.returncode, 1)
+        result = self.run_qsv_command(f"load {fixture} - chart bytes --by missing")
+        self.assertEqual(result.returncode, 2)
+
+if __name__ == '__main__':
+    unittest.main()
@@ -1,3 +1,4 @@
+import json
 import unittest
 from pathlib import Path
 from test_base import QsvTestBase
@@ -73,5 +74,187 @@ class TestDump(QsvTestBase):
         output_file.unlink()
 
 
+    def test_dump_to_ndjson_by_extension(self):
+        """Test dump picks NDJSON from the output extension"""
+
+        output_file = Path("/tmp/test_output.ndjson")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - head 2 - dump -o {output_file}")
+        self.assertTrue(output_file.exists())
+
+        self.assertEqual(
+            output_file.read_text().strip(),
+            '\n'.join([
+                '{"datetime":"2023-01-01 12:00:00","col1":1,"col2":2,"col3":3,"str":"foo"}',
+                '{"datetime":"2023-01-01 13:00:00","col1":4,"col2":5,"col3":6,"str":"bar"}',
+            ])
+        )
+        output_file.unlink()
+
+
+    def test_dump_to_json_array(self):
+        """Test dump to a JSON array"""
+
+        output_file = Path("/tmp/test_output.json")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - dump -o {output_file}")
+        self.assertTrue(output_file.exists())
+
+        self.assertEqual(
+            json.loads(output_file.read_text()),
+            [
+                {"col1": 1, "str": "foo"},
+                {"col1": 4, "str": "bar"},
+                {"col1": 7, "str": "baz"},
+            ]
+        )
+        output_file.unlink()
+
+
+    def test_dump_to_json_array_streaming(self):
+        """Test dump to a JSON array with batch size (streaming mode)"""
+
+        output_file = Path("/tmp/test_output_streaming.json")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1 - dump -o {output_file} --batch-size 1MB")
+        self.assertTrue(output_file.exists())
+
+        self.assertEqual(
+            json.loads(output_file.read_text()),
+            [{"col1": 1}, {"col1": 4}, {"col1": 7}]
+        )
+        output_file.unlink()
+
+
+    def test_dump_with_format_option(self):
+        """Test --format overrides the output extension"""
+
+        output_file = Path("/tmp/test_output.txt")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select col1,str - head 2 - dump -o {output_file} --format markdown")
+        self.assertTrue(output_file.exists())
+
+        self.assertEqual(
+            output_file.read_text().strip(),
+            '\n'.join([
+                "| col1 | str |",
+                "| --- | --- |",
+                "| 1 | foo |",
+                "| 4 | bar |",
+            ])
+        )
+        output_file.unlink()
+
+
+    def test_dump_to_html(self):
+        """Test dump to an HTML table"""
+
+        output_file = Path("/tmp/test_output.html")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - select str - head 1 - dump -o {output_file}")
+        self.assertTrue(output_file.exists())
+
+        content = output_file.read_text()
+        self.assertIn("<table>", content)
+        self.assertIn("<th>str</th>", content)
+        self.assertIn("<td>foo</td>", content)
+        self.assertIn("</table>", content)
+        output_file.unlink()
+
+
+    def test_dump_to_parquet_streaming_roundtrip(self):
+        """Test streaming dump to parquet can be loaded back"""
+
+        output_file = Path("/tmp/test_output_streaming.parquet")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - dump -o {output_file} --batch-size 1MB")
+        self.assertTrue(output_file.exists())
+
+        result = self.run_qsv_command(f"load {output_file} - select col1,str - show")
+        self.assertEqual(
+            result.stdout.strip(),
+            '\n'.join([
+                "col1,str",
+                "1,foo",
+                "4,bar",
+                "7,baz",
+            ])
+        )
+        output_file.unlink()
+
+
+    def test_dump_to_arrow_ipc(self):
+        """Test dump to Arrow IPC writes a file with the Arrow magic bytes"""
+
+        output_file = Path("/tmp/test_output.arrow")
+
+        if output_file.exists():
+            output_file.unlink()
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - dump -o {output_file}")
+        self.assertTrue(output_file.exists())
+        self.assertTrue(output_file.read_bytes().startswith(b"ARROW1"))
+        output_file.unlink()
+
+
+    def test_dump_to_compressed_csv(self):
+        """Test dump compresses CSV output chosen by the file extension"""
+
+        for ext in ["gz", "zst", "bz2", "xz"]:
+            with self.subTest(ext=ext):
+                output_file = Path(f"/tmp/test_output.csv.{ext}")
+
+                if output_file.exists():
+                    output_file.unlink()
+
+                self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - dump -o {output_file}")
+                self.assertTrue(output_file.exists())
+                self.assertFalse(output_file.read_bytes().startswith(b"datetime"))
+
+                result = self.run_qsv_command(f"load {output_file} - show")
+                self.assertEqual(result.stdout.strip(), '\n'.join([
+                        "datetime,col1,col2,col3,str",
+                        "2023-01-01 12:00:00,1,2,3,foo",
+                        "2023-01-01 13:00:00,4,5,6,bar",
+                        "2023-01-01 14:00:00,7,8,9,baz",
+                    ])
+                )
+                output_file.unlink()
+
+
+    def test_dump_compressed_parquet_fails(self):
+        """Test dump refuses a compression extension on Parquet output"""
+
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - dump -o /tmp/test_output.parquet.gz")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("cannot be written as gzip", result.stderr)
+
+
+    def test_dump_with_invalid_format(self):
+        """Test dump with an unsupported format fails"""
+
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - dump -o /tmp/test_output.txt --format xlsx")
+        self.assertNotEqual(result.returncode, 0)
+        self.assertIn("Unsupported dump format", result.stderr)
+
+
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
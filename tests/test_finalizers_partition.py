@@ -56,5 +56,36 @@ class TestPartition(QsvTestBase):
         
         shutil.rmtree(output_dir)
 
+
+    def test_partition_with_compression(self):
+        """Test partition writes compressed files with --compression"""
+        output_dir = Path("/tmp/test_partition_compressed")
+
+        if output_dir.exists():
+            shutil.rmtree(output_dir)
+
+        output_dir.mkdir(parents=True)
+
+        self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - partition str {output_dir} --compression zst")
+        for filename in ["foo.csv.zst", "bar.csv.zst", "baz.csv.zst"]:
+            self.assertTrue((output_dir / filename).exists())
+
+        result = self.run_qsv_command(f"load {output_dir / 'bar.csv.zst'} - show")
+        self.assertEqual(
+            result.stdout.strip(),
+            "\n".join([
+                "datetime,col1,col2,col3,str",
+                "2023-01-01 13:00:00,4,5,6,bar",
+            ])
+        )
+
+        shutil.rmtree(output_dir)
+
+    def test_partition_with_unsupported_compression(self):
+        """Test partition rejects an unknown codec"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} - partition str /tmp --compression lz4")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Unsupported compression", result.stderr)
+
 if __name__ == "__main__":
     unittest.main() 
\ No newline at end of file
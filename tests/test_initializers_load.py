@@ -26,6 +26,32 @@ class TestLoad(QsvTestBase):
             ])
         )
     
+    def test_load_zstd_bzip2_xz_files(self):
+        """Test loading zstd, bzip2 and xz compressed CSV files"""
+        for fixture in ['simple.csv.zst', 'simple.csv.bz2', 'simple.csv.xz']:
+            with self.subTest(fixture=fixture):
+                result = self.run_qsv_command(f"load {self.get_fixture_path(fixture)} - show")
+                self.assertEqual(result.returncode, 0)
+                self.assertEqual(result.stdout.strip(), '\n'.join([
+                        "datetime,col1,col2,col3,str",
+                        "2023-01-01 12:00:00,1,2,3,foo",
+                        "2023-01-01 13:00:00,4,5,6,bar",
+                        "2023-01-01 14:00:00,7,8,9,baz",
+                    ])
+                )
+
+    def test_load_zip_archive(self):
+        """Test that the CSV members of a zip archive are concatenated and other members skipped"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple_parts.zip')} - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "datetime,col1,col2,col3,str",
+                "2023-01-01 12:00:00,1,2,3,foo",
+                "2023-01-01 13:00:00,4,5,6,bar",
+                "2023-01-01 14:00:00,7,8,9,baz",
+            ])
+        )
+
     def test_load_multiple_files(self):
         """Test loading multiple CSV files"""
         result = self.run_qsv_command(f"load {self.get_fixture_path('simple.csv')} {self.get_fixture_path('simple.csv')} - show")
@@ -84,14 +110,69 @@ class TestLoad(QsvTestBase):
             ])
         )
     
+    def test_load_jsonl_file(self):
+        """Test loading a JSON Lines file"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.jsonl')} - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "datetime,col1,col2,col3,str",
+                "2023-01-01 12:00:00,1,2,3,foo",
+                "2023-01-01 13:00:00,4,5,6,bar",
+                "2023-01-01 14:00:00,7,8,9,baz",
+            ])
+        )
+
+    def test_load_jsonl_gzip_file(self):
+        """Test loading a gzip compressed JSON Lines file"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.jsonl.gz')} - isin col1 4 - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "datetime,col1,col2,col3,str",
+                "2023-01-01 13:00:00,4,5,6,bar",
+            ])
+        )
+
+    def test_load_json_array_file(self):
+        """Test loading a .json file containing an array of records"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.json')} - select col1,str - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "col1,str",
+                "1,foo",
+                "4,bar",
+                "7,baz",
+            ])
+        )
+
+    def test_load_ndjson_flattens_nested_objects(self):
+        """Test that nested objects become dotted column names and missing keys become nulls"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('nested.ndjson')} - select Event.System.EventID,Event.System.Computer,Event.EventData.DestinationPort,score - show")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "Event.System.EventID,Event.System.Computer,Event.EventData.DestinationPort,score",
+                "1,dc01,,0.5",
+                "3,ws02,443,2.0",
+                "1,ws02,,",
+            ])
+        )
+
+    def test_load_ndjson_with_chainables(self):
+        """Test that existing chainables work on flattened JSON columns"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('nested.ndjson')} - grep explorer - select Event.EventData.Image,Event.EventData.Hashes - show")
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+                "Event.EventData.Image,Event.EventData.Hashes",
+                "C:\\Windows\\explorer.exe,",
+            ])
+        )
+
+    def test_load_mixed_json_and_csv_fails(self):
+        """Test that mixing JSON and CSV inputs is rejected"""
+        result = self.run_qsv_command(f"load {self.get_fixture_path('simple.jsonl')} {self.get_fixture_path('simple.csv')} - show")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("Cannot mix JSON", result.stderr)
+
     def test_load_nonexistent_file(self):
         """Test loading a non-existent file should fail gracefully"""
         result = self.run_qsv_command(f"load non_existent_file.csv - show")
-        self.assertEqual(result.stderr.strip(), '\n'.join([
-                "Error: File not found: non_existent_file.csv",
-                "One or more files do not exist",
-            ])
-        )
+        self.assertEqual(result.stderr.strip(), "Error: file not found: non_existent_file.csv")
+        self.assertEqual(result.returncode, 4)
 
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
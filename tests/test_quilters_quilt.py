@@ -1,5 +1,7 @@
 import unittest
+import json
 import os
+import re
 import tempfile
 from test_base import QsvTestBase
 
@@ -131,8 +133,8 @@ stages:
     def test_quilt_nonexistent_file(self):
         """Test quilt execution with non-existent config file"""
         result = self.run_qsv_command("quilt nonexistent_file.yaml")
-        self.assertNotEqual(result.returncode, 0)
-        self.assertIn("Error reading config file", result.stderr)
+        self.assertEqual(result.returncode, 4)
+        self.assertIn("Error: I/O error: reading config file", result.stderr)
     
     def test_quilt_invalid_yaml(self):
         """Test quilt execution with invalid YAML"""
@@ -151,8 +153,8 @@ stages:
             f.write(invalid_yaml_content)
         
         result = self.run_qsv_command(f"quilt {temp_quilt_file}")
-        # The process may exit with 0 but should show error messages
-        self.assertIn("Error parsing", result.stderr)
+        self.assertEqual(result.returncode, 3)
+        self.assertIn("Error: failed to parse config for stage", result.stderr)
     
     def test_quilt_with_multiple_chainable_operations(self):
         """Test quilt with multiple chainable operations in sequence"""
@@ -205,5 +207,783 @@ stages:
             # If no data, at least check that header is present
             self.assertIn("col1,col2,str", result.stdout)
 
+    def test_quilt_load_jsonl(self):
+        """Test quilt load step with a JSON Lines source"""
+        quilt_content = f"""title: 'JSON Lines Load'
+stages:
+  events:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('nested.ndjson')}"
+      isin:
+        colname: Event.System.Computer
+        values:
+          - ws02
+      select:
+        colnames:
+          - Event.System.EventID
+          - Event.System.Computer
+      show:
+"""
+        temp_quilt_file = os.path.join(self.temp_dir, "jsonl_quilt.yaml")
+        with open(temp_quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {temp_quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "Event.System.EventID,Event.System.Computer",
+            "3,ws02",
+            "1,ws02",
+        ]))
+
+    def test_quilt_filter_step(self):
+        """Test quilt filter step with an expression"""
+        quilt_content = f"""title: 'Filter Step'
+stages:
+  filtered:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+      filter:
+        expr: 'col1 >= 4 and str != "baz"'
+      select:
+        colnames:
+          - col1
+          - str
+      show:
+"""
+        temp_quilt_file = os.path.join(self.temp_dir, "filter_quilt.yaml")
+        with open(temp_quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {temp_quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), '\n'.join([
+            "col1,str",
+            "4,bar",
+        ]))
+
+    def test_quilt_unknown_step(self):
+        """Test that an unknown step fails with the configuration exit code"""
+        quilt_content = f"""title: 'Unknown Step'
+stages:
+  broken:
+    type: process
+    steps:
+      load:
+        path: {self.get_fixture_path('simple.csv')}
+      frobnicate:
+        colname: col1
+"""
+        temp_quilt_file = os.path.join(self.temp_dir, "unknown_step.yaml")
+        with open(temp_quilt_file, 'w') as f:
+            f.write(quilt_content)
+
+        result = self.run_qsv_command(f"quilt {temp_quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("Unknown or unsupported step 'frobnicate'", result.stderr)
+
+    def write_params_quilt(self):
+        quilt_content = f"""title: 'Params Test'
+params:
+  path: "{self.get_fixture_path('simple.csv')}"
+  word: foo
+  rows: 5
+  other: ~
+stages:
+  filtered:
+    type: process
+    steps:
+      load:
+        path: ${{path}}
+      isin:
+        colname: str
+        values:
+          - ${{word}}
+          - ${{other}}
+      select:
+        colnames: col1,str
+      head:
+        number: ${{rows}}
+      show:
+"""
+        temp_quilt_file = os.path.join(self.temp_dir, "params.yaml")
+        with open(temp_quilt_file, 'w') as f:
+            f.write(quilt_content)
+        return temp_quilt_file
+
+    def test_quilt_params_override(self):
+        """Test params defaults with --param overrides"""
+        quilt_file = self.write_params_quilt()
+        result = self.run_qsv_command(f"quilt {quilt_file} --param other=bar --param rows=1")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "col1,str\n1,foo")
+
+        result = self.run_qsv_command(f"quilt {quilt_file} --param word=baz --param other=bar")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "col1,str\n4,bar\n7,baz")
+
+    def test_quilt_params_env(self):
+        """Test reading variables from the environment, also in params defaults"""
+        quilt_file = self.write_params_quilt()
+        with open(quilt_file) as f:
+            content = f.read().replace("other: ~", 'other: "${env:QSV_TEST_WORD}"')
+        with open(quilt_file, 'w') as f:
+            f.write(content)
+
+        os.environ["QSV_TEST_WORD"] = "bar"
+        try:
+            result = self.run_qsv_command(f"quilt {quilt_file}")
+        finally:
+            del os.environ["QSV_TEST_WORD"]
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "col1,str\n1,foo\n4,bar")
+
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("env:QSV_TEST_WORD", result.stderr)
+
+    def test_quilt_params_undefined(self):
+        """Test that undefined variables fail before running"""
+        quilt_file = self.write_params_quilt()
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("stage 'filtered': undefined variable '${other}'", result.stderr)
+
+    def write_quilt(self, name, content):
+        temp_quilt_file = os.path.join(self.temp_dir, name)
+        with open(temp_quilt_file, 'w') as f:
+            f.write(content)
+        return temp_quilt_file
+
+    def test_quilt_dependency_order(self):
+        """Test that stages run after their sources regardless of YAML order"""
+        quilt_file = self.write_quilt("order.yaml", f"""title: 'Order Test'
+stages:
+  final:
+    type: process
+    source: picked
+    steps:
+      head:
+        number: 2
+      show:
+  picked:
+    type: process
+    source: loaded
+    steps:
+      select:
+        colnames: col1,str
+  loaded:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "col1,str\n1,foo\n4,bar")
+
+    def test_quilt_depends_on_sql(self):
+        """Test depends_on for stages only referenced from a sql query"""
+        quilt_file = self.write_quilt("depends_on.yaml", f"""title: 'Depends On Test'
+stages:
+  report:
+    type: process
+    depends_on: [events]
+    steps:
+      sql:
+        query: "SELECT COUNT(*) AS n FROM events"
+      show:
+  events:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0)
+        self.assertEqual(result.stdout.strip(), "n\n3")
+
+    def test_quilt_dependency_cycle(self):
+        """Test that dependency cycles are rejected"""
+        quilt_file = self.write_quilt("cycle.yaml", """title: 'Cycle Test'
+stages:
+  a:
+    type: process
+    source: b
+  b:
+    type: process
+    source: a
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("dependency cycle", result.stderr)
+
+    def test_quilt_invalid_stages_reported_together(self):
+        """Test that dangling references and unknown stage types fail before loading data"""
+        output_file = os.path.join(self.temp_dir, "never.csv")
+        quilt_file = self.write_quilt("invalid_stages.yaml", f"""title: 'Invalid Stages'
+stages:
+  loaded:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+      dump:
+        output: "{output_file}"
+  merged:
+    type: merge
+    sources: [loaded, missing]
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("stage 'merged': unknown stage type 'merge'", result.stderr)
+        self.assertIn("stage 'merged': references undefined stage 'missing'", result.stderr)
+        self.assertFalse(os.path.exists(output_file))
+
+    def write_parallel_quilt(self, extra_stage=""):
+        simple = self.get_fixture_path('simple.csv')
+        return self.write_quilt("parallel.yaml", f"""title: 'Parallel Test'
+max_parallel: 4
+stages:
+  first:
+    type: process
+    steps:
+      load:
+        path: "{simple}"
+      head:
+        number: 1
+      show:
+  last:
+    type: process
+    steps:
+      load:
+        path: "{simple}"
+      tail:
+        number: 1
+      show:
+      dump:
+        output: "{os.path.join(self.temp_dir, 'last.csv')}"
+  names:
+    type: process
+    steps:
+      load:
+        path: "{simple}"
+      select:
+        colnames: str
+      show:
+{extra_stage}""")
+
+    def test_quilt_parallel_output_order(self):
+        """Test that independent stages print in stage order whatever the parallelism"""
+        quilt_file = self.write_parallel_quilt()
+        expected = "\n".join([
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 12:00:00,1,2,3,foo",
+            "",
+            "datetime,col1,col2,col3,str",
+            "2023-01-01 14:00:00,7,8,9,baz",
+            "",
+            "str",
+            "foo",
+            "bar",
+            "baz",
+        ])
+        for jobs in ["1", "4"]:
+            result = self.run_qsv_command(f"quilt {quilt_file} --jobs {jobs}")
+            self.assertEqual(result.returncode, 0)
+            self.assertEqual(result.stdout.strip(), expected)
+        with open(os.path.join(self.temp_dir, 'last.csv')) as f:
+            self.assertIn("baz", f.read())
+
+    def test_quilt_parallel_error(self):
+        """Test that a failing stage fails the quilt when stages run concurrently"""
+        quilt_file = self.write_parallel_quilt("""  broken:
+    type: process
+    source: names
+    steps:
+      select:
+        colnames: nonexistent
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file} --jobs 4")
+        self.assertEqual(result.returncode, 2)
+        self.assertIn("nonexistent", result.stderr)
+
+    def test_quilt_invalid_jobs(self):
+        """Test that --jobs must be a positive integer"""
+        result = self.run_qsv_command(f"quilt {self.get_fixture_path('quilt-simple.yaml')} --jobs 0")
+        self.assertEqual(result.returncode, 1)
+        self.assertIn("--jobs", result.stderr)
+
+    def test_quilt_validate(self):
+        """Test that --validate accepts a valid quilt without running it"""
+        output_file = os.path.join(self.temp_dir, "never-written.csv")
+        result = self.run_qsv_command(
+            f"quilt {self.get_fixture_path('quilt-simple.yaml')} --validate -o {output_file}"
+        )
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("is valid", result.stdout)
+        self.assertFalse(os.path.exists(output_file))
+
+    def test_quilt_validate_reports_all_problems(self):
+        """Test that --validate lists every problem with its stage and step"""
+        quilt_file = self.write_quilt("invalid.yaml", """
+title: invalid
+stages:
+  first:
+    type: process
+    steps:
+      load:
+        path: does-not-exist.csv
+      slect:
+        colnames: a
+      head:
+        number: 5
+        colour: red
+      renamecol:
+        old_name: a
+  second:
+    type: join
+    sources: [first, missing]
+    params:
+      how: inner
+      key: a
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file} --validate")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("Invalid quilt (4 problems)", result.stderr)
+        self.assertIn("stage 'first': Unknown or unsupported step 'slect' (did you mean 'select'?)", result.stderr)
+        self.assertIn("stage 'first', step 'head': unknown argument 'colour'", result.stderr)
+        self.assertIn("stage 'first', step 'renamecol': missing required argument 'new_name'", result.stderr)
+        self.assertIn("stage 'second': references undefined stage 'missing'", result.stderr)
+        # Nothing was loaded, so the missing input file is not reported
+        self.assertNotIn("does-not-exist.csv", result.stderr)
+
+    def test_quilt_run_warns_about_unknown_arguments(self):
+        """Test that a normal run only warns about arguments --validate rejects"""
+        quilt_file = self.write_quilt("unknown_argument.yaml", f"""title: 'Unknown Argument'
+stages:
+  first:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+      head:
+        number: 1
+        colour: red
+      show:
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file} --validate")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("stage 'first', step 'head': unknown argument 'colour'", result.stderr)
+
+        saved_log = os.environ.get("RUST_LOG")
+        os.environ["RUST_LOG"] = "warn"
+        try:
+            result = self.run_qsv_command(f"quilt {quilt_file}")
+        finally:
+            if saved_log is None:
+                os.environ.pop("RUST_LOG", None)
+            else:
+                os.environ["RUST_LOG"] = saved_log
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "datetime,col1,col2,col3,str\n2023-01-01 12:00:00,1,2,3,foo")
+        self.assertIn("stage 'first', step 'head': unknown argument 'colour'", result.stderr)
+
+    def test_quilt_validate_pivot_fill(self):
+        """Test that pivot fill must be a value, not a flag the run would ignore"""
+        quilt_file = self.write_quilt("pivot_fill.yaml", f"""title: 'Pivot Fill'
+stages:
+  pivoted:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('simple.csv')}"
+      pivot:
+        rows: str
+        cols: col1
+        values: col2
+        fill: true
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file} --validate")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("stage 'pivoted', step 'pivot': argument 'fill'", result.stderr)
+
+    def test_quilt_schema(self):
+        """Test that --schema prints a JSON Schema describing every step"""
+        result = self.run_qsv_command("quilt --schema")
+        self.assertEqual(result.returncode, 0)
+        schema = json.loads(result.stdout)
+        self.assertIn("stages", schema["properties"])
+        self.assertIn("groupby", result.stdout)
+
+    def write_join_sources(self):
+        events = os.path.join(self.temp_dir, "events.csv")
+        with open(events, 'w') as f:
+            f.write("time,host,event\n"
+                    "2024-01-01 10:05:00,dc01,open\n"
+                    "2024-01-01 10:20:00,dc01,delete\n"
+                    "2024-01-01 09:00:00,dc02,open\n"
+                    "2024-01-01 12:00:00,dc01,close\n")
+        logins = os.path.join(self.temp_dir, "logins.csv")
+        with open(logins, 'w') as f:
+            f.write("login_time,hostname,user\n"
+                    "2024-01-01 10:00:00,dc01,alice\n"
+                    "2024-01-01 10:15:00,dc01,bob\n"
+                    "2024-01-01 08:59:00,dc02,carol\n"
+                    "2024-01-01 11:00:00,dc02,dave\n")
+        return events, logins
+
+    def join_quilt(self, params, extra_sources=""):
+        events, logins = self.write_join_sources()
+        return self.write_quilt("join.yaml", f"""
+title: join
+stages:
+  events:
+    type: process
+    steps:
+      load:
+        path: {events}
+  logins:
+    type: process
+    steps:
+      load:
+        path: {logins}
+{extra_sources}
+  joined:
+    type: join
+    sources: [events, logins{', hosts' if extra_sources else ''}]
+    params:
+{params}
+  final:
+    type: process
+    source: joined
+    steps:
+      show:
+""")
+
+    def test_quilt_join_left_on_right_on(self):
+        """Test joining on differently named key columns with a suffix"""
+        quilt_file = self.join_quilt("""
+      how: left
+      left_on: host
+      right_on: hostname
+      suffix: _login
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        lines = result.stdout.strip().split("\n")
+        self.assertEqual(lines[0], "time,host,event,login_time,user")
+        # dc01 events match both dc01 logins
+        self.assertEqual(len(lines), 1 + 3 * 2 + 2)
+
+    def test_quilt_join_anti(self):
+        """Test anti joins keep only unmatched left rows"""
+        quilt_file = self.join_quilt("""
+      how: anti
+      left_on: [host, time]
+      right_on: [hostname, login_time]
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(len(result.stdout.strip().split("\n")), 5)
+
+    def test_quilt_join_asof(self):
+        """Test as-of joins match the nearest preceding login on the same host"""
+        quilt_file = self.join_quilt("""
+      how: asof
+      left_on: time
+      right_on: login_time
+      by_left: host
+      by_right: hostname
+      tolerance: 30m
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "time,host,event,login_time,user",
+            "2024-01-01 09:00:00,dc02,open,2024-01-01 08:59:00,carol",
+            "2024-01-01 10:05:00,dc01,open,2024-01-01 10:00:00,alice",
+            "2024-01-01 10:20:00,dc01,delete,2024-01-01 10:15:00,bob",
+            "2024-01-01 12:00:00,dc01,close,,",
+        ]))
+
+    def test_quilt_join_asof_epoch(self):
+        """Test as-of join keys are read by the shared datetime parser and unparsable ones reported"""
+        events = os.path.join(self.temp_dir, "epoch_events.csv")
+        with open(events, 'w') as f:
+            f.write("time,event\n1704103500,open\nn/a,close\n")
+        logins = os.path.join(self.temp_dir, "epoch_logins.csv")
+        with open(logins, 'w') as f:
+            f.write("login_time,user\n1704103200,alice\n1704106800,bob\n")
+        quilt_file = self.write_quilt("join_epoch.yaml", f"""
+title: join
+stages:
+  events:
+    type: process
+    steps:
+      load:
+        path: {events}
+  logins:
+    type: process
+    steps:
+      load:
+        path: {logins}
+  joined:
+    type: join
+    sources: [events, logins]
+    params:
+      how: asof
+      left_on: time
+      right_on: login_time
+      epoch: unix
+  final:
+    type: process
+    source: joined
+    steps:
+      show:
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "time,event,login_time,user",
+            "1704103500,open,1704103200,alice",
+            "n/a,close,,",
+        ]))
+        self.assertIn("join could not parse 1 distinct value(s) of column 'time'", result.stderr)
+
+    def test_quilt_join_more_than_two_sources(self):
+        """Test that further sources are joined onto the result in order"""
+        hosts = os.path.join(self.temp_dir, "hosts.csv")
+        with open(hosts, 'w') as f:
+            f.write("host,site\ndc01,tokyo\ndc02,osaka\n")
+        quilt_file = self.join_quilt("""
+      how: cross
+""", extra_sources=f"""
+  hosts:
+    type: process
+    steps:
+      load:
+        path: {hosts}
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        lines = result.stdout.strip().split("\n")
+        self.assertEqual(lines[0], "time,host,event,login_time,hostname,user,host_right,site")
+        self.assertEqual(len(lines), 1 + 4 * 4 * 2)
+
+    def test_quilt_join_invalid_params(self):
+        """Test that unsupported join types are rejected instead of becoming inner joins"""
+        quilt_file = self.join_quilt("""
+      how: sideways
+      key: host
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("unsupported join type 'sideways'", result.stderr.lower())
+
+    def concat_quilt(self, how):
+        v1 = os.path.join(self.temp_dir, "export_v1.csv")
+        with open(v1, 'w') as f:
+            f.write("time,host\n2024-01-01 10:00:00,dc01\n2024-01-01 11:00:00,dc02\n")
+        v2 = os.path.join(self.temp_dir, "export_v2.csv")
+        with open(v2, 'w') as f:
+            f.write("time,user,host\n2024-01-02 09:00:00,alice,dc03\n")
+        return self.write_quilt("concat.yaml", f"""
+title: concat
+stages:
+  v1:
+    type: process
+    steps:
+      load:
+        path: {v1}
+  v2:
+    type: process
+    steps:
+      load:
+        path: {v2}
+  merged:
+    type: concat
+    sources: [v1, v2]
+    params:
+      how: {how}
+  final:
+    type: process
+    source: merged
+    steps:
+      show:
+""")
+
+    def test_quilt_concat_diagonal(self):
+        """Test that diagonal concat unions columns by name and fills missing values"""
+        result = self.run_qsv_command(f"quilt {self.concat_quilt('diagonal')}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "time,host,user",
+            "2024-01-01 10:00:00,dc01,",
+            "2024-01-01 11:00:00,dc02,",
+            "2024-01-02 09:00:00,dc03,alice",
+        ]))
+
+    def test_quilt_concat_horizontal(self):
+        """Test that horizontal concat places columns side by side"""
+        quilt_file = self.concat_quilt("horizontal")
+        with open(quilt_file) as f:
+            content = f.read()
+        # Rename the clashing columns of the second source first
+        content = content.replace("""        path: {0}
+""".format(os.path.join(self.temp_dir, "export_v2.csv")), """        path: {0}
+      renamecol:
+        old_name: time
+        new_name: time2
+      select:
+        colnames: [time2, user]
+""".format(os.path.join(self.temp_dir, "export_v2.csv")))
+        with open(quilt_file, 'w') as f:
+            f.write(content)
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "time,host,time2,user",
+            "2024-01-01 10:00:00,dc01,2024-01-02 09:00:00,alice",
+            "2024-01-01 11:00:00,dc02,,",
+        ]))
+
+    def test_quilt_concat_horizontal_duplicate_columns(self):
+        """Test that horizontal concat names clashing columns instead of failing obscurely"""
+        result = self.run_qsv_command(f"quilt {self.concat_quilt('horizontal')}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("renamecol", result.stderr)
+
+    def test_quilt_concat_vertical_mismatch(self):
+        """Test that vertical concat of different headers suggests diagonal"""
+        result = self.run_qsv_command(f"quilt {self.concat_quilt('vertical')}")
+        self.assertEqual(result.returncode, 6)
+        self.assertIn("how: diagonal", result.stderr)
+
+    def test_quilt_timeline_week_start_alias(self):
+        """Test that the hyphenated week-start key is read like week_start"""
+        quilt_file = self.write_quilt("week_start.yaml", f"""title: 'Week Start'
+stages:
+  weekly:
+    type: process
+    steps:
+      load:
+        path: "{self.get_fixture_path('timeline_hosts.csv')}"
+      timeline:
+        time_column: time
+        interval: 1w
+        week-start: sun
+      show:
+""")
+        result = self.run_qsv_command(f"quilt {quilt_file} --validate")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        result = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "timeline_1w,count",
+            "2023-01-01 00:00:00,5",
+            "2023-01-08 00:00:00,1",
+            "2023-02-12 00:00:00,1",
+        ]))
+
+    def assert_round_trip(self, pipeline):
+        """Export a pipeline with toquilt and check the quilt prints what the pipeline prints"""
+        quilt_file = os.path.join(self.temp_dir, "exported.yaml")
+        direct = self.run_qsv_command(pipeline)
+        self.assertEqual(direct.returncode, 0, direct.stderr)
+        result = self.run_qsv_command(f"{pipeline} - toquilt {quilt_file}")
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertIn(f"qsv quilt {quilt_file}", result.stdout)
+        replayed = self.run_qsv_command(f"quilt {quilt_file}")
+        self.assertEqual(replayed.returncode, 0, replayed.stderr)
+        self.assertEqual(replayed.stdout, direct.stdout)
+        with open(quilt_file) as f:
+            return f.read()
+
+    def test_toquilt_round_trip(self):
+        """Test that an exported pipeline replays to the same output"""
+        content = self.assert_round_trip(
+            f"load {self.get_fixture_path('simple.csv')} - select col1-col3 - head 3 - head 2 - sort col1 --desc - showtable"
+        )
+        self.assertIn("title: Exported qsv pipeline", content)
+        self.assertIn("head_:", content)
+        self.assertIn("- col1\n        - col2\n        - col3", content)
+        self.assertIn(os.path.abspath(self.get_fixture_path('simple.csv')), content)
+
+    def test_toquilt_sql_table(self):
+        """Test that sql --table files are exported as load stages"""
+        simple = self.get_fixture_path('simple.csv')
+        content = self.assert_round_trip(
+            f"load {simple} - sql 'select self.col1, t.str from self join t using (col1)' --table t={simple} - show"
+        )
+        self.assertIn("  t:\n    type: process", content)
+
+    def test_toquilt_epoch(self):
+        """Test that --epoch options are exported and decoded by quilt stages"""
+        content = self.assert_round_trip(
+            f"load {self.get_fixture_path('epochs.csv')} - changetz filetime --from-tz UTC --to-tz UTC --epoch filetime - timeround webkit --unit d --epoch webkit - select event,filetime,webkit - show"
+        )
+        self.assertIn("epoch: filetime", content)
+
+    def test_toquilt_todatetime(self):
+        """Test that todatetime is exported and keeps its zone in quilt stages"""
+        content = self.assert_round_trip(
+            f"load {self.get_fixture_path('epochs.csv')} - todatetime unix_s --epoch unix_s --tz Asia/Tokyo - timeline unix_s --interval 1d - show"
+        )
+        self.assertIn("tz: Asia/Tokyo", content)
+
+    def test_toquilt_timeline_options(self):
+        """Test that timeline aggregations, series and gap filling are exported"""
+        content = self.assert_round_trip(
+            f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1w --week-start sun --sum bytes,latency --agg 'max(latency) as peak' --by host --start 2023-01-01 --end 2023-01-15 - show"
+        )
+        self.assertIn("sum: bytes,latency", content)
+        self.assertIn("week_start: sun", content)
+
+    def test_toquilt_chart(self):
+        """Test that chart options are exported and drawn by quilt stages"""
+        content = self.assert_round_trip(
+            f"load {self.get_fixture_path('timeline_hosts.csv')} - timeline time --interval 1d --by host --fill - chart count --by host --type spark --width 60 --ascii"
+        )
+        self.assertIn("type: spark", content)
+        self.assertIn("width: 60", content)
+
+    def test_toquilt_title(self):
+        """Test that --title sets the quilt title"""
+        quilt_file = os.path.join(self.temp_dir, "titled.yaml")
+        result = self.run_qsv_command(
+            f"load {self.get_fixture_path('simple.csv')} - head 1 - toquilt {quilt_file} --title 'Daily triage'"
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        with open(quilt_file) as f:
+            self.assertIn("title: Daily triage", f.read())
+        validated = self.run_qsv_command(f"quilt {quilt_file} --validate")
+        self.assertEqual(validated.returncode, 0, validated.stderr)
+
+    def test_toquilt_not_last(self):
+        """Test that toquilt must end the pipeline"""
+        quilt_file = os.path.join(self.temp_dir, "misplaced.yaml")
+        result = self.run_qsv_command(
+            f"load {self.get_fixture_path('simple.csv')} - toquilt {quilt_file} - head 1"
+        )
+        self.assertEqual(result.returncode, 1)
+        self.assertFalse(os.path.exists(quilt_file))
+
+    def test_toquilt_dump_default_path(self):
+        """Test that a dump without -o is exported with the file name the command line would use"""
+        quilt_file = os.path.join(self.temp_dir, "dump.yaml")
+        result = self.run_qsv_command(
+            f"load {self.get_fixture_path('simple.csv')} - dump --format json - toquilt {quilt_file}"
+        )
+        self.assertEqual(result.returncode, 0, result.stderr)
+        with open(quilt_file) as f:
+            content = f.read()
+        self.assertRegex(content, r"path: dump_\d{8}_\d{6}\.json")
+        self.assertNotIn("output.csv", content)
+
 if __name__ == "__main__":
     unittest.main()
\ No newline at end of file
@@ -0,0 +1,99 @@
+import unittest
+import os
+import subprocess
+import tempfile
+from test_base import QsvTestBase
+
+class TestRepl(QsvTestBase):
+
+    def run_repl(self, lines, *options):
+        """Run a REPL over simple.csv, feeding it the given lines on stdin"""
+        return subprocess.run(
+            [str(self.qsv_path), "repl", self.get_fixture_path('simple.csv'), *options],
+            input="\n".join(lines) + "\n",
+            capture_output=True,
+            text=True,
+            cwd=self.root_dir,
+        )
+
+    def test_repl_chain(self):
+        """Test that each line extends the chain built by the previous ones"""
+        result = self.run_repl(["select col1,str", "filter 'col1 > 1' - show", "head 1 - show"])
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "\n".join([
+            "shape: (3, 2)",
+            "┌──────┬─────┐",
+            "│ col1 ┆ str │",
+            "╞══════╪═════╡",
+            "│ 1    ┆ foo │",
+            "├╌╌╌╌╌╌┼╌╌╌╌╌┤",
+            "│ 4    ┆ bar │",
+            "├╌╌╌╌╌╌┼╌╌╌╌╌┤",
+            "│ 7    ┆ baz │",
+            "└──────┴─────┘",
+            "col1,str",
+            "4,bar",
+            "7,baz",
+            "",
+            "col1,str",
+            "4,bar",
+        ]))
+
+    def test_repl_undo_and_history(self):
+        """Test that undo removes the last chainable and history prints the chain"""
+        result = self.run_repl([
+            "select col1,str - show",
+            "sort col1 --desc - show",
+            "history",
+            "undo",
+            "history",
+            "head 1 - show",
+        ])
+        self.assertEqual(result.returncode, 0, result.stderr)
+        fixture = self.get_fixture_path('simple.csv')
+        self.assertIn(f"qsv load {fixture} - select col1,str - sort col1 --desc\n", result.stdout)
+        self.assertIn("Removed: sort col1 --desc\n", result.stdout)
+        self.assertIn(f"qsv load {fixture} - select col1,str\n", result.stdout)
+        self.assertTrue(result.stdout.endswith("col1,str\n1,foo\n\n"))
+
+    def test_repl_error_keeps_chain(self):
+        """Test that a failing line is reported and leaves the chain unchanged"""
+        result = self.run_repl(["head 2 - select missing - show", "undo", "undo"])
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("missing", result.stderr)
+        self.assertIn("Nothing to undo", result.stdout)
+        self.assertNotIn("Removed", result.stdout)
+
+    def test_repl_quoting(self):
+        """Test that lines are split like a shell command line"""
+        result = self.run_repl(["filter \"str == 'bar'\" - select 'col1' - show"])
+        self.assertEqual(result.returncode, 0, result.stderr)
+        self.assertEqual(result.stdout.strip(), "col1\n4")
+
+    def test_repl_unavailable_command(self):
+        """Test that commands replacing the input are rejected"""
+        result = self.run_repl([f"load {self.get_fixture_path('simple.csv')}", "exit", "headers"])
+        self.assertEqual(result.returncode, 0)
+        self.assertIn("'load' is not available in the REPL", result.stderr)
+        self.assertEqual(result.stdout, "")
+
+    def test_repl_toquilt(self):
+        """Test that the current chain can be exported as a quilt"""
+        with tempfile.TemporaryDirectory() as temp_dir:
+            quilt_file = os.path.join(temp_dir, "repl.yaml")
+            result = self.run_repl(["select col1,str", "tail 1", f"toquilt {quilt_file}"])
+            self.assertEqual(result.returncode, 0, result.stderr)
+            replayed = self.run_qsv_command(f"quilt {quilt_file}")
+            self.assertEqual(replayed.returncode, 0, replayed.stderr)
+            self.assertIn("baz", replayed.stdout)
+            self.assertNotIn("foo", replayed.stdout)
+
+    def test_repl_requires_file(self):
+        """Test that repl needs files and takes no pipeline"""
+        result = self.run_qsv_command("repl")
+        self.assertEqual(result.returncode, 1)
+        result = self.run_qsv_command(f"repl {self.get_fixture_path('simple.csv')} - head 1")
+        self.assertEqual(result.returncode, 1)
+
+if __name__ == "__main__":
+    unittest.main()